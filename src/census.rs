@@ -0,0 +1,329 @@
+use crate::i18n::{Language, Localized};
+use crate::keybindings::Action;
+use crate::objects::{Census, Identified, Object, World, census, identify};
+use crate::patterns::library_name;
+use crate::selection::Selection;
+use crate::theme::{ThemeColor, Themed, ThemedText};
+use crate::{CellStates, DEAD, GRID_SIZE, Simulation};
+use bevy::prelude::*;
+use std::fmt::Write as _;
+use std::fs;
+
+/// 普查与识别结果导出到当前工作目录下
+pub const CENSUS_FILE: &str = "lifegame_census.txt";
+pub const IDENTIFY_FILE: &str = "lifegame_identify.txt";
+
+/// 结果界面的内容：整个网格的物体普查，或当前图案（选区）的识别，失败时为错误信息
+enum Findings {
+    Census(Result<Census, String>),
+    Identify {
+        selection: bool,
+        result: Result<Identified, String>,
+    },
+}
+
+/// 最近一次普查或识别：开始时的代数、规则与结果
+#[derive(Resource, Default)]
+pub struct CensusReport {
+    generation: u64,
+    rule: String,
+    findings: Option<Findings>,
+}
+
+impl CensusReport {
+    fn file(&self) -> &'static str {
+        match self.findings {
+            Some(Findings::Identify { .. }) => IDENTIFY_FILE,
+            _ => CENSUS_FILE,
+        }
+    }
+
+    /// 面板中显示、导出到文件的文本
+    fn text(&self, language: Language) -> String {
+        let title = match &self.findings {
+            None => return String::new(),
+            Some(Findings::Census(_)) => language.trf("census.title", self.generation),
+            Some(Findings::Identify {
+                selection: true, ..
+            }) => language.trf("identify.title_selection", self.generation),
+            Some(Findings::Identify { .. }) => language.trf("identify.title", self.generation),
+        };
+        let mut text = format!("{}\n{}\n\n", title, language.trf("census.rule", &self.rule));
+        match &self.findings {
+            None => {}
+            Some(Findings::Census(Err(e))) => text += &language.trf("census.failed", e),
+            Some(Findings::Census(Ok(census))) => {
+                let _ = writeln!(
+                    text,
+                    "{}\n{}\n",
+                    language.trf("census.settled", census.generations),
+                    language.trf("census.period", census.period)
+                );
+                if census.entries.is_empty() {
+                    text += language.tr("census.empty");
+                }
+                for entry in &census.entries {
+                    let _ = match &entry.object {
+                        Some(object) => writeln!(
+                            text,
+                            "{:>4} × {}  {}{}",
+                            entry.count,
+                            object.code,
+                            object_details(object, language),
+                            object
+                                .name
+                                .map(|key| format!("  {}", language.tr(key)))
+                                .unwrap_or_default()
+                        ),
+                        None => writeln!(
+                            text,
+                            "{:>4} × {}",
+                            entry.count,
+                            language.tr("census.unknown")
+                        ),
+                    };
+                }
+            }
+            Some(Findings::Identify { result: Err(e), .. }) => {
+                text += &language.trf("identify.failed", e)
+            }
+            Some(Findings::Identify {
+                result: Ok(identified),
+                ..
+            }) => {
+                let object = &identified.object;
+                let name = match object.name {
+                    Some(key) => language.trf("identify.name", language.tr(key)),
+                    None => language.tr("identify.unnamed").to_string(),
+                };
+                let _ = writeln!(
+                    text,
+                    "{}\n{}\n{}\n{}",
+                    name,
+                    language.trf("identify.code", &object.code),
+                    object_details(object, language),
+                    language.trf("identify.transient", identified.transient)
+                );
+            }
+        }
+        text
+    }
+}
+
+/// 物体的类别、周期、位移与热度，如 "飞船 p4 (1, 1) 热度 4.0"
+pub fn object_details(object: &Object, language: Language) -> String {
+    let mut details = language.tr(object.kind.key()).to_string();
+    if object.period > 1 {
+        let _ = write!(details, " p{}", object.period);
+    }
+    if object.displacement != (0, 0) {
+        let (dx, dy) = object.displacement;
+        let _ = write!(details, " ({}, {})", dx, dy);
+    }
+    if object.heat > 0.0 {
+        let heat = format!("{:.1}", object.heat);
+        details += &format!(" {}", language.trf("census.heat", heat));
+    }
+    details
+}
+
+/// 结果界面根节点
+#[derive(Component)]
+pub struct CensusOverlay;
+
+#[derive(Component)]
+pub struct CensusText;
+
+/// 结果界面的按钮
+#[derive(Component, Clone, Copy)]
+pub enum CensusButton {
+    Export,
+    Close,
+}
+
+/// 覆盖整个窗口的普查 / 识别结果界面（与帮助界面相同的样式），默认隐藏
+pub fn census_overlay(commands: &mut Commands, font: &Handle<Font>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                z_index: ZIndex::Global(100),
+                ..default()
+            },
+            CensusOverlay,
+            Themed(ThemeColor::HelpBackdrop),
+        ))
+        .with_children(|p| {
+            p.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(12.0),
+                        padding: UiRect::all(Val::Px(20.0)),
+                        ..default()
+                    },
+                    ..default()
+                },
+                Themed(ThemeColor::HelpPanel),
+            ))
+            .with_children(|q| {
+                q.spawn((
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.0,
+                            color: Color::BLACK,
+                        },
+                    ),
+                    CensusText,
+                    ThemedText(ThemeColor::Text),
+                ));
+                q.spawn(NodeBundle::default()).with_children(|row| {
+                    for (label, button) in [
+                        ("census.export", CensusButton::Export),
+                        ("census.close", CensusButton::Close),
+                    ] {
+                        row.spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    width: Val::Px(100.0),
+                                    height: Val::Px(30.0),
+                                    margin: UiRect::horizontal(Val::Px(6.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            button,
+                            Themed(ThemeColor::SessionButton),
+                        ))
+                        .with_children(|btn| {
+                            btn.spawn((
+                                TextBundle::from_section(
+                                    "",
+                                    TextStyle {
+                                        font: font.clone(),
+                                        font_size: 14.0,
+                                        color: Color::WHITE,
+                                    },
+                                ),
+                                Localized(label),
+                                ThemedText(ThemeColor::ButtonText),
+                            ));
+                        });
+                    }
+                });
+            });
+        });
+}
+
+/// 按快捷键对当前网格做一次普查，或识别当前图案（有选区时只看选区内），并显示结果
+/// 结果显示时再按一次或点关闭收起；可把结果导出为文本
+#[allow(clippy::too_many_arguments)]
+pub fn census_system(
+    interaction_query: Query<(&Interaction, &CensusButton), Changed<Interaction>>,
+    mut actions: EventReader<Action>,
+    mut report: ResMut<CensusReport>,
+    cell_states: Res<CellStates>,
+    sim: Res<Simulation>,
+    selection: Res<Selection>,
+    language: Res<Language>,
+    mut overlay: Query<&mut Style, With<CensusOverlay>>,
+    mut text: Query<&mut Text, With<CensusText>>,
+) {
+    let Ok(mut style) = overlay.get_single_mut() else {
+        return;
+    };
+    for action in actions.read() {
+        if !matches!(action, Action::Census | Action::Identify) {
+            continue;
+        }
+        if style.display != Display::None {
+            style.display = Display::None;
+            continue;
+        }
+        let world = World {
+            rule: &sim.rule,
+            neighborhood: &sim.neighborhood,
+            topology: sim.topology,
+        };
+        let findings = if *action == Action::Census {
+            let result = census(&cell_states.states, &world);
+            match &result {
+                Ok(c) => info!(
+                    "普查：{} 代后稳定，周期 {}，共 {} 类物体",
+                    c.generations,
+                    c.period,
+                    c.entries.len()
+                ),
+                Err(e) => warn!("普查失败: {}", e),
+            }
+            Findings::Census(result)
+        } else {
+            let (min, max) = selection
+                .rect()
+                .unwrap_or((IVec2::ZERO, IVec2::splat(GRID_SIZE as i32 - 1)));
+            let mut cells = Vec::new();
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    if cell_states.states[y as usize][x as usize] != DEAD {
+                        cells.push((x, y));
+                    }
+                }
+            }
+            // 示例图案的名称优先于内置目录中的名称
+            let result = identify(&cells, &world).map(|mut identified| {
+                let object = &mut identified.object;
+                object.name = library_name(&object.code, &world).or(object.name);
+                identified
+            });
+            match &result {
+                Ok(i) => info!("识别：{}，进入周期前 {} 代", i.object.code, i.transient),
+                Err(e) => warn!("识别失败: {}", e),
+            }
+            Findings::Identify {
+                selection: selection.rect().is_some(),
+                result,
+            }
+        };
+        *report = CensusReport {
+            generation: sim.generation,
+            rule: sim.rule.to_string(),
+            findings: Some(findings),
+        };
+        style.display = Display::Flex;
+    }
+
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            CensusButton::Close => style.display = Display::None,
+            CensusButton::Export => {
+                let path = report.file();
+                match fs::write(path, report.text(*language)) {
+                    Ok(()) => info!("已导出结果到 {}", path),
+                    Err(e) => error!("导出结果失败: {}: {}", path, e),
+                }
+            }
+        }
+    }
+
+    if report.is_changed() || language.is_changed() {
+        for mut text in &mut text {
+            text.sections[0].value = report.text(*language);
+        }
+    }
+}
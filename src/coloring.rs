@@ -0,0 +1,265 @@
+use crate::draw::DrawState;
+use crate::evolution::Rule;
+use crate::heatmap::Heatmap;
+use crate::i18n::Language;
+use crate::keybindings::Action;
+use crate::lenia::Lenia;
+use crate::theme::{Theme, ThemeColor, Themed, ThemedText};
+use crate::{ALIVE, Cell, CellStates, Simulation};
+use bevy::color::Mix;
+use bevy::prelude::*;
+
+/// 存活超过该代数即视为稳定细胞，使用 stable 颜色
+const MATURE_AGE: u32 = 10;
+/// 死亡后残影持续的代数
+const GHOST_GENERATIONS: u32 = 4;
+
+/// 热力图叠加在细胞颜色上的最大不透明度，热度越高越不透明
+const HEATMAP_OPACITY: f32 = 0.7;
+
+/// 按年龄上色的调色板
+pub struct AgePalette {
+    // i18n 字符串表中的 key
+    pub name: &'static str,
+    // 刚出生（存活第 1 代）
+    pub newborn: Color,
+    // 存活第 2 代，逐渐过渡到 stable
+    pub young: Color,
+    pub stable: Color,
+    // 刚死亡的残影，逐渐淡出到主题的死细胞颜色
+    pub ghost: Color,
+}
+
+/// 预置调色板
+pub const PALETTES: &[AgePalette] = &[
+    AgePalette {
+        name: "palette.fire",
+        newborn: Color::srgb(1.0, 0.85, 0.2),
+        young: Color::srgb(0.95, 0.45, 0.1),
+        stable: Color::srgb(0.55, 0.05, 0.05),
+        ghost: Color::srgb(0.75, 0.75, 0.85),
+    },
+    AgePalette {
+        name: "palette.ocean",
+        newborn: Color::srgb(0.3, 0.95, 0.8),
+        young: Color::srgb(0.1, 0.6, 0.85),
+        stable: Color::srgb(0.05, 0.15, 0.45),
+        ghost: Color::srgb(0.85, 0.8, 0.65),
+    },
+    AgePalette {
+        name: "palette.forest",
+        newborn: Color::srgb(0.7, 0.95, 0.3),
+        young: Color::srgb(0.3, 0.7, 0.2),
+        stable: Color::srgb(0.05, 0.3, 0.1),
+        ghost: Color::srgb(0.85, 0.75, 0.75),
+    },
+];
+
+/// 网格着色方式
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    // 活细胞黑色，死细胞白色
+    #[default]
+    Binary,
+    // 按年龄上色，参数为 PALETTES 中的下标
+    Age(usize),
+}
+
+impl ColorMode {
+    /// 黑白 → 各个调色板 → 黑白 循环切换
+    fn next(self) -> Self {
+        match self {
+            ColorMode::Binary => ColorMode::Age(0),
+            ColorMode::Age(i) if i + 1 < PALETTES.len() => ColorMode::Age(i + 1),
+            ColorMode::Age(_) => ColorMode::Binary,
+        }
+    }
+
+    fn label(self, language: Language) -> String {
+        let name = match self {
+            ColorMode::Binary => "color_mode.binary",
+            ColorMode::Age(i) => PALETTES[i].name,
+        };
+        language.trf("label.color_mode", language.tr(name))
+    }
+}
+
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    LinearRgba::from(a).mix(&LinearRgba::from(b), t).into()
+}
+
+/// 按年龄计算颜色：alive 时 age 为已存活代数，死亡时为距离死亡的代数
+pub fn age_color(palette: &AgePalette, dead: Color, alive: bool, age: u32) -> Color {
+    if alive {
+        match age {
+            0 | 1 => palette.newborn,
+            age if age >= MATURE_AGE => palette.stable,
+            age => {
+                let t = (age - 2) as f32 / (MATURE_AGE - 2) as f32;
+                mix(palette.young, palette.stable, t)
+            }
+        }
+    } else if (1..=GHOST_GENERATIONS).contains(&age) {
+        let t = (age - 1) as f32 / GHOST_GENERATIONS as f32;
+        mix(palette.ghost, dead, t)
+    } else {
+        dead
+    }
+}
+
+/// Generations 规则中衰亡状态的颜色：第一个衰亡状态为 dying，之后逐步淡出到 dead
+pub fn decay_color(dying: Color, dead: Color, state: u8, states: u8) -> Color {
+    let steps = states.saturating_sub(2).max(1);
+    let t = (state.saturating_sub(2) as f32 / steps as f32).min(1.0);
+    mix(dying, dead, t)
+}
+
+/// 连续模式的渐变色：0 为主题的死细胞颜色，随数值增大依次经过各个颜色
+const GRADIENT: [Color; 4] = [
+    Color::srgb(0.3, 0.05, 0.45),
+    Color::srgb(0.05, 0.45, 0.75),
+    Color::srgb(0.35, 0.8, 0.4),
+    Color::srgb(1.0, 0.9, 0.2),
+];
+
+/// 连续状态 value ∈ [0, 1] 的颜色
+pub fn gradient_color(dead: Color, value: f32) -> Color {
+    let stops = GRADIENT.len() as f32;
+    let t = value.clamp(0.0, 1.0) * stops;
+    let i = (t.floor() as usize).min(GRADIENT.len() - 1);
+    let from = if i == 0 { dead } else { GRADIENT[i - 1] };
+    mix(from, GRADIENT[i], t - i as f32)
+}
+
+/// 配色切换按钮
+#[derive(Component)]
+pub struct ColorModeButton;
+
+#[derive(Component)]
+pub struct ColorModeText;
+
+pub fn color_mode_ui(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(140.0),
+                    height: Val::Px(40.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            ColorModeButton,
+            Themed(ThemeColor::ModeButton),
+        ))
+        .with_children(|r| {
+            r.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 18.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                ColorModeText,
+                ThemedText(ThemeColor::ButtonText),
+            ));
+        });
+}
+
+/// 点击按钮或按快捷键循环切换配色；语言变化时刷新按钮文字
+pub fn color_mode_system(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<ColorModeButton>)>,
+    mut actions: EventReader<Action>,
+    mut mode: ResMut<ColorMode>,
+    language: Res<Language>,
+    mut text: Query<&mut Text, With<ColorModeText>>,
+) {
+    let pressed = interaction_query
+        .iter()
+        .filter(|i| **i == Interaction::Pressed)
+        .count();
+    let cycles = pressed
+        + actions
+            .read()
+            .filter(|a| **a == Action::CycleColorMode)
+            .count();
+    for _ in 0..cycles {
+        *mode = mode.next();
+    }
+    if cycles > 0 {
+        info!("{}", mode.label(*language));
+    }
+
+    if cycles > 0 || language.is_changed() {
+        for mut text in &mut text {
+            text.sections[0].value = mode.label(*language);
+        }
+    }
+}
+
+/// 根据细胞状态、年龄、着色方式、主题与绘图预览刷新所有格子的颜色
+/// 衰亡状态在黑白模式下用主题的 cell_dying 色，按年龄模式下用调色板的残影色
+/// 连续模式下按连续状态取渐变色，热力图打开时把热度的渐变色半透明地叠加在上面
+#[allow(clippy::too_many_arguments)]
+pub fn render_cells_system(
+    cell_states: Res<CellStates>,
+    sim: Res<Simulation>,
+    mode: Res<ColorMode>,
+    theme: Res<Theme>,
+    draw: Res<DrawState>,
+    lenia: Res<Lenia>,
+    heatmap: Res<Heatmap>,
+    mut cells: Query<(&Cell, &mut BackgroundColor)>,
+) {
+    if !cell_states.is_changed()
+        && !sim.is_changed()
+        && !mode.is_changed()
+        && !theme.is_changed()
+        && !draw.is_changed()
+        && !lenia.is_changed()
+        && !heatmap.is_changed()
+    {
+        return;
+    }
+    let preview = draw.preview(&theme);
+    let dead = theme.cell_color(false);
+    let dying = match *mode {
+        ColorMode::Binary => theme.color(ThemeColor::CellDying),
+        ColorMode::Age(i) => PALETTES[i].ghost,
+    };
+
+    // 规则文件的 @COLORS 优先于主题与调色板
+    let table_color = |state: u8| match &sim.rule {
+        Rule::Table(table) => table.color(state),
+        _ => None,
+    };
+
+    for (cell, mut color) in &mut cells {
+        let (x, y) = (cell.x, cell.y);
+        let state = cell_states.states[y][x];
+        let alive = state == ALIVE;
+        let target = match (&preview, *mode) {
+            (Some((mask, preview_color)), _) if mask[y][x] => *preview_color,
+            _ if lenia.enabled => gradient_color(dead, lenia.field[y][x]),
+            _ if let Some([r, g, b]) = table_color(state) => Color::srgb_u8(r, g, b),
+            _ if state > ALIVE => decay_color(dying, dead, state, sim.rule.states()),
+            (_, ColorMode::Binary) => theme.cell_color(alive),
+            (_, ColorMode::Age(i)) => age_color(&PALETTES[i], dead, alive, cell_states.ages[y][x]),
+        };
+        let target = match &preview {
+            Some((mask, _)) if mask[y][x] => target,
+            _ if heatmap.enabled() => {
+                let heat = heatmap.heat[y][x].clamp(0.0, 1.0);
+                mix(target, gradient_color(dead, heat), heat * HEATMAP_OPACITY)
+            }
+            _ => target,
+        };
+        if color.0 != target {
+            color.0 = target;
+        }
+    }
+}
@@ -0,0 +1,524 @@
+use crate::draw::BlocksGrid;
+use crate::evolution::{Topology, next_generation};
+use crate::export::{ExportPalette, RenderOptions, render_cells, rgba};
+use crate::i18n::{Language, Localized};
+use crate::keybindings::Action;
+use crate::lenia::Lenia;
+use crate::metrics::Metrics;
+use crate::rules::RuleLibrary;
+use crate::theme::{Theme, ThemeColor, Themed, ThemedText};
+use crate::{
+    ALIVE, CellStates, GRID_SIZE, Grid, History, Simulation, clamp_states, set_cell_state,
+    step_generation,
+};
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::ui::FocusPolicy;
+
+/// 对比视图中每格的像素
+const COMPARE_SCALE: u32 = 10;
+
+/// 对比视图中的第二个模拟（B）：与主模拟（A）同一起点，规则与边界可以不同
+/// A 每演化一代 B 也演化一代，A 在同一代里的编辑照搬到 B
+#[derive(Resource, Default)]
+pub struct Comparison {
+    enabled: bool,
+    sim: Simulation,
+    history: History,
+    cell_states: CellStates,
+    metrics: Metrics,
+    // A 上次同步时的代数与网格，用来区分演化、编辑与跳转
+    last: Option<(u64, Grid)>,
+    // 两侧图像，按 A、B 排列
+    images: [Option<Handle<Image>>; 2],
+}
+
+impl Comparison {
+    /// 从 A 的当前网格重新开始，B 的规则没有的状态按存活处理
+    fn reseed(&mut self, sim: &Simulation, cell_states: &CellStates) {
+        self.cell_states.states = cell_states.states;
+        self.cell_states.ages = cell_states.ages;
+        clamp_states(&mut self.cell_states, self.sim.rule.states());
+        self.sim.generation = sim.generation;
+        self.history.generations.clear();
+        self.metrics.samples.clear();
+        self.last = Some((sim.generation, cell_states.states));
+    }
+
+    /// 跟上 A：A 演化了几代 B 就演化几代，同一代里改动的格子照搬到 B
+    /// 其余变化（清空、撤销、打开会话、拖动时间轴等）从 A 的网格重新开始；返回 B 是否变化
+    fn follow(&mut self, sim: &Simulation, history: &History, cell_states: &CellStates) -> bool {
+        let Some((generation, last)) = self.last else {
+            self.reseed(sim, cell_states);
+            return true;
+        };
+        if generation == sim.generation && last == cell_states.states {
+            return false;
+        }
+
+        // 演化：历史中 steps 代之前是上次的网格，且最后一代确实由演化得到（没有同时编辑）
+        let steps = sim.generation.saturating_sub(generation) as usize;
+        let past = &history.generations;
+        let stepped = steps > 0
+            && steps <= past.len()
+            && past[past.len() - steps] == last
+            && past.back().is_some_and(|grid| {
+                next_generation(grid, &sim.rule, &sim.neighborhood, sim.topology)
+                    == cell_states.states
+            });
+        if stepped {
+            for _ in 0..steps {
+                step_generation(
+                    &mut self.sim,
+                    &mut self.history,
+                    &mut self.cell_states,
+                    &mut self.metrics,
+                    None,
+                );
+            }
+        } else if generation == sim.generation {
+            let states = self.sim.rule.states();
+            for (y, row) in cell_states.states.iter().enumerate() {
+                for (x, &state) in row.iter().enumerate() {
+                    if last[y][x] != state {
+                        let state = if state >= states { ALIVE } else { state };
+                        set_cell_state(&mut self.cell_states, x, y, state);
+                    }
+                }
+            }
+        } else {
+            self.reseed(sim, cell_states);
+            return true;
+        }
+        self.last = Some((sim.generation, cell_states.states));
+        true
+    }
+}
+
+fn topology_key(topology: Topology) -> &'static str {
+    match topology {
+        Topology::Bounded => "compare.bounded",
+        Topology::Torus => "compare.torus",
+    }
+}
+
+fn population(grid: &Grid) -> usize {
+    grid.iter().flatten().filter(|s| **s == ALIVE).count()
+}
+
+/// 对比视图根节点
+#[derive(Component)]
+pub struct ComparePanel;
+
+/// 一侧的网格图像：0 为 A，1 为 B
+#[derive(Component)]
+pub struct CompareImage(usize);
+
+/// 一侧图像上方的规则、边界与人口
+#[derive(Component)]
+pub struct CompareLabel(usize);
+
+#[derive(Component)]
+pub struct CompareDiffText;
+
+/// 对比视图的按钮
+#[derive(Component, Clone, Copy)]
+pub enum CompareButton {
+    Rule,
+    Topology,
+    Close,
+}
+
+/// 盖住整个网格外框的对比视图：左右两侧的网格、不同格子的数目与图例、B 的设置按钮，默认隐藏
+pub fn compare_panel(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    let text = |size: f32| TextStyle {
+        font: font.clone(),
+        font_size: size,
+        color: Color::BLACK,
+    };
+    let side = GRID_SIZE as f32 * COMPARE_SCALE as f32 + 1.0;
+    parent
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                focus_policy: FocusPolicy::Block,
+                ..default()
+            },
+            Interaction::default(),
+            ComparePanel,
+            BlocksGrid,
+            Themed(ThemeColor::Panel),
+        ))
+        .with_children(|panel| {
+            panel
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(20.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    for index in 0..2 {
+                        row.spawn(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                align_items: AlignItems::Center,
+                                row_gap: Val::Px(6.0),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|column| {
+                            column.spawn((
+                                TextBundle::from_section("", text(16.0)),
+                                CompareLabel(index),
+                                ThemedText(ThemeColor::Text),
+                            ));
+                            column.spawn((
+                                ImageBundle {
+                                    style: Style {
+                                        width: Val::Px(side),
+                                        height: Val::Px(side),
+                                        ..default()
+                                    },
+                                    ..default()
+                                },
+                                CompareImage(index),
+                            ));
+                        });
+                    }
+                });
+            panel.spawn((
+                TextBundle::from_section("", text(16.0)),
+                CompareDiffText,
+                ThemedText(ThemeColor::Text),
+            ));
+            panel
+                .spawn(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(6.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|legend| {
+                    for (color, label) in [
+                        (ThemeColor::DiffHere, "compare.only_here"),
+                        (ThemeColor::DiffThere, "compare.only_there"),
+                    ] {
+                        legend.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    width: Val::Px(14.0),
+                                    height: Val::Px(14.0),
+                                    margin: UiRect::left(Val::Px(10.0)),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            Themed(color),
+                        ));
+                        legend.spawn((
+                            TextBundle::from_section("", text(14.0)),
+                            Localized(label),
+                            ThemedText(ThemeColor::Text),
+                        ));
+                    }
+                });
+            panel.spawn(NodeBundle::default()).with_children(|row| {
+                for (label, button) in [
+                    ("compare.rule", CompareButton::Rule),
+                    ("compare.topology", CompareButton::Topology),
+                    ("compare.close", CompareButton::Close),
+                ] {
+                    row.spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(110.0),
+                                height: Val::Px(30.0),
+                                margin: UiRect::horizontal(Val::Px(6.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        button,
+                        Themed(ThemeColor::SessionButton),
+                    ))
+                    .with_children(|btn| {
+                        btn.spawn((
+                            TextBundle::from_section(
+                                "",
+                                TextStyle {
+                                    font: font.clone(),
+                                    font_size: 14.0,
+                                    color: Color::WHITE,
+                                },
+                            ),
+                            Localized(label),
+                            ThemedText(ThemeColor::ButtonText),
+                        ));
+                    });
+                }
+            });
+        });
+}
+
+/// 把一侧的网格画成图像：只在这一侧存活、只在另一侧存活的格子用对比色，其余按状态上色
+fn render_side(grid: &Grid, other: &Grid, palette: &ExportPalette, theme: &Theme) -> Image {
+    let here = rgba(theme.color(ThemeColor::DiffHere));
+    let there = rgba(theme.color(ThemeColor::DiffThere));
+    let options = RenderOptions {
+        scale: COMPARE_SCALE,
+        grid_lines: true,
+        major_lines: true,
+    };
+    let max = IVec2::splat(GRID_SIZE as i32 - 1);
+    let frame = render_cells(IVec2::ZERO, max, options, palette, |x, y| {
+        match (grid[y][x] == ALIVE, other[y][x] == ALIVE) {
+            (true, false) => here,
+            (false, true) => there,
+            _ => palette.state(grid[y][x]),
+        }
+    });
+    Image::new(
+        Extent3d {
+            width: frame.width,
+            height: frame.height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        frame.pixels,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    )
+}
+
+/// 按快捷键打开或关闭对比视图；打开时 B 沿用 A 的规则、邻域与边界，可用按钮另换规则或边界
+/// 打开期间 B 跟随 A 演化，两侧图像与文字随之刷新；连续模式下不能对比
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn compare_system(
+    interaction_query: Query<(&Interaction, &CompareButton), Changed<Interaction>>,
+    mut actions: EventReader<Action>,
+    mut comparison: ResMut<Comparison>,
+    sim: Res<Simulation>,
+    history: Res<History>,
+    cell_states: Res<CellStates>,
+    library: Res<RuleLibrary>,
+    lenia: Res<Lenia>,
+    theme: Res<Theme>,
+    language: Res<Language>,
+    mut images: ResMut<Assets<Image>>,
+    mut panel: Query<&mut Style, With<ComparePanel>>,
+    mut views: Query<(&CompareImage, &mut UiImage)>,
+    mut labels: Query<(&CompareLabel, &mut Text), Without<CompareDiffText>>,
+    mut diff_text: Query<&mut Text, (With<CompareDiffText>, Without<CompareLabel>)>,
+) {
+    let Ok(mut style) = panel.get_single_mut() else {
+        return;
+    };
+    let mut enabled = comparison.enabled;
+    for action in actions.read() {
+        if *action == Action::ToggleCompare {
+            enabled = !enabled;
+        }
+    }
+    let mut reseed = false;
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            CompareButton::Close => enabled = false,
+            CompareButton::Rule => {
+                comparison.sim.rule = library.next(&comparison.sim.rule);
+                info!("对比视图 B 切换规则: {}", comparison.sim.rule);
+                reseed = true;
+            }
+            CompareButton::Topology => {
+                comparison.sim.topology = match comparison.sim.topology {
+                    Topology::Bounded => Topology::Torus,
+                    Topology::Torus => Topology::Bounded,
+                };
+                info!("对比视图 B 切换边界: {}", comparison.sim.topology.name());
+                reseed = true;
+            }
+        }
+    }
+    if enabled && lenia.enabled {
+        if !comparison.enabled {
+            warn!("连续模式下不能打开对比视图");
+        }
+        enabled = false;
+    }
+
+    if enabled != comparison.enabled {
+        comparison.enabled = enabled;
+        style.display = if enabled {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if enabled {
+            comparison.sim.rule = sim.rule.clone();
+            comparison.sim.topology = sim.topology;
+            reseed = true;
+            info!("打开对比视图");
+        }
+    }
+    if !comparison.enabled {
+        return;
+    }
+
+    // B 始终使用与 A 相同的邻域
+    if comparison.sim.neighborhood != sim.neighborhood {
+        comparison.sim.neighborhood = sim.neighborhood.clone();
+        reseed = true;
+    }
+    let changed = if reseed {
+        comparison.reseed(&sim, &cell_states);
+        true
+    } else {
+        comparison.follow(&sim, &history, &cell_states)
+    };
+
+    let mut handles = comparison.images.clone();
+    let grids = [cell_states.states, comparison.cell_states.states];
+    let sims = [&*sim, &comparison.sim];
+    if changed || theme.is_changed() {
+        for (view, mut ui_image) in &mut views {
+            let side = view.0;
+            let palette = ExportPalette::new(&theme, &sims[side].rule);
+            let image = render_side(&grids[side], &grids[1 - side], &palette, &theme);
+            let handle = match &handles[side] {
+                Some(handle) => {
+                    images.insert(handle, image);
+                    handle.clone()
+                }
+                None => images.add(image),
+            };
+            ui_image.texture = handle.clone();
+            handles[side] = Some(handle);
+        }
+    }
+
+    if changed || language.is_changed() {
+        for (label, mut text) in &mut labels {
+            let sim = sims[label.0];
+            text.sections[0].value = format!(
+                "{}  {}  {}\n{}",
+                ["A", "B"][label.0],
+                library.label(&sim.rule, *language),
+                language.tr(topology_key(sim.topology)),
+                language.trf("compare.population", population(&grids[label.0]))
+            );
+        }
+        let differ = grids[0]
+            .iter()
+            .flatten()
+            .zip(grids[1].iter().flatten())
+            .filter(|(a, b)| (**a == ALIVE) != (**b == ALIVE))
+            .count();
+        for mut text in &mut diff_text {
+            text.sections[0].value = language.trf("compare.diff", differ);
+        }
+    }
+    comparison.images = handles;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evolution::Rule;
+
+    /// 主模拟（A）的状态
+    #[derive(Default)]
+    struct Main {
+        sim: Simulation,
+        history: History,
+        cell_states: CellStates,
+        metrics: Metrics,
+    }
+
+    impl Main {
+        fn step(&mut self) {
+            step_generation(
+                &mut self.sim,
+                &mut self.history,
+                &mut self.cell_states,
+                &mut self.metrics,
+                None,
+            );
+        }
+    }
+
+    fn follow(comparison: &mut Comparison, main: &Main) -> bool {
+        comparison.follow(&main.sim, &main.history, &main.cell_states)
+    }
+
+    #[test]
+    fn follow_steps_edits_and_jumps() {
+        let mut main = Main::default();
+        for x in 1..4 {
+            set_cell_state(&mut main.cell_states, x, 2, ALIVE);
+        }
+        let mut comparison = Comparison::default();
+        assert!(follow(&mut comparison, &main));
+        assert!(!follow(&mut comparison, &main));
+
+        // 演化：B 按自己的规则演化同样的代数
+        main.step();
+        main.step();
+        assert!(follow(&mut comparison, &main));
+        assert_eq!(comparison.sim.generation, 2);
+        assert_eq!(comparison.history.generations.len(), 2);
+        assert_eq!(comparison.cell_states.states, main.cell_states.states);
+
+        // 同一代里的编辑照搬到 B，B 自己的历史保留
+        set_cell_state(&mut main.cell_states, 10, 10, ALIVE);
+        assert!(follow(&mut comparison, &main));
+        assert_eq!(comparison.cell_states.states[10][10], ALIVE);
+        assert_eq!(comparison.history.generations.len(), 2);
+
+        // 演化同时编辑、或跳到别的代，都从 A 的网格重新开始
+        main.step();
+        set_cell_state(&mut main.cell_states, 20, 20, ALIVE);
+        assert!(follow(&mut comparison, &main));
+        assert_eq!(comparison.sim.generation, 3);
+        assert!(comparison.history.generations.is_empty());
+        assert_eq!(comparison.cell_states.states, main.cell_states.states);
+
+        main.sim.generation = 40;
+        assert!(follow(&mut comparison, &main));
+        assert_eq!(comparison.sim.generation, 40);
+    }
+
+    #[test]
+    fn states_clamped_to_rule() {
+        // A 为多状态规则，B 为康威规则：B 没有的衰减状态按存活处理
+        let mut main = Main::default();
+        main.sim.rule = Rule::parse("B2/S/C4").unwrap();
+        set_cell_state(&mut main.cell_states, 1, 1, 2);
+        let mut comparison = Comparison::default();
+        follow(&mut comparison, &main);
+        assert_eq!(comparison.cell_states.states[1][1], ALIVE);
+
+        set_cell_state(&mut main.cell_states, 5, 5, 3);
+        follow(&mut comparison, &main);
+        assert_eq!(comparison.cell_states.states[5][5], ALIVE);
+    }
+}
@@ -0,0 +1,443 @@
+use crate::edit::CellEdit;
+use crate::i18n::Localized;
+use crate::theme::{Theme, ThemeColor, ThemedText};
+use crate::{ALIVE, CellStates, DEAD, GRID_SIZE, GridCamera, GridViewport};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+/// 当前绘图工具
+#[derive(Resource, Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DrawTool {
+    #[default]
+    Pencil,
+    Line,
+    Rect,
+    FilledRect,
+    Ellipse,
+    // 框选区域，见 selection.rs
+    Select,
+}
+
+/// 一次拖拽：从按下到松开
+struct Stroke {
+    // 拖拽起点与上一帧所在格子
+    start: IVec2,
+    last: IVec2,
+    // true 为绘制，false 为擦除（由起点格子翻转后的状态决定）
+    paint: bool,
+    button: MouseButton,
+    // 同一笔画产生的编辑合并为一次撤销
+    group: u32,
+}
+
+#[derive(Resource, Default)]
+pub struct DrawState {
+    stroke: Option<Stroke>,
+    next_group: u32,
+    // 形状工具拖拽中的预览格子
+    preview: Vec<IVec2>,
+}
+
+impl DrawState {
+    /// 形状预览：预览中的格子用主题的绘制 / 擦除预览色显示
+    pub fn preview(&self, theme: &Theme) -> Option<([[bool; GRID_SIZE]; GRID_SIZE], Color)> {
+        if self.preview.is_empty() {
+            return None;
+        }
+        let paint = self.stroke.as_ref().is_some_and(|s| s.paint);
+        let color = theme.color(if paint {
+            ThemeColor::PreviewPaint
+        } else {
+            ThemeColor::PreviewErase
+        });
+
+        let mut mask = [[false; GRID_SIZE]; GRID_SIZE];
+        for p in self.preview.iter().filter(|p| in_grid(**p)) {
+            mask[p.y as usize][p.x as usize] = true;
+        }
+        Some((mask, color))
+    }
+}
+
+/// 光标所在格子（可能在网格之外，调用方自行判断）
+pub fn cursor_cell(
+    window: &Window,
+    viewport: (&Node, &GlobalTransform),
+    camera: &GridCamera,
+) -> Option<IVec2> {
+    let cursor = window.cursor_position()?;
+    let (node, transform) = viewport;
+    let top_left = transform.translation().truncate() - node.size() / 2.0;
+    let local = (cursor - top_left - camera.offset) / camera.cell_size();
+    let y = local.y.floor();
+    // 六边形显示时奇数行向右错开半格
+    let shift = if camera.hex && (y as i32).rem_euclid(2) == 1 {
+        0.5
+    } else {
+        0.0
+    };
+    Some(IVec2::new((local.x - shift).floor() as i32, y as i32))
+}
+
+fn in_grid(p: IVec2) -> bool {
+    p.x >= 0 && p.y >= 0 && (p.x as usize) < GRID_SIZE && (p.y as usize) < GRID_SIZE
+}
+
+/// Bresenham 直线，包含两端点
+pub fn line(a: IVec2, b: IVec2) -> Vec<IVec2> {
+    let mut points = Vec::new();
+    let (dx, dy) = ((b.x - a.x).abs(), -(b.y - a.y).abs());
+    let (sx, sy) = ((b.x - a.x).signum(), (b.y - a.y).signum());
+    let (mut x, mut y) = (a.x, a.y);
+    let mut err = dx + dy;
+    loop {
+        points.push(IVec2::new(x, y));
+        if x == b.x && y == b.y {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+    points
+}
+
+fn rect(a: IVec2, b: IVec2, filled: bool) -> Vec<IVec2> {
+    let (min, max) = (a.min(b), a.max(b));
+    let mut points = Vec::new();
+    for y in min.y..=max.y {
+        for x in min.x..=max.x {
+            if filled || x == min.x || x == max.x || y == min.y || y == max.y {
+                points.push(IVec2::new(x, y));
+            }
+        }
+    }
+    points
+}
+
+/// 内切于 a、b 所围矩形的椭圆轮廓（Zingl 的中点椭圆算法）
+fn ellipse(a: IVec2, b: IVec2) -> Vec<IVec2> {
+    // 退化为一行或一列时直接画直线
+    if a.x == b.x || a.y == b.y {
+        return line(a, b);
+    }
+    let mut points = Vec::new();
+    let (mut x0, mut y0, mut x1, mut y1) = (a.x as i64, a.y as i64, b.x as i64, b.y as i64);
+    let mut plot = |x: i64, y: i64| points.push(IVec2::new(x as i32, y as i32));
+
+    let mut a = (x1 - x0).abs();
+    let b = (y1 - y0).abs();
+    let mut b1 = b & 1;
+    let mut dx = 4 * (1 - a) * b * b;
+    let mut dy = 4 * (b1 + 1) * a * a;
+    let mut err = dx + dy + b1 * a * a;
+
+    if x0 > x1 {
+        x0 = x1;
+        x1 += a;
+    }
+    if y0 > y1 {
+        y0 = y1;
+    }
+    y0 += (b + 1) / 2;
+    y1 = y0 - b1;
+    a = 8 * a * a;
+    b1 = 8 * b * b;
+
+    loop {
+        plot(x1, y0);
+        plot(x0, y0);
+        plot(x0, y1);
+        plot(x1, y1);
+        let e2 = 2 * err;
+        if e2 <= dy {
+            y0 += 1;
+            y1 -= 1;
+            dy += a;
+            err += dy;
+        }
+        if e2 >= dx || 2 * err > dy {
+            x0 += 1;
+            x1 -= 1;
+            dx += b1;
+            err += dx;
+        }
+        if x0 > x1 {
+            break;
+        }
+    }
+
+    // 扁平椭圆（宽度为 1）提前结束时补齐两端
+    while y0 - y1 < b {
+        plot(x0 - 1, y0);
+        plot(x1 + 1, y0);
+        y0 += 1;
+        plot(x0 - 1, y1);
+        plot(x1 + 1, y1);
+        y1 -= 1;
+    }
+
+    points
+}
+
+fn shape(tool: DrawTool, a: IVec2, b: IVec2) -> Vec<IVec2> {
+    match tool {
+        DrawTool::Pencil | DrawTool::Line => line(a, b),
+        DrawTool::Rect => rect(a, b, false),
+        DrawTool::FilledRect => rect(a, b, true),
+        DrawTool::Ellipse => ellipse(a, b),
+        DrawTool::Select => Vec::new(),
+    }
+}
+
+fn to_edit(points: &[IVec2], paint: bool, group: u32) -> CellEdit {
+    let cells = points
+        .iter()
+        .filter(|p| in_grid(**p))
+        .map(|p| (p.x as usize, p.y as usize, if paint { ALIVE } else { DEAD }))
+        .collect();
+    CellEdit {
+        cells,
+        group: Some(group),
+    }
+}
+
+/// 盖在网格上的界面（时间轴、对比视图），鼠标在其上时网格不响应绘制与框选
+#[derive(Component)]
+pub struct BlocksGrid;
+
+/// 拖拽绘制：左键按下时起点格子翻转后的状态决定整笔是绘制还是擦除，右键总是擦除
+/// 铅笔工具逐帧用 Bresenham 连接上一格与当前格，形状工具在松开时一次性写入
+#[allow(clippy::too_many_arguments)]
+pub fn draw_system(
+    mouse: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    viewport: Query<(&Node, &GlobalTransform), With<GridViewport>>,
+    camera: Res<GridCamera>,
+    tool: Res<DrawTool>,
+    cell_states: Res<CellStates>,
+    mut draw: ResMut<DrawState>,
+    mut edits: EventWriter<CellEdit>,
+    blockers: Query<&Interaction, With<BlocksGrid>>,
+) {
+    if *tool == DrawTool::Select {
+        return;
+    }
+    let (Ok(window), Ok(viewport)) = (windows.get_single(), viewport.get_single()) else {
+        return;
+    };
+    let cursor = cursor_cell(window, viewport, &camera);
+
+    // 开始新的一笔：必须在可见的网格区域内按下
+    if draw.stroke.is_none() {
+        let (node, transform) = viewport;
+        let visible = Rect::from_center_size(transform.translation().truncate(), node.size());
+        // 时间轴等盖住的部分不算
+        let over_viewport = window
+            .cursor_position()
+            .is_some_and(|p| visible.contains(p))
+            && blockers.iter().all(|i| *i == Interaction::None);
+        let Some(start) = cursor.filter(|p| over_viewport && in_grid(*p)) else {
+            return;
+        };
+        let button = if mouse.just_pressed(MouseButton::Left) {
+            MouseButton::Left
+        } else if mouse.just_pressed(MouseButton::Right) {
+            MouseButton::Right
+        } else {
+            return;
+        };
+        // 衰亡中的格子按死细胞处理，点击后绘制
+        let paint = button == MouseButton::Left
+            && cell_states.states[start.y as usize][start.x as usize] != ALIVE;
+        let group = draw.next_group;
+        draw.next_group = draw.next_group.wrapping_add(1);
+        draw.stroke = Some(Stroke {
+            start,
+            last: start,
+            paint,
+            button,
+            group,
+        });
+
+        if *tool == DrawTool::Pencil {
+            edits.send(to_edit(&[start], paint, group));
+        } else {
+            draw.preview = vec![start];
+        }
+        return;
+    }
+
+    let stroke = draw.stroke.as_ref().unwrap();
+    let (start, last, paint, group) = (stroke.start, stroke.last, stroke.paint, stroke.group);
+    let released = !mouse.pressed(stroke.button);
+    let current = cursor.unwrap_or(last);
+
+    if *tool == DrawTool::Pencil {
+        if current != last {
+            edits.send(to_edit(&line(last, current)[1..], paint, group));
+        }
+    } else if released {
+        edits.send(to_edit(&shape(*tool, start, current), paint, group));
+        draw.preview.clear();
+    } else if current != last {
+        draw.preview = shape(*tool, start, current);
+    }
+
+    if released {
+        draw.stroke = None;
+    } else if let Some(stroke) = draw.stroke.as_mut() {
+        stroke.last = current;
+    }
+}
+
+/// 右侧绘图工具按钮
+pub fn tools_ui(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    parent.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: font.clone(),
+                font_size: 18.0,
+                color: Color::BLACK,
+            },
+        ),
+        Localized("label.tools"),
+        ThemedText(ThemeColor::Text),
+    ));
+
+    let tools = [
+        ("tool.pencil", DrawTool::Pencil),
+        ("tool.line", DrawTool::Line),
+        ("tool.rect", DrawTool::Rect),
+        ("tool.filled_rect", DrawTool::FilledRect),
+        ("tool.ellipse", DrawTool::Ellipse),
+        ("tool.select", DrawTool::Select),
+    ];
+
+    for (label, tool) in tools {
+        parent
+            .spawn((
+                ButtonBundle {
+                    style: Style {
+                        width: Val::Px(160.0),
+                        height: Val::Px(30.0),
+                        margin: UiRect::all(Val::Px(3.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                },
+                tool,
+            ))
+            .with_children(|btn| {
+                btn.spawn((
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    Localized(label),
+                    ThemedText(ThemeColor::ButtonText),
+                ));
+            });
+    }
+}
+
+/// 切换绘图工具并高亮当前工具
+pub fn tool_button_system(
+    interaction_query: Query<(&Interaction, &DrawTool), Changed<Interaction>>,
+    mut buttons: Query<(&DrawTool, &mut BackgroundColor)>,
+    mut tool: ResMut<DrawTool>,
+    theme: Res<Theme>,
+) {
+    for (interaction, selected) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            *tool = *selected;
+        }
+    }
+
+    if tool.is_changed() || theme.is_changed() {
+        for (button, mut color) in &mut buttons {
+            color.0 = theme.color(if *button == *tool {
+                ThemeColor::ToolSelected
+            } else {
+                ThemeColor::ToolButton
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut points: Vec<IVec2>) -> Vec<IVec2> {
+        points.sort_by_key(|p| (p.y, p.x));
+        points.dedup();
+        points
+    }
+
+    #[test]
+    fn line_is_connected() {
+        for (a, b) in [
+            (IVec2::new(0, 0), IVec2::new(7, 3)),
+            (IVec2::new(5, 9), IVec2::new(1, 0)),
+            (IVec2::new(2, 2), IVec2::new(2, 2)),
+        ] {
+            let points = line(a, b);
+            assert_eq!((points[0], *points.last().unwrap()), (a, b));
+            // 8 邻接连续，格子数为两轴距离中的较大者加一
+            assert!(
+                points
+                    .windows(2)
+                    .all(|w| (w[1] - w[0]).abs().max_element() == 1)
+            );
+            assert_eq!(points.len() as i32, (b - a).abs().max_element() + 1);
+        }
+    }
+
+    #[test]
+    fn rect_outline_and_fill() {
+        let (a, b) = (IVec2::new(4, 1), IVec2::new(1, 3));
+        assert_eq!(rect(a, b, true).len(), 12);
+        let outline = rect(a, b, false);
+        assert_eq!(outline.len(), 10);
+        assert!(!outline.contains(&IVec2::new(2, 2)));
+    }
+
+    #[test]
+    fn ellipse_fits_its_box() {
+        let (a, b) = (IVec2::new(2, 3), IVec2::new(12, 9));
+        let points = sorted(ellipse(a, b));
+        assert!(points.iter().all(|p| p.cmpge(a).all() && p.cmple(b).all()));
+        // 四条边都碰到，且左右、上下对称
+        assert!(points.iter().any(|p| p.x == a.x) && points.iter().any(|p| p.x == b.x));
+        assert!(points.iter().any(|p| p.y == a.y) && points.iter().any(|p| p.y == b.y));
+        for p in &points {
+            assert!(points.contains(&IVec2::new(a.x + b.x - p.x, p.y)));
+            assert!(points.contains(&IVec2::new(p.x, a.y + b.y - p.y)));
+        }
+        // 端点顺序不影响结果，退化时为直线
+        assert_eq!(sorted(ellipse(b, a)), points);
+        assert_eq!(ellipse(a, IVec2::new(a.x, 8)), line(a, IVec2::new(a.x, 8)));
+    }
+
+    #[test]
+    fn edits_skip_cells_outside_grid() {
+        let points = line(IVec2::new(-2, 0), IVec2::new(2, 0));
+        let edit = to_edit(&points, true, 3);
+        assert_eq!(edit.cells, [(0, 0, ALIVE), (1, 0, ALIVE), (2, 0, ALIVE)]);
+        assert_eq!(edit.group, Some(3));
+    }
+}
@@ -0,0 +1,254 @@
+use crate::keybindings::Action;
+use crate::{ALIVE, CellStates, DEAD, GRID_SIZE, Grid, History, Simulation, set_cell_state};
+use bevy::prelude::*;
+
+/// 保留的撤销步数上限
+const UNDO_LIMIT: usize = 200;
+
+/// 一次编辑：若干格子的目标状态 (x, y, 状态)
+/// 所有手动编辑（点击、清空、加载图案等）都通过发送该事件完成，便于撤销
+#[derive(Event, Clone, Default)]
+pub struct CellEdit {
+    pub cells: Vec<(usize, usize, u8)>,
+    // 相同 group 的连续编辑（如一次拖拽）合并为一个撤销步骤
+    pub group: Option<u32>,
+}
+
+impl CellEdit {
+    /// 把整个网格设为给定活细胞集合，其余格子清空
+    pub fn replace_all(alive: &[(usize, usize)]) -> Self {
+        let mut grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+        for &(x, y) in alive {
+            if x < GRID_SIZE && y < GRID_SIZE {
+                grid[y][x] = ALIVE;
+            }
+        }
+        let mut cells = Vec::with_capacity(GRID_SIZE * GRID_SIZE);
+        for (y, row) in grid.iter().enumerate() {
+            for (x, &state) in row.iter().enumerate() {
+                cells.push((x, y, state));
+            }
+        }
+        Self { cells, group: None }
+    }
+
+    pub fn clear() -> Self {
+        Self::replace_all(&[])
+    }
+}
+
+/// 已应用的编辑：编辑前后的整个网格与编辑所在的代数
+/// 演化之后仍可撤销，撤销时回到编辑所在的那一代（例如运行中误按了清空）
+struct AppliedEdit {
+    generation: u64,
+    before: Grid,
+    after: Grid,
+    group: Option<u32>,
+}
+
+#[derive(Resource, Default)]
+pub struct EditHistory {
+    undo: Vec<AppliedEdit>,
+    redo: Vec<AppliedEdit>,
+    // 上次处理时的代数：演化之后重做栈作废
+    generation: u64,
+}
+
+impl EditHistory {
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+/// 把网格恢复为 grid、代数恢复为 generation，返回变化的格子数
+/// 回到较早的代时，那之后记录的历史作废
+fn restore(
+    cell_states: &mut CellStates,
+    sim: &mut Simulation,
+    past: &mut History,
+    grid: &Grid,
+    generation: u64,
+) -> usize {
+    let mut changed = 0;
+    for (y, row) in grid.iter().enumerate() {
+        for (x, &state) in row.iter().enumerate() {
+            if cell_states.states[y][x] != state {
+                set_cell_state(cell_states, x, y, state);
+                changed += 1;
+            }
+        }
+    }
+    if sim.generation != generation {
+        let dropped = sim.generation.saturating_sub(generation) as usize;
+        let keep = past.generations.len().saturating_sub(dropped);
+        past.generations.truncate(keep);
+        sim.generation = generation;
+    }
+    changed
+}
+
+/// 应用编辑事件，并处理撤销 / 重做（默认 Ctrl+Z / Ctrl+Shift+Z）
+/// 撤销之前若已演化，网格与代数一起回到编辑之前；演化后重做栈作废
+pub fn edit_system(
+    mut events: EventReader<CellEdit>,
+    mut actions: EventReader<Action>,
+    mut history: ResMut<EditHistory>,
+    mut cell_states: ResMut<CellStates>,
+    mut sim: ResMut<Simulation>,
+    mut past: ResMut<History>,
+) {
+    if history.generation != sim.generation {
+        history.redo.clear();
+        history.generation = sim.generation;
+    }
+
+    for edit in events.read() {
+        let before = cell_states.states;
+        for &(x, y, state) in &edit.cells {
+            if x < GRID_SIZE && y < GRID_SIZE && cell_states.states[y][x] != state {
+                set_cell_state(&mut cell_states, x, y, state);
+            }
+        }
+
+        // 没有任何变化的编辑不进入撤销栈
+        if cell_states.states == before {
+            continue;
+        }
+        history.redo.clear();
+        let generation = sim.generation;
+        if let Some(last) = history.undo.last_mut()
+            && edit.group.is_some()
+            && last.group == edit.group
+            && last.generation == generation
+        {
+            last.after = cell_states.states;
+            continue;
+        }
+        history.undo.push(AppliedEdit {
+            generation,
+            before,
+            after: cell_states.states,
+            group: edit.group,
+        });
+        if history.undo.len() > UNDO_LIMIT {
+            history.undo.remove(0);
+        }
+    }
+
+    let history = &mut *history;
+    for action in actions.read() {
+        let (from, to) = match action {
+            Action::Undo => (&mut history.undo, &mut history.redo),
+            Action::Redo => (&mut history.redo, &mut history.undo),
+            _ => continue,
+        };
+        let Some(edit) = from.pop() else {
+            continue;
+        };
+        let grid = if *action == Action::Undo {
+            &edit.before
+        } else {
+            &edit.after
+        };
+        let changed = restore(&mut cell_states, &mut sim, &mut past, grid, edit.generation);
+        if *action == Action::Undo {
+            info!("撤销编辑（{} 个格子）", changed);
+        } else {
+            info!("重做编辑（{} 个格子）", changed);
+        }
+        to.push(edit);
+        history.generation = sim.generation;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_event::<CellEdit>()
+            .add_event::<Action>()
+            .init_resource::<EditHistory>()
+            .insert_resource(CellStates::default())
+            .insert_resource(Simulation::default())
+            .insert_resource(History::default())
+            .add_systems(Update, edit_system);
+        app
+    }
+
+    fn paint(app: &mut App, cells: &[(usize, usize)], group: Option<u32>) {
+        let cells = cells.iter().map(|&(x, y)| (x, y, ALIVE)).collect();
+        app.world_mut().send_event(CellEdit { cells, group });
+        app.update();
+    }
+
+    fn act(app: &mut App, action: Action) {
+        app.world_mut().send_event(action);
+        app.update();
+    }
+
+    fn alive(app: &App) -> usize {
+        let states = &app.world().resource::<CellStates>().states;
+        states.iter().flatten().filter(|s| **s == ALIVE).count()
+    }
+
+    #[test]
+    fn undo_redo_and_grouping() {
+        let mut app = app();
+        // 同组的两次编辑（一次拖拽）合并为一步
+        paint(&mut app, &[(1, 1)], Some(7));
+        paint(&mut app, &[(2, 1)], Some(7));
+        paint(&mut app, &[(5, 5)], None);
+        assert_eq!(alive(&app), 3);
+
+        act(&mut app, Action::Undo);
+        assert_eq!(alive(&app), 2);
+        act(&mut app, Action::Undo);
+        assert_eq!(alive(&app), 0);
+        act(&mut app, Action::Undo);
+        assert_eq!(alive(&app), 0);
+
+        act(&mut app, Action::Redo);
+        assert_eq!(alive(&app), 2);
+        // 新的编辑使重做栈作废
+        paint(&mut app, &[(9, 9)], None);
+        act(&mut app, Action::Redo);
+        assert_eq!(alive(&app), 3);
+
+        // 没有变化的编辑不占用撤销步骤
+        paint(&mut app, &[(9, 9)], None);
+        act(&mut app, Action::Undo);
+        assert_eq!(alive(&app), 2);
+    }
+
+    #[test]
+    fn undo_clear_after_evolution() {
+        let mut app = app();
+        paint(&mut app, &[(1, 1), (2, 2)], None);
+        app.world_mut().send_event(CellEdit::clear());
+        app.update();
+        assert_eq!(alive(&app), 0);
+
+        // 清空之后又演化了两代
+        let cleared = app.world().resource::<CellStates>().states;
+        app.world_mut()
+            .resource_mut::<History>()
+            .generations
+            .extend([cleared, cleared]);
+        app.world_mut().resource_mut::<Simulation>().generation = 2;
+        app.update();
+
+        act(&mut app, Action::Undo);
+        assert_eq!(alive(&app), 2);
+        assert_eq!(app.world().resource::<Simulation>().generation, 0);
+        assert!(app.world().resource::<History>().generations.is_empty());
+
+        act(&mut app, Action::Redo);
+        assert_eq!(alive(&app), 0);
+        act(&mut app, Action::Undo);
+        act(&mut app, Action::Undo);
+        assert_eq!(alive(&app), 0);
+    }
+}
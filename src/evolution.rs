@@ -1,70 +1,1165 @@
-use crate::GRID_SIZE;
+use crate::rule_table::RuleTable;
+use crate::{ALIVE, DEAD, GRID_SIZE, Grid};
+use std::fmt;
+use std::sync::Arc;
 
-pub fn next_generation_bounded(
-    current: &[[bool; GRID_SIZE]; GRID_SIZE],
-) -> [[bool; GRID_SIZE]; GRID_SIZE] {
-    let mut next = [[false; GRID_SIZE]; GRID_SIZE];
+/// 边界拓扑：有界（边界外视为死细胞）或环面（上下左右相连）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Topology {
+    #[default]
+    Bounded,
+    Torus,
+}
 
-    for y in 0..GRID_SIZE {
-        for x in 0..GRID_SIZE {
-            let mut neighbors = 0i32;
-            for dy in -1..=1 {
-                for dx in -1..=1 {
-                    if dx == 0 && dy == 0 {
-                        continue;
+impl Topology {
+    pub fn name(self) -> &'static str {
+        match self {
+            Topology::Bounded => "bounded",
+            Topology::Torus => "torus",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bounded" => Some(Topology::Bounded),
+            "torus" => Some(Topology::Torus),
+            _ => None,
+        }
+    }
+}
+
+/// 规则表能表示的最大邻居数（半径 3 的 Moore 邻域共 48 个邻居）
+pub const MAX_NEIGHBORS: usize = 48;
+
+/// 生命类规则：birth[n] / survive[n] 表示 n 个邻居时出生 / 存活
+/// states > 2 时为 Generations 规则：不满足存活条件的细胞不会立即死亡，
+/// 而是依次经过 2..states-1 的衰亡状态（不计为邻居、也不能出生）后才变为死细胞
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LifeRule {
+    pub birth: [bool; MAX_NEIGHBORS + 1],
+    pub survive: [bool; MAX_NEIGHBORS + 1],
+    // 状态总数（含死亡与存活），生命类规则为 2
+    pub states: u8,
+}
+
+impl Default for LifeRule {
+    /// 康威生命游戏 B3/S23
+    fn default() -> Self {
+        let mut rule = LifeRule {
+            birth: [false; MAX_NEIGHBORS + 1],
+            survive: [false; MAX_NEIGHBORS + 1],
+            states: 2,
+        };
+        rule.birth[3] = true;
+        rule.survive[2] = true;
+        rule.survive[3] = true;
+        rule
+    }
+}
+
+impl LifeRule {
+    /// 解析 "B3/S23" 形式（也接受传统的 "23/3" 即 S/B 形式）
+    /// Generations 规则写作 "B2/S/C3"，或传统的 S/B/C 形式如 "345/2/4"、"/2/3"
+    /// 大于 8 的邻居数用逗号与区间书写，如 "B3,10..12/S2,3"（用于扩展邻域）
+    pub fn parse(text: &str) -> Option<Self> {
+        let parts: Vec<&str> = text.trim().split('/').map(str::trim).collect();
+        let (mut birth, mut survive, mut states) = (None, None, None);
+        if parts.iter().any(|p| p.starts_with(['B', 'b'])) {
+            for part in &parts {
+                match part.chars().next() {
+                    Some('B' | 'b') => birth = Some(&part[1..]),
+                    Some('S' | 's') => survive = Some(&part[1..]),
+                    Some('C' | 'c' | 'G' | 'g') => states = Some(&part[1..]),
+                    _ => return None,
+                }
+            }
+        } else {
+            // 无字母前缀时按 S/B（/C）解释
+            match parts[..] {
+                [s, b] => (survive, birth) = (Some(s), Some(b)),
+                [s, b, c] => (survive, birth, states) = (Some(s), Some(b), Some(c)),
+                _ => return None,
+            }
+        }
+        let (birth, survive) = (birth?, survive.unwrap_or(""));
+        let states = match states {
+            Some(c) => c.parse::<u8>().ok().filter(|c| *c >= 2)?,
+            None => 2,
+        };
+
+        let mut rule = LifeRule {
+            birth: [false; MAX_NEIGHBORS + 1],
+            survive: [false; MAX_NEIGHBORS + 1],
+            states,
+        };
+        for (counts, table) in [(birth, &mut rule.birth), (survive, &mut rule.survive)] {
+            parse_counts(counts, table)?;
+        }
+        Some(rule)
+    }
+
+    /// 由当前状态与存活邻居数（加权邻域中为权重之和）得到下一状态
+    fn apply(&self, state: u8, neighbors: u32) -> u8 {
+        let lookup = |table: &[bool]| table.get(neighbors as usize).copied().unwrap_or(false);
+        next_state(
+            state,
+            self.states,
+            lookup(&self.birth),
+            lookup(&self.survive),
+        )
+    }
+}
+
+/// 出生 / 存活条件已知时的下一状态，两类规则共用
+fn next_state(state: u8, states: u8, birth: bool, survive: bool) -> u8 {
+    match state {
+        DEAD if birth => ALIVE,
+        DEAD => DEAD,
+        ALIVE if survive => ALIVE,
+        // 衰亡状态逐代推进，最后一个状态之后死亡
+        _ if state < states.saturating_sub(1) => state + 1,
+        _ => DEAD,
+    }
+}
+
+/// Larger than Life 规则允许的最大半径
+pub const MAX_LTL_RANGE: u8 = 10;
+
+/// Larger than Life 规则，写作 "R5,C0,M1,S34..58,B34..45,NM"
+/// R 半径，C 状态数（0 或 2 表示两状态），M 是否把中心格计入，
+/// S / B 为存活 / 出生的邻居数区间，N 为邻域形状（M 方形、N 菱形）
+/// 邻域由规则自身决定，不使用 Simulation 中选择的邻域
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LtlRule {
+    pub range: u8,
+    pub states: u8,
+    pub middle: bool,
+    pub survive: (u32, u32),
+    pub birth: (u32, u32),
+    pub von_neumann: bool,
+}
+
+impl LtlRule {
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut rule = LtlRule {
+            range: 0,
+            states: 2,
+            middle: false,
+            survive: (1, 0),
+            birth: (1, 0),
+            von_neumann: false,
+        };
+        let (mut has_survive, mut has_birth) = (false, false);
+        let interval = |v: &str| -> Option<(u32, u32)> {
+            match v.split_once("..") {
+                Some((low, high)) => Some((low.trim().parse().ok()?, high.trim().parse().ok()?)),
+                None => v.trim().parse().ok().map(|n| (n, n)),
+            }
+        };
+        for field in text.split(',').map(str::trim) {
+            let mut chars = field.chars();
+            let key = chars.next()?.to_ascii_uppercase();
+            let value = chars.as_str().trim();
+            match key {
+                'R' => rule.range = value.parse().ok()?,
+                'C' => rule.states = value.parse::<u8>().ok()?.max(2),
+                'M' => rule.middle = value.parse::<u8>().ok()? == 1,
+                'S' => (rule.survive, has_survive) = (interval(value)?, true),
+                'B' => (rule.birth, has_birth) = (interval(value)?, true),
+                'N' => {
+                    rule.von_neumann = match value {
+                        "M" | "m" => false,
+                        "N" | "n" => true,
+                        _ => return None,
                     }
-                    let nx = x as isize + dx;
-                    let ny = y as isize + dy;
-                    if nx >= 0 && nx < GRID_SIZE as isize && ny >= 0 && ny < GRID_SIZE as isize {
-                        if current[ny as usize][nx as usize] {
-                            neighbors += 1;
-                        }
+                }
+                _ => return None,
+            }
+        }
+        let valid = (1..=MAX_LTL_RANGE).contains(&rule.range) && has_survive && has_birth;
+        valid.then_some(rule)
+    }
+
+    fn apply(&self, state: u8, neighbors: u32) -> u8 {
+        let within = |(low, high): (u32, u32)| (low..=high).contains(&neighbors);
+        next_state(state, self.states, within(self.birth), within(self.survive))
+    }
+}
+
+impl fmt::Display for LtlRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "R{},C{},M{},S{}..{},B{}..{},N{}",
+            self.range,
+            if self.states > 2 { self.states } else { 0 },
+            self.middle as u8,
+            self.survive.0,
+            self.survive.1,
+            self.birth.0,
+            self.birth.1,
+            if self.von_neumann { 'N' } else { 'M' }
+        )
+    }
+}
+
+/// 摩尔邻域 8 个邻居的相对位置，按行从左上到右下；第 i 个邻居对应构型掩码的第 i 位
+const MOORE_OFFSETS: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// Hensel 记法中 1..=4 个邻居的字母及其代表构型（3×3 图案按行以 '/' 分隔，o 为存活邻居，中心忽略）
+/// 其余构型由代表构型旋转、翻转得到；5..=7 个邻居沿用 8 - n 的字母，构型取补集
+const HENSEL_LETTERS: [&[(char, &str)]; 5] = [
+    &[],
+    &[('c', "o../.../..."), ('e', ".o./.../...")],
+    &[
+        ('c', "o.o/.../..."),
+        ('e', ".o./o../..."),
+        ('k', ".o./.../o.."),
+        ('a', "oo./.../..."),
+        ('i', ".o./.../.o."),
+        ('n', "o../.../..o"),
+    ],
+    &[
+        ('c', "o.o/.../o.."),
+        ('e', ".o./o.o/..."),
+        ('k', ".o./..o/o.."),
+        ('a', "oo./o../..."),
+        ('i', "ooo/.../..."),
+        ('n', "o.o/o../..."),
+        ('y', "o../..o/o.."),
+        ('q', ".oo/.../o.."),
+        ('j', ".oo/o../..."),
+        ('r', "o../o.o/..."),
+    ],
+    &[
+        ('c', "o.o/.../o.o"),
+        ('e', ".o./o.o/.o."),
+        ('k', "oo./..o/o.."),
+        ('a', "ooo/o../..."),
+        ('i', "o.o/o.o/..."),
+        ('n', "ooo/.../o.."),
+        ('y', "o.o/..o/o.."),
+        ('q', ".oo/..o/o.."),
+        ('j', ".o./o.o/o.."),
+        ('r', "oo./o.o/..."),
+        ('t', "ooo/.../.o."),
+        ('w', "o../o../.oo"),
+        ('z', "..o/o.o/o.."),
+    ],
+];
+
+/// n 个邻居时可用的字母及代表构型
+fn hensel_letters(n: u32) -> &'static [(char, &'static str)] {
+    HENSEL_LETTERS[n.min(8 - n) as usize]
+}
+
+/// 3×3 图案转为构型掩码
+fn picture_mask(picture: &str) -> u8 {
+    let cells: Vec<char> = picture.chars().filter(|c| *c != '/').collect();
+    MOORE_OFFSETS
+        .iter()
+        .enumerate()
+        .filter(|(_, (dx, dy))| cells[((dy + 1) * 3 + dx + 1) as usize] == 'o')
+        .fold(0, |mask, (i, _)| mask | 1 << i)
+}
+
+/// 构型经旋转、翻转得到的 8 个构型：依次组合交换坐标轴、水平翻转、垂直翻转
+fn symmetries(mask: u8) -> [u8; 8] {
+    std::array::from_fn(|k| {
+        let mut result = 0;
+        for (i, &(dx, dy)) in MOORE_OFFSETS.iter().enumerate() {
+            if mask & 1 << i == 0 {
+                continue;
+            }
+            let (x, y) = if k & 1 != 0 { (dy, dx) } else { (dx, dy) };
+            let x = if k & 2 != 0 { -x } else { x };
+            let y = if k & 4 != 0 { -y } else { y };
+            let p = MOORE_OFFSETS.iter().position(|&o| o == (x, y)).unwrap();
+            result |= 1 << p;
+        }
+        result
+    })
+}
+
+/// 每个构型所属的 Hensel 字母；0 与 8 个邻居只有一种构型，没有字母
+fn hensel_classes() -> [Option<char>; 256] {
+    let mut classes = [None; 256];
+    for n in 1..=4 {
+        for &(letter, picture) in hensel_letters(n) {
+            for mask in symmetries(picture_mask(picture)) {
+                classes[mask as usize] = Some(letter);
+                // 补集构型有 8 - n 个邻居，使用同一字母
+                if n < 4 {
+                    classes[!mask as usize] = Some(letter);
+                }
+            }
+        }
+    }
+    classes
+}
+
+/// 256 种邻居构型的集合，按位存储
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MaskSet([u64; 4]);
+
+impl MaskSet {
+    pub fn contains(&self, mask: usize) -> bool {
+        self.0[mask / 64] & 1 << (mask % 64) != 0
+    }
+
+    pub(crate) fn insert(&mut self, mask: usize) {
+        self.0[mask / 64] |= 1 << (mask % 64);
+    }
+}
+
+/// 各向同性非总和规则（Hensel 记法），如 "B2-a/S12"、"B2ce3/S23-k"
+/// 数字后的字母只选取对应构型，"-" 后的字母表示排除，只写数字表示全部构型
+/// 按 3×3 摩尔邻域的具体构型决定出生 / 存活，不使用 Simulation 中选择的邻域
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IsotropicRule {
+    // 以构型掩码为下标，见 MOORE_OFFSETS
+    pub birth: MaskSet,
+    pub survive: MaskSet,
+    pub states: u8,
+}
+
+impl IsotropicRule {
+    /// 只接受带 B / S 前缀的写法，可带 /C 表示 Generations 规则
+    pub fn parse(text: &str) -> Option<Self> {
+        let classes = hensel_classes();
+        let mut rule = IsotropicRule {
+            birth: MaskSet::default(),
+            survive: MaskSet::default(),
+            states: 2,
+        };
+        let mut has_birth = false;
+        for part in text.trim().split('/').map(str::trim) {
+            let mut chars = part.chars();
+            let key = chars.next()?;
+            let value = chars.as_str();
+            match key {
+                'B' | 'b' => {
+                    parse_hensel(value, &classes, &mut rule.birth)?;
+                    has_birth = true;
+                }
+                'S' | 's' => parse_hensel(value, &classes, &mut rule.survive)?,
+                'C' | 'c' | 'G' | 'g' => {
+                    rule.states = value.parse::<u8>().ok().filter(|c| *c >= 2)?;
+                }
+                _ => return None,
+            }
+        }
+        has_birth.then_some(rule)
+    }
+
+    /// 由当前状态与邻居构型掩码得到下一状态
+    fn apply(&self, state: u8, mask: u32) -> u8 {
+        let mask = mask as usize;
+        next_state(
+            state,
+            self.states,
+            self.birth.contains(mask),
+            self.survive.contains(mask),
+        )
+    }
+}
+
+/// 解析 "2-a3ce4" 这样的数字与字母序列，选中的构型加入 table
+fn parse_hensel(text: &str, classes: &[Option<char>; 256], table: &mut MaskSet) -> Option<()> {
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let n = c.to_digit(10).filter(|n| *n <= 8)?;
+        let negate = chars.next_if_eq(&'-').is_some();
+        let mut letters = Vec::new();
+        while let Some(letter) = chars.next_if(char::is_ascii_lowercase) {
+            if !hensel_letters(n).iter().any(|(l, _)| *l == letter) {
+                return None;
+            }
+            letters.push(letter);
+        }
+        if negate && letters.is_empty() {
+            return None;
+        }
+        for mask in (0..256).filter(|m: &usize| m.count_ones() == n) {
+            let chosen = classes[mask].is_some_and(|l| letters.contains(&l));
+            if letters.is_empty() || chosen != negate {
+                table.insert(mask);
+            }
+        }
+    }
+    Some(())
+}
+
+/// parse_hensel 的逆过程：全选只写数字，选中超过一半时写成排除形式
+fn write_hensel(
+    f: &mut fmt::Formatter<'_>,
+    classes: &[Option<char>; 256],
+    table: &MaskSet,
+) -> fmt::Result {
+    for n in 0..=8 {
+        let letters = hensel_letters(n);
+        let selected = |letter: Option<char>| {
+            (0..256)
+                .any(|m: usize| m.count_ones() == n && classes[m] == letter && table.contains(m))
+        };
+        if letters.is_empty() {
+            if selected(None) {
+                write!(f, "{}", n)?;
+            }
+            continue;
+        }
+        let (present, missing): (Vec<char>, Vec<char>) = letters
+            .iter()
+            .map(|(l, _)| *l)
+            .partition(|l| selected(Some(*l)));
+        match (present.len(), missing.len()) {
+            (0, _) => {}
+            (_, 0) => write!(f, "{}", n)?,
+            (p, m) if p > m => write!(f, "{}-{}", n, missing.iter().collect::<String>())?,
+            _ => write!(f, "{}{}", n, present.iter().collect::<String>())?,
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Display for IsotropicRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let classes = hensel_classes();
+        write!(f, "B")?;
+        write_hensel(f, &classes, &self.birth)?;
+        write!(f, "/S")?;
+        write_hensel(f, &classes, &self.survive)?;
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+        Ok(())
+    }
+}
+
+/// 演化规则：生命类 / Generations 规则、各向同性非总和规则、Larger than Life 规则，
+/// 或从 Golly .rule 文件读取的规则表
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Rule {
+    Life(LifeRule),
+    Isotropic(IsotropicRule),
+    LargerThanLife(LtlRule),
+    Table(Arc<RuleTable>),
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::Life(LifeRule::default())
+    }
+}
+
+impl Rule {
+    /// 以 R 开头的按 Larger than Life 记法解析，其余按 B/S 记法解析，
+    /// 数字后带字母的按 Hensel 记法解析
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if text.starts_with(['R', 'r']) {
+            LtlRule::parse(text).map(Rule::LargerThanLife)
+        } else {
+            LifeRule::parse(text)
+                .map(Rule::Life)
+                .or_else(|| IsotropicRule::parse(text).map(Rule::Isotropic))
+        }
+    }
+
+    /// 状态总数（含死亡与存活）
+    pub fn states(&self) -> u8 {
+        match self {
+            Rule::Life(rule) => rule.states,
+            Rule::Isotropic(rule) => rule.states,
+            Rule::LargerThanLife(rule) => rule.states,
+            Rule::Table(rule) => rule.states,
+        }
+    }
+
+    /// 是否为多状态（Generations）规则
+    pub fn is_generations(&self) -> bool {
+        self.states() > 2
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rule::Life(rule) => rule.fmt(f),
+            Rule::Isotropic(rule) => rule.fmt(f),
+            Rule::LargerThanLife(rule) => rule.fmt(f),
+            // 规则表以名称表示，读回时在已加载的规则中查找
+            Rule::Table(rule) => write!(f, "{}", rule.name),
+        }
+    }
+}
+
+/// 单个数字串（"23"）或逗号分隔的数字与区间（"2,3,10..12"）
+fn parse_counts(text: &str, table: &mut [bool; MAX_NEIGHBORS + 1]) -> Option<()> {
+    if !text.contains([',', '.']) {
+        for c in text.chars() {
+            let n = c.to_digit(10)? as usize;
+            if n > 8 {
+                return None;
+            }
+            table[n] = true;
+        }
+        return Some(());
+    }
+    for item in text.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+        let (low, high) = match item.split_once("..") {
+            Some((low, high)) => (low.trim().parse().ok()?, high.trim().parse().ok()?),
+            None => {
+                let n: usize = item.parse().ok()?;
+                (n, n)
+            }
+        };
+        if low > high || high > MAX_NEIGHBORS {
+            return None;
+        }
+        table[low..=high].fill(true);
+    }
+    Some(())
+}
+
+/// parse_counts 的逆过程：只含 0..8 时写成数字串，否则写成逗号与区间形式
+fn write_counts(f: &mut fmt::Formatter<'_>, table: &[bool; MAX_NEIGHBORS + 1]) -> fmt::Result {
+    if table[9..].iter().all(|b| !b) {
+        for n in (0..9).filter(|&n| table[n]) {
+            write!(f, "{}", n)?;
+        }
+        return Ok(());
+    }
+    let mut items = Vec::new();
+    let mut n = 0;
+    while n <= MAX_NEIGHBORS {
+        if !table[n] {
+            n += 1;
+            continue;
+        }
+        let start = n;
+        while n < MAX_NEIGHBORS && table[n + 1] {
+            n += 1;
+        }
+        items.push(if start == n {
+            start.to_string()
+        } else {
+            format!("{}..{}", start, n)
+        });
+        n += 1;
+    }
+    // 只有一个数时补成区间，避免被当作数字串读回
+    match &items[..] {
+        [single] if !single.contains("..") => write!(f, "{}..{}", single, single),
+        _ => write!(f, "{}", items.join(",")),
+    }
+}
+
+impl fmt::Display for LifeRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "B")?;
+        write_counts(f, &self.birth)?;
+        write!(f, "/S")?;
+        write_counts(f, &self.survive)?;
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+        Ok(())
+    }
+}
+
+/// 邻域：统计哪些相对位置上的存活细胞
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Neighborhood {
+    // 参数为半径（1..=3），半径 1 即标准的 3×3 邻域
+    Moore(u8),
+    VonNeumann(u8),
+    // 六边形：奇数行向右错开半格，每格 6 个邻居
+    Hexagonal,
+    Custom(Kernel),
+}
+
+impl Default for Neighborhood {
+    fn default() -> Self {
+        Neighborhood::Moore(1)
+    }
+}
+
+/// 自定义加权邻域，中心位于正中，宽高均为奇数
+/// 邻居数为所有存活格子权重之和；中心格的权重也会计入
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Kernel {
+    pub width: usize,
+    pub height: usize,
+    // 按行存储
+    pub weights: Vec<u32>,
+}
+
+impl Kernel {
+    /// 每行一组以空白或逗号分隔的权重，行之间用换行或 '/' 分隔
+    /// 单个权重不能超过 MAX_NEIGHBORS（更大的邻居数不会与任何规则匹配）
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut rows: Vec<Vec<u32>> = Vec::new();
+        for line in text.split(['\n', '/']).map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let row = line
+                .split([' ', '\t', ','])
+                .filter(|w| !w.is_empty())
+                .map(|w| match w.parse::<u32>() {
+                    Ok(weight) if weight as usize <= MAX_NEIGHBORS => Ok(weight),
+                    Ok(_) => Err(format!("权重 {} 超过 {}", w, MAX_NEIGHBORS)),
+                    Err(_) => Err(format!("无法解析权重 {}", w)),
+                })
+                .collect::<Result<Vec<u32>, String>>()?;
+            rows.push(row);
+        }
+        let height = rows.len();
+        let width = rows.first().map_or(0, Vec::len);
+        if width.is_multiple_of(2) || height.is_multiple_of(2) {
+            return Err("邻域的宽高必须为奇数".to_string());
+        }
+        if rows.iter().any(|r| r.len() != width) {
+            return Err("邻域每行的权重个数必须相同".to_string());
+        }
+        Ok(Kernel {
+            width,
+            height,
+            weights: rows.concat(),
+        })
+    }
+}
+
+impl fmt::Display for Kernel {
+    /// 单行形式，如 "1,1,1/1,0,1/1,1,1"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows: Vec<String> = self
+            .weights
+            .chunks(self.width)
+            .map(|row| row.iter().map(u32::to_string).collect::<Vec<_>>().join(","))
+            .collect();
+        write!(f, "{}", rows.join("/"))
+    }
+}
+
+impl Neighborhood {
+    /// 会话文件中使用的名称，如 "moore"、"von_neumann:2"、"custom:1,1,1/1,0,1/1,1,1"
+    pub fn name(&self) -> String {
+        match self {
+            Neighborhood::Moore(1) => "moore".to_string(),
+            Neighborhood::Moore(r) => format!("moore:{}", r),
+            Neighborhood::VonNeumann(1) => "von_neumann".to_string(),
+            Neighborhood::VonNeumann(r) => format!("von_neumann:{}", r),
+            Neighborhood::Hexagonal => "hex".to_string(),
+            Neighborhood::Custom(kernel) => format!("custom:{}", kernel),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (kind, param) = match name.split_once(':') {
+            Some((kind, param)) => (kind.trim(), Some(param.trim())),
+            None => (name.trim(), None),
+        };
+        let radius = || match param {
+            None => Some(1),
+            Some(r) => r.parse::<u8>().ok().filter(|r| (1..=3).contains(r)),
+        };
+        match kind {
+            "moore" => Some(Neighborhood::Moore(radius()?)),
+            "von_neumann" => Some(Neighborhood::VonNeumann(radius()?)),
+            "hex" if param.is_none() => Some(Neighborhood::Hexagonal),
+            "custom" => Kernel::parse(param?).ok().map(Neighborhood::Custom),
+            _ => None,
+        }
+    }
+
+    pub fn is_hexagonal(&self) -> bool {
+        *self == Neighborhood::Hexagonal
+    }
+
+    /// 相对偏移 (dx, dy, 权重)；六边形邻域与所在行的奇偶有关
+    pub fn offsets(&self, odd_row: bool) -> Vec<(isize, isize, u32)> {
+        let square = |r: u8, keep: &dyn Fn(isize, isize) -> bool| {
+            let r = r as isize;
+            let mut offsets = Vec::new();
+            for dy in -r..=r {
+                for dx in -r..=r {
+                    if (dx, dy) != (0, 0) && keep(dx, dy) {
+                        offsets.push((dx, dy, 1));
                     }
                 }
             }
-            next[y][x] = if current[y][x] {
-                neighbors == 2 || neighbors == 3
-            } else {
-                neighbors == 3
-            };
+            offsets
+        };
+        match self {
+            Neighborhood::Moore(r) => square(*r, &|_, _| true),
+            Neighborhood::VonNeumann(r) => square(*r, &|dx, dy| dx.abs() + dy.abs() <= *r as isize),
+            Neighborhood::Hexagonal => {
+                // 上下两行中与本格相邻的两格，取决于本行是否错开
+                let shift = if odd_row { 0 } else { -1 };
+                vec![
+                    (-1, 0, 1),
+                    (1, 0, 1),
+                    (shift, -1, 1),
+                    (shift + 1, -1, 1),
+                    (shift, 1, 1),
+                    (shift + 1, 1, 1),
+                ]
+            }
+            Neighborhood::Custom(kernel) => {
+                let (cx, cy) = ((kernel.width / 2) as isize, (kernel.height / 2) as isize);
+                kernel
+                    .weights
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, w)| **w > 0)
+                    .map(|(i, w)| {
+                        let (x, y) = ((i % kernel.width) as isize, (i / kernel.width) as isize);
+                        (x - cx, y - cy, *w)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// (x, y) 偏移 (dx, dy) 处的格子；wrap 为 true 时上下左右相连，否则超出网格时为 None
+/// hex 为六边形排列：GRID_SIZE 为奇数，首尾两行都不错开，
+/// 从第 0 行向上越过边界时向右多移一格，使环面上的邻接关系仍然对称
+fn neighbor_at(
+    x: usize,
+    y: usize,
+    dx: isize,
+    dy: isize,
+    wrap: bool,
+    hex: bool,
+) -> Option<(usize, usize)> {
+    let size = GRID_SIZE as isize;
+    let (mut nx, mut ny) = (x as isize + dx, y as isize + dy);
+    if wrap {
+        if hex && ny < 0 {
+            nx += 1;
+        }
+        nx = nx.rem_euclid(size);
+        ny = ny.rem_euclid(size);
+    } else if nx < 0 || nx >= size || ny < 0 || ny >= size {
+        return None;
+    }
+    Some((nx as usize, ny as usize))
+}
+
+/// 统计 (x, y) 的邻居，wrap 与 hex 见 neighbor_at
+fn count_neighbors(
+    current: &Grid,
+    offsets: &[(isize, isize, u32)],
+    x: usize,
+    y: usize,
+    wrap: bool,
+    hex: bool,
+) -> u32 {
+    let mut neighbors = 0u32;
+    for &(dx, dy, weight) in offsets {
+        if let Some((nx, ny)) = neighbor_at(x, y, dx, dy, wrap, hex)
+            && current[ny][nx] == ALIVE
+        {
+            neighbors = neighbors.saturating_add(weight);
+        }
+    }
+    neighbors
+}
+
+fn next_generation_with(
+    current: &Grid,
+    rule: &Rule,
+    neighborhood: &Neighborhood,
+    wrap: bool,
+) -> Grid {
+    let rule = match rule {
+        Rule::Life(rule) => rule,
+        Rule::Isotropic(rule) => return next_generation_isotropic(current, rule, wrap),
+        Rule::LargerThanLife(rule) => return next_generation_ltl(current, rule, wrap),
+        Rule::Table(rule) => return rule.next_generation(current, wrap),
+    };
+    let mut next = [[DEAD; GRID_SIZE]; GRID_SIZE];
+    let offsets = [neighborhood.offsets(false), neighborhood.offsets(true)];
+    let hex = neighborhood.is_hexagonal();
+
+    for y in 0..GRID_SIZE {
+        for x in 0..GRID_SIZE {
+            let neighbors = count_neighbors(current, &offsets[y % 2], x, y, wrap, hex);
+            next[y][x] = rule.apply(current[y][x], neighbors);
         }
     }
 
     next
 }
 
-pub fn next_generation_torus(
-    current: &[[bool; GRID_SIZE]; GRID_SIZE],
-) -> [[bool; GRID_SIZE]; GRID_SIZE] {
-    let mut next = [[false; GRID_SIZE]; GRID_SIZE];
+/// 各向同性非总和规则：第 i 个邻居的权重取 1 << i，加权和即为邻居构型掩码
+fn next_generation_isotropic(current: &Grid, rule: &IsotropicRule, wrap: bool) -> Grid {
+    let offsets: Vec<(isize, isize, u32)> = MOORE_OFFSETS
+        .iter()
+        .enumerate()
+        .map(|(i, &(dx, dy))| (dx, dy, 1 << i))
+        .collect();
+    let mut next = [[DEAD; GRID_SIZE]; GRID_SIZE];
+    for y in 0..GRID_SIZE {
+        for x in 0..GRID_SIZE {
+            let mask = count_neighbors(current, &offsets, x, y, wrap, false);
+            next[y][x] = rule.apply(current[y][x], mask);
+        }
+    }
+    next
+}
+
+/// Larger than Life：先把网格按半径向四周扩展（环面取对侧、有界补零），
+/// 方形邻域用二维前缀和（summed-area table）O(1) 求和，菱形邻域用每行的前缀和逐行累加
+/// 半径不超过 MAX_LTL_RANGE，小于网格一半，环面上不会重复计数
+fn next_generation_ltl(current: &Grid, rule: &LtlRule, wrap: bool) -> Grid {
+    let r = rule.range as usize;
+    let size = GRID_SIZE + 2 * r;
+    let stride = size + 1;
+    let alive = |px: usize, py: usize| {
+        let n = GRID_SIZE as isize;
+        let (gx, gy) = (px as isize - r as isize, py as isize - r as isize);
+        let (gx, gy) = if wrap {
+            (gx.rem_euclid(n), gy.rem_euclid(n))
+        } else if gx < 0 || gx >= n || gy < 0 || gy >= n {
+            return 0;
+        } else {
+            (gx, gy)
+        };
+        (current[gy as usize][gx as usize] == ALIVE) as u32
+    };
 
+    // rows[py][px + 1]：扩展网格第 py 行前 px + 1 格的存活数
+    // sat[py + 1][px + 1]：扩展网格左上 (px + 1) × (py + 1) 区域的存活数
+    let mut rows = vec![0u32; size * stride];
+    let mut sat = vec![0u32; stride * stride];
+    for py in 0..size {
+        let mut row_sum = 0;
+        for px in 0..size {
+            row_sum += alive(px, py);
+            rows[py * stride + px + 1] = row_sum;
+            sat[(py + 1) * stride + px + 1] = sat[py * stride + px + 1] + row_sum;
+        }
+    }
+
+    let mut next = [[DEAD; GRID_SIZE]; GRID_SIZE];
     for y in 0..GRID_SIZE {
         for x in 0..GRID_SIZE {
-            let mut neighbors = 0i32;
-            for dy in -1..=1 {
-                for dx in -1..=1 {
-                    if dx == 0 && dy == 0 {
-                        continue;
-                    }
-                    let nx = ((x as isize + dx).rem_euclid(GRID_SIZE as isize)) as usize;
-                    let ny = ((y as isize + dy).rem_euclid(GRID_SIZE as isize)) as usize;
-                    if current[ny][nx] {
-                        neighbors += 1;
-                    }
-                }
-            }
-            next[y][x] = if current[y][x] {
-                neighbors == 2 || neighbors == 3
+            // 本格在扩展网格中的位置
+            let (cx, cy) = (x + r, y + r);
+            let mut neighbors = if rule.von_neumann {
+                (0..=2 * r)
+                    .map(|dy| {
+                        let py = cy + dy - r;
+                        let half = r - dy.abs_diff(r);
+                        rows[py * stride + cx + half + 1] - rows[py * stride + cx - half]
+                    })
+                    .sum()
             } else {
-                neighbors == 3
+                let (x0, y0, x1, y1) = (cx - r, cy - r, cx + r + 1, cy + r + 1);
+                sat[y1 * stride + x1] + sat[y0 * stride + x0]
+                    - sat[y0 * stride + x1]
+                    - sat[y1 * stride + x0]
             };
+            if !rule.middle && current[y][x] == ALIVE {
+                neighbors -= 1;
+            }
+            next[y][x] = rule.apply(current[y][x], neighbors);
         }
     }
 
     next
 }
 
-pub fn next_generation(current: &[[bool; GRID_SIZE]; GRID_SIZE]) -> [[bool; GRID_SIZE]; GRID_SIZE] {
-    debug_assert_eq!(current.len(), GRID_SIZE);
-    next_generation_bounded(current)
+pub fn next_generation_bounded(current: &Grid, rule: &Rule, neighborhood: &Neighborhood) -> Grid {
+    next_generation_with(current, rule, neighborhood, false)
+}
+
+pub fn next_generation_torus(current: &Grid, rule: &Rule, neighborhood: &Neighborhood) -> Grid {
+    next_generation_with(current, rule, neighborhood, true)
+}
+
+pub fn next_generation(
+    current: &Grid,
+    rule: &Rule,
+    neighborhood: &Neighborhood,
+    topology: Topology,
+) -> Grid {
+    match topology {
+        Topology::Bounded => next_generation_bounded(current, rule, neighborhood),
+        Topology::Torus => next_generation_torus(current, rule, neighborhood),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 由 (x, y, 状态) 列表构造网格
+    fn grid(cells: &[(usize, usize, u8)]) -> Grid {
+        let mut grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+        for &(x, y, state) in cells {
+            grid[y][x] = state;
+        }
+        grid
+    }
+
+    fn step(grid: &Grid, rule: &str) -> Grid {
+        let rule = Rule::parse(rule).unwrap();
+        next_generation(grid, &rule, &Neighborhood::default(), Topology::Bounded)
+    }
+
+    #[test]
+    fn generations_rule_round_trip() {
+        let rule = Rule::parse("B2/S/C3").unwrap();
+        assert_eq!(rule.states(), 3);
+        assert!(rule.is_generations());
+        assert_eq!(rule.to_string(), "B2/S/C3");
+        assert!(!Rule::parse("B3/S23").unwrap().is_generations());
+    }
+
+    #[test]
+    fn brians_brain_domino() {
+        let start = grid(&[(5, 5, ALIVE), (6, 5, ALIVE)]);
+        let next = step(&start, "B2/S/C3");
+        // 活细胞不能存活而进入衰亡状态，上下两格各有两个活邻居而出生
+        let expected = grid(&[
+            (5, 5, 2),
+            (6, 5, 2),
+            (5, 4, ALIVE),
+            (6, 4, ALIVE),
+            (5, 6, ALIVE),
+            (6, 6, ALIVE),
+        ]);
+        assert_eq!(next, expected);
+        // 衰亡状态不算作活邻居，最后一个状态之后死亡
+        let next = step(&next, "B2/S/C3");
+        assert_eq!(next[5][5], DEAD);
+        assert_eq!(next[5][6], DEAD);
+        assert_eq!(next[4][5], 2);
+        assert_eq!(next[3][5], ALIVE);
+    }
+
+    /// 伪随机的初始网格，约一半格子存活
+    fn soup(seed: u64) -> Grid {
+        let mut state = seed;
+        let mut grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+        for cell in grid.iter_mut().flatten() {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            *cell = (state & 1) as u8;
+        }
+        grid
+    }
+
+    /// 逐格数出 LtL 邻居数，作为前缀和实现的对照
+    fn ltl_brute_force(current: &Grid, rule: &LtlRule, wrap: bool) -> Grid {
+        let (r, n) = (rule.range as isize, GRID_SIZE as isize);
+        let mut next = [[DEAD; GRID_SIZE]; GRID_SIZE];
+        for y in 0..n {
+            for x in 0..n {
+                let mut neighbors = 0;
+                for dy in -r..=r {
+                    for dx in -r..=r {
+                        if (dx, dy) == (0, 0) && !rule.middle
+                            || rule.von_neumann && dx.abs() + dy.abs() > r
+                        {
+                            continue;
+                        }
+                        let (nx, ny) = if wrap {
+                            ((x + dx).rem_euclid(n), (y + dy).rem_euclid(n))
+                        } else {
+                            (x + dx, y + dy)
+                        };
+                        if (0..n).contains(&nx)
+                            && (0..n).contains(&ny)
+                            && current[ny as usize][nx as usize] == ALIVE
+                        {
+                            neighbors += 1;
+                        }
+                    }
+                }
+                next[y as usize][x as usize] =
+                    rule.apply(current[y as usize][x as usize], neighbors);
+            }
+        }
+        next
+    }
+
+    #[test]
+    fn ltl_rule_round_trip() {
+        let rule = LtlRule::parse("R5,C0,M1,S34..58,B34..45,NM").unwrap();
+        assert_eq!(rule.range, 5);
+        assert!(rule.middle && !rule.von_neumann);
+        assert_eq!((rule.survive, rule.birth), ((34, 58), (34, 45)));
+        assert_eq!(rule.to_string(), "R5,C0,M1,S34..58,B34..45,NM");
+        assert_eq!(
+            LtlRule::parse("R2,C3,M0,S4,B3..5,NN").unwrap().to_string(),
+            "R2,C3,M0,S4..4,B3..5,NN"
+        );
+        assert!(LtlRule::parse("R11,C0,M1,S1..2,B1..2,NM").is_none());
+        assert!(LtlRule::parse("R3,C0,M1,S1..2,NM").is_none());
+    }
+
+    #[test]
+    fn ltl_sums_match_brute_force() {
+        for (seed, text) in [
+            (1, "R5,C0,M1,S34..58,B34..45,NM"),
+            (2, "R4,C0,M0,S12..30,B14..20,NN"),
+            (3, "R1,C0,M0,S2..3,B3..3,NM"),
+            (4, "R10,C4,M1,S100..220,B90..150,NM"),
+            (5, "R7,C0,M1,S20..60,B25..40,NN"),
+        ] {
+            let rule = LtlRule::parse(text).unwrap();
+            let mut grid = soup(seed);
+            for wrap in [false, true] {
+                for _ in 0..3 {
+                    let expected = ltl_brute_force(&grid, &rule, wrap);
+                    grid = next_generation_ltl(&grid, &rule, wrap);
+                    assert_eq!(grid, expected, "{} wrap={}", text, wrap);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hensel_classes_are_symmetry_orbits() {
+        let classes = hensel_classes();
+        for mask in 0..256usize {
+            let n = mask.count_ones();
+            if n == 0 || n == 8 {
+                assert_eq!(classes[mask], None);
+                continue;
+            }
+            // 同一字母、同样邻居数的构型恰好是该构型旋转、翻转所得的全部构型
+            let orbit: Vec<usize> = symmetries(mask as u8).map(usize::from).to_vec();
+            for other in (0..256usize).filter(|m| m.count_ones() == n) {
+                assert_eq!(
+                    classes[other] == classes[mask],
+                    orbit.contains(&other),
+                    "{:08b} {:08b}",
+                    mask,
+                    other
+                );
+            }
+        }
+        // 1..=7 个邻居分别有 2、6、10、13、10、6、2 类
+        for n in 1..=7 {
+            let mut letters: Vec<char> = (0..256usize)
+                .filter(|m| m.count_ones() == n)
+                .filter_map(|m| classes[m])
+                .collect();
+            letters.sort();
+            letters.dedup();
+            assert_eq!(letters.len(), hensel_letters(n).len());
+        }
+    }
+
+    #[test]
+    fn isotropic_rule_round_trip() {
+        for text in [
+            "B2-a/S12",
+            "B2ce3/S23-k",
+            "B3/S23",
+            "B2n3/S2-i34q",
+            "B2/S/C3",
+        ] {
+            assert_eq!(IsotropicRule::parse(text).unwrap().to_string(), text);
+        }
+        assert!(IsotropicRule::parse("B2x/S").is_none());
+        assert!(IsotropicRule::parse("B2-/S").is_none());
+    }
+
+    #[test]
+    fn isotropic_totalistic_matches_life() {
+        let life = Rule::parse("B3/S23").unwrap();
+        let isotropic = Rule::Isotropic(IsotropicRule::parse("B3/S23").unwrap());
+        let mut grid = soup(7);
+        for topology in [Topology::Bounded, Topology::Torus] {
+            for _ in 0..10 {
+                let next = next_generation(&grid, &life, &Neighborhood::default(), topology);
+                let other = next_generation(&grid, &isotropic, &Neighborhood::default(), topology);
+                assert_eq!(next, other);
+                grid = next;
+            }
+        }
+    }
+
+    #[test]
+    fn b2_minus_a_diagonal_pair() {
+        // 对角的两个细胞：各有 1 个邻居而存活，两侧的格子为 2e 构型而出生，得到一个方块
+        let start = grid(&[(10, 10, ALIVE), (11, 11, ALIVE)]);
+        let block = grid(&[
+            (10, 10, ALIVE),
+            (11, 10, ALIVE),
+            (10, 11, ALIVE),
+            (11, 11, ALIVE),
+        ]);
+        let next = step(&start, "B2-a/S12");
+        assert_eq!(next, block);
+        // 方块的细胞有 3 个邻居而死亡，四周的格子都是 2a 构型，不出生
+        assert_eq!(step(&next, "B2-a/S12"), grid(&[]));
+        // 总和规则 B2/S12 则会在四周出生
+        assert_ne!(step(&next, "B2/S12"), grid(&[]));
+    }
+
+    #[test]
+    fn next_state_decays_without_overflow() {
+        assert_eq!(next_state(ALIVE, 4, false, false), 2);
+        assert_eq!(next_state(2, 4, false, false), 3);
+        assert_eq!(next_state(3, 4, false, false), DEAD);
+        // 超出状态数的格子（如外部会话中的 255）直接死亡
+        assert_eq!(next_state(255, 2, false, false), DEAD);
+        assert_eq!(next_state(255, 255, true, true), DEAD);
+    }
+
+    #[test]
+    fn kernel_rejects_large_weights() {
+        assert!(Kernel::parse("1 1 1/1 0 1/1 1 1").is_ok());
+        assert!(Kernel::parse("1 1 1/1 0 1/1 1 48").is_ok());
+        assert!(Kernel::parse("1 1 1/1 0 1/1 1 49").is_err());
+        assert!(Kernel::parse("4000000000 4000000000 1/1 0 1/1 1 1").is_err());
+    }
+
+    #[test]
+    fn hexagonal_torus_is_symmetric() {
+        let hex = Neighborhood::Hexagonal;
+        let neighbors = |x: usize, y: usize| -> Vec<(usize, usize)> {
+            hex.offsets(y % 2 == 1)
+                .iter()
+                .filter_map(|&(dx, dy, _)| neighbor_at(x, y, dx, dy, true, true))
+                .collect()
+        };
+        for y in 0..GRID_SIZE {
+            for x in 0..GRID_SIZE {
+                let mut around = neighbors(x, y);
+                around.sort_unstable();
+                around.dedup();
+                assert_eq!(around.len(), 6, "({}, {})", x, y);
+                for (nx, ny) in around {
+                    assert!(
+                        neighbors(nx, ny).contains(&(x, y)),
+                        "({}, {}) ({}, {})",
+                        x,
+                        y,
+                        nx,
+                        ny
+                    );
+                }
+            }
+        }
+    }
 }
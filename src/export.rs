@@ -0,0 +1,652 @@
+use crate::coloring::{decay_color, gradient_color};
+use crate::evolution::Rule;
+use crate::grid_lines::{GridLines, MAJOR_EVERY, label_step};
+use crate::heatmap::Heatmap;
+use crate::i18n::{Language, Localized};
+use crate::keybindings::Action;
+use crate::selection::Selection;
+use crate::theme::{Theme, ThemeColor, Themed, ThemedText};
+use crate::{ALIVE, CellStates, DEAD, GRID_SIZE, Grid, Simulation};
+use bevy::prelude::*;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Stdout, Write};
+use std::path::{Path, PathBuf};
+
+/// 导出图片时每格像素的可选值
+const SCALES: [u32; 4] = [4, 8, 16, 32];
+/// SVG 中坐标数字所占的边距
+const SVG_LABEL_MARGIN: u32 = 24;
+
+/// 导出设置：当前选用的每格像素（SCALES 中的下标）
+#[derive(Resource)]
+pub struct ExportSettings {
+    scale: usize,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self { scale: 1 }
+    }
+}
+
+impl ExportSettings {
+    pub fn scale(&self) -> u32 {
+        SCALES[self.scale]
+    }
+}
+
+/// 绘制导出图像的参数
+#[derive(Clone, Copy)]
+pub struct RenderOptions {
+    pub scale: u32,
+    pub grid_lines: bool,
+    pub major_lines: bool,
+}
+
+/// 导出图像用到的颜色（RGBA），按状态编号存放细胞颜色
+pub struct ExportPalette {
+    states: Vec<[u8; 4]>,
+    line: [u8; 4],
+    major: [u8; 4],
+    text: [u8; 4],
+}
+
+pub fn rgba(color: Color) -> [u8; 4] {
+    color.to_srgba().to_u8_array()
+}
+
+impl ExportPalette {
+    /// 与 render_cells_system 的黑白配色一致：规则文件的 @COLORS 优先，其次是衰亡色与主题色
+    pub fn new(theme: &Theme, rule: &Rule) -> Self {
+        let dead = theme.cell_color(false);
+        let dying = theme.color(ThemeColor::CellDying);
+        let states = (0..rule.states())
+            .map(|state| {
+                let table_color = match rule {
+                    Rule::Table(table) => table.color(state),
+                    _ => None,
+                };
+                rgba(match table_color {
+                    Some([r, g, b]) => Color::srgb_u8(r, g, b),
+                    None if state > ALIVE => decay_color(dying, dead, state, rule.states()),
+                    None => theme.cell_color(state == ALIVE),
+                })
+            })
+            .collect();
+        Self {
+            states,
+            line: rgba(theme.color(ThemeColor::GridLine)),
+            major: rgba(theme.color(ThemeColor::GridMajor)),
+            text: rgba(theme.color(ThemeColor::Text)),
+        }
+    }
+
+    /// 图像中可能出现的所有颜色（去重），用作 GIF 的调色板
+    pub fn colors(&self) -> Vec<[u8; 4]> {
+        let mut colors = Vec::new();
+        for color in self.states.iter().chain([&self.line, &self.major]) {
+            if !colors.contains(color) {
+                colors.push(*color);
+            }
+        }
+        colors
+    }
+
+    pub fn state(&self, state: u8) -> [u8; 4] {
+        // 超出规则状态数的格子按存活处理
+        self.states
+            .get(state as usize)
+            .copied()
+            .unwrap_or(self.states[ALIVE as usize])
+    }
+}
+
+/// 一帧 RGBA 图像，按行存储
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// 把网格中 min..=max 的矩形区域画成图像
+pub fn render_frame(
+    grid: &Grid,
+    min: IVec2,
+    max: IVec2,
+    options: RenderOptions,
+    palette: &ExportPalette,
+) -> Frame {
+    render_cells(min, max, options, palette, |x, y| palette.state(grid[y][x]))
+}
+
+/// 按 color(x, y) 给每格上色画出 min..=max 的区域（热力图等不按状态上色的图像也用它）
+/// 有网格线时线画在格子边界上（覆盖格子的第一行、第一列像素），右下再补一条边线
+pub fn render_cells(
+    min: IVec2,
+    max: IVec2,
+    options: RenderOptions,
+    palette: &ExportPalette,
+    color: impl Fn(usize, usize) -> [u8; 4],
+) -> Frame {
+    let scale = options.scale.max(1);
+    let size = (max - min + IVec2::ONE).as_uvec2() * scale;
+    let border = u32::from(options.grid_lines);
+    let (width, height) = (size.x + border, size.y + border);
+
+    // 像素 p 是否落在网格线上，是的话返回是否为粗线
+    let line = |p: u32, origin: i32| {
+        (options.grid_lines && p.is_multiple_of(scale)).then(|| {
+            let index = origin as usize + (p / scale) as usize;
+            options.major_lines && index.is_multiple_of(MAJOR_EVERY)
+        })
+    };
+
+    let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+    for py in 0..height {
+        let row_line = line(py, min.y);
+        for px in 0..width {
+            let color = match (row_line, line(px, min.x)) {
+                (Some(true), _) | (_, Some(true)) => palette.major,
+                (Some(false), _) | (_, Some(false)) => palette.line,
+                _ => {
+                    let x = min.x as usize + (px / scale) as usize;
+                    let y = min.y as usize + (py / scale) as usize;
+                    color(x, y)
+                }
+            };
+            pixels.extend_from_slice(&color);
+        }
+    }
+    Frame {
+        width,
+        height,
+        pixels,
+    }
+}
+
+/// 把一帧写成 PNG 文件
+pub fn write_png(path: &Path, frame: &Frame) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), frame.width, frame.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer
+        .write_image_data(&frame.pixels)
+        .map_err(|e| e.to_string())?;
+    writer.finish().map_err(|e| e.to_string())
+}
+
+fn hex([r, g, b, _]: [u8; 4]) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// 把网格中 min..=max 的矩形区域写成 SVG，每格边长 options.scale
+/// 同一行中相邻、状态相同的细胞合并成一个矩形，每种状态一条路径
+/// labels 为真时在上方与左侧留出边距，写出坐标数字
+pub fn render_svg(
+    grid: &Grid,
+    min: IVec2,
+    max: IVec2,
+    options: RenderOptions,
+    labels: bool,
+    palette: &ExportPalette,
+) -> String {
+    let scale = options.scale.max(1);
+    let size = (max - min + IVec2::ONE).as_uvec2();
+    let (width, height) = (size.x * scale, size.y * scale);
+    let margin = if labels { SVG_LABEL_MARGIN } else { 0 };
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width + margin,
+        h = height + margin
+    );
+    let _ = writeln!(
+        out,
+        r#"<g transform="translate({margin} {margin})" shape-rendering="crispEdges">"#
+    );
+    let _ = writeln!(
+        out,
+        r#"<rect width="{width}" height="{height}" fill="{}"/>"#,
+        hex(palette.state(DEAD))
+    );
+
+    // 按状态收集每行中的连续段
+    let mut paths: Vec<String> = vec![String::new(); palette.states.len().max(ALIVE as usize + 1)];
+    for y in 0..size.y {
+        let row = &grid[min.y as usize + y as usize][min.x as usize..=max.x as usize];
+        let mut x = 0;
+        while x < row.len() {
+            let state = row[x];
+            let run = row[x..].iter().take_while(|s| **s == state).count();
+            if state != DEAD {
+                let path = &mut paths[(state as usize).min(palette.states.len() - 1)];
+                let _ = write!(
+                    path,
+                    "M{} {}h{}v{}h-{}z",
+                    x as u32 * scale,
+                    y * scale,
+                    run as u32 * scale,
+                    scale,
+                    run as u32 * scale
+                );
+            }
+            x += run;
+        }
+    }
+    for (state, path) in paths.iter().enumerate() {
+        if !path.is_empty() {
+            let fill = hex(palette.state(state as u8));
+            let _ = writeln!(out, r#"<path fill="{fill}" d="{path}"/>"#);
+        }
+    }
+
+    if options.grid_lines {
+        let (mut minor, mut major) = (String::new(), String::new());
+        let is_major =
+            |index: u32| options.major_lines && (index as usize).is_multiple_of(MAJOR_EVERY);
+        for i in 0..=size.x {
+            let path = if is_major(min.x as u32 + i) {
+                &mut major
+            } else {
+                &mut minor
+            };
+            let _ = write!(path, "M{} 0V{}", i * scale, height);
+        }
+        for i in 0..=size.y {
+            let path = if is_major(min.y as u32 + i) {
+                &mut major
+            } else {
+                &mut minor
+            };
+            let _ = write!(path, "M0 {}H{}", i * scale, width);
+        }
+        for (path, color, stroke) in [(minor, palette.line, 1), (major, palette.major, 2)] {
+            if !path.is_empty() {
+                let _ = writeln!(
+                    out,
+                    r#"<path fill="none" stroke="{}" stroke-width="{stroke}" d="{path}"/>"#,
+                    hex(color)
+                );
+            }
+        }
+    }
+    out += "</g>\n";
+
+    if labels {
+        let step = label_step(scale as f32);
+        let _ = writeln!(
+            out,
+            r#"<g font-family="sans-serif" font-size="10" fill="{}">"#,
+            hex(palette.text)
+        );
+        for i in 0..size.x {
+            let index = min.x as u32 + i;
+            if (index as usize).is_multiple_of(step) {
+                let x = margin + i * scale + scale / 2;
+                let _ = writeln!(
+                    out,
+                    r#"<text x="{x}" y="{}" text-anchor="middle">{index}</text>"#,
+                    margin - 6
+                );
+            }
+        }
+        for i in 0..size.y {
+            let index = min.y as u32 + i;
+            if (index as usize).is_multiple_of(step) {
+                let y = margin + i * scale + scale / 2;
+                let _ = writeln!(
+                    out,
+                    r#"<text x="{}" y="{y}" text-anchor="end" dominant-baseline="central">{index}</text>"#,
+                    margin - 4
+                );
+            }
+        }
+        out += "</g>\n";
+    }
+    out += "</svg>\n";
+    out
+}
+
+/// 逐代输出帧的去处：目录中的编号 PNG，或写到标准输出的原始 RGB 流
+/// 原始流每帧为 width × height × 3 字节，可直接交给外部编码器（如 ffmpeg 的 rawvideo）
+pub enum FrameSink {
+    Sequence { dir: PathBuf, next: usize },
+    Raw(BufWriter<Stdout>),
+}
+
+impl FrameSink {
+    /// 输出编号 PNG，目录不存在时创建
+    pub fn sequence(dir: PathBuf) -> Result<Self, String> {
+        fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        Ok(FrameSink::Sequence { dir, next: 0 })
+    }
+
+    pub fn raw() -> Self {
+        FrameSink::Raw(BufWriter::new(io::stdout()))
+    }
+
+    pub fn write(&mut self, frame: &Frame) -> Result<(), String> {
+        match self {
+            FrameSink::Sequence { dir, next } => {
+                write_png(&dir.join(format!("frame_{:06}.png", next)), frame)?;
+                *next += 1;
+                Ok(())
+            }
+            FrameSink::Raw(out) => {
+                for pixel in frame.pixels.chunks_exact(4) {
+                    out.write_all(&pixel[..3]).map_err(|e| e.to_string())?;
+                }
+                // 每帧及时刷出，管道另一端不必等缓冲区写满
+                out.flush().map_err(|e| e.to_string())
+            }
+        }
+    }
+}
+
+/// 演化时逐代输出的帧（由命令行参数打开），每帧都是整个网格
+/// 帧尺寸在启动时确定，保证原始流中每帧大小一致
+#[derive(Resource)]
+pub struct FrameOutput {
+    pub sink: Option<FrameSink>,
+    pub options: RenderOptions,
+}
+
+impl FrameOutput {
+    /// 按当前配色绘制网格并输出一帧；没有打开输出时什么也不做
+    pub fn write(&mut self, grid: &Grid, palette: &ExportPalette) -> Result<(), String> {
+        let Some(sink) = self.sink.as_mut() else {
+            return Ok(());
+        };
+        let max = IVec2::splat(GRID_SIZE as i32 - 1);
+        sink.write(&render_frame(grid, IVec2::ZERO, max, self.options, palette))
+    }
+
+    /// 演化出新一代时由 step_generation 调用；出错后停止输出
+    pub fn emit(&mut self, grid: &Grid, theme: &Theme, rule: &Rule) {
+        if self.sink.is_none() {
+            return;
+        }
+        if let Err(e) = self.write(grid, &ExportPalette::new(theme, rule)) {
+            error!("输出帧失败: {}", e);
+            self.sink = None;
+        }
+    }
+}
+
+/// 启动时先输出初始网格，之后每演化一代输出一帧
+pub fn frame_output_start_system(
+    mut frame_output: ResMut<FrameOutput>,
+    cell_states: Res<CellStates>,
+    theme: Res<Theme>,
+    sim: Res<Simulation>,
+) {
+    frame_output.emit(&cell_states.states, &theme, &sim.rule);
+}
+
+/// 导出面板上的按钮
+#[derive(Component, Clone, Copy)]
+pub enum ExportButton {
+    Png,
+    Svg,
+    Scale,
+}
+
+#[derive(Component)]
+pub struct ExportScaleText;
+
+/// 右侧导出按钮（与选区按钮同样的小按钮）
+pub fn export_ui(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    parent.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: font.clone(),
+                font_size: 18.0,
+                color: Color::BLACK,
+            },
+        ),
+        Localized("label.export"),
+        ThemedText(ThemeColor::Text),
+    ));
+
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                flex_wrap: FlexWrap::Wrap,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|row| {
+            for button in [ExportButton::Png, ExportButton::Svg, ExportButton::Scale] {
+                let width = match button {
+                    ExportButton::Scale => 90.0,
+                    _ => 50.0,
+                };
+                row.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(width),
+                            height: Val::Px(26.0),
+                            margin: UiRect::all(Val::Px(2.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    button,
+                    Themed(ThemeColor::SelectionButton),
+                ))
+                .with_children(|btn| {
+                    let mut text = btn.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 14.0,
+                                color: Color::WHITE,
+                            },
+                        ),
+                        ThemedText(ThemeColor::ButtonText),
+                    ));
+                    match button {
+                        ExportButton::Png => text.insert(Localized("export.png")),
+                        ExportButton::Svg => text.insert(Localized("export.svg")),
+                        ExportButton::Scale => text.insert(ExportScaleText),
+                    };
+                });
+            }
+        });
+}
+
+/// 点击 PNG / SVG 按钮或按快捷键把网格（有选区时只导出选区）写成图片
+/// 使用当前主题，网格线跟随界面上的网格线设置，SVG 中的坐标数字跟随标尺设置
+/// 热力图打开时 PNG 中画的是热力图
+#[allow(clippy::too_many_arguments)]
+pub fn export_system(
+    interaction_query: Query<(&Interaction, &ExportButton), Changed<Interaction>>,
+    mut actions: EventReader<Action>,
+    mut settings: ResMut<ExportSettings>,
+    cell_states: Res<CellStates>,
+    sim: Res<Simulation>,
+    theme: Res<Theme>,
+    lines: Res<GridLines>,
+    selection: Res<Selection>,
+    language: Res<Language>,
+    heatmap: Res<Heatmap>,
+    mut text: Query<&mut Text, With<ExportScaleText>>,
+) {
+    let (mut png, mut svg) = (false, false);
+    for action in actions.read() {
+        match action {
+            Action::ExportPng => png = true,
+            Action::ExportSvg => svg = true,
+            _ => {}
+        }
+    }
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            ExportButton::Png => png = true,
+            ExportButton::Svg => svg = true,
+            ExportButton::Scale => settings.scale = (settings.scale + 1) % SCALES.len(),
+        }
+    }
+
+    if settings.is_changed() || language.is_changed() {
+        for mut text in &mut text {
+            text.sections[0].value = language.trf("export.scale", settings.scale());
+        }
+    }
+
+    if !png && !svg {
+        return;
+    }
+    let (min, max) = selection
+        .rect()
+        .unwrap_or((IVec2::ZERO, IVec2::splat(GRID_SIZE as i32 - 1)));
+    let options = RenderOptions {
+        scale: settings.scale(),
+        grid_lines: lines.visible,
+        major_lines: lines.major,
+    };
+    let palette = ExportPalette::new(&theme, &sim.rule);
+    if png {
+        let (frame, path) = if heatmap.enabled() {
+            let dead = theme.cell_color(false);
+            let frame = render_cells(min, max, options, &palette, |x, y| {
+                rgba(gradient_color(dead, heatmap.heat[y][x]))
+            });
+            (frame, format!("lifegame_heatmap_{}.png", sim.generation))
+        } else {
+            let frame = render_frame(&cell_states.states, min, max, options, &palette);
+            (frame, format!("lifegame_{}.png", sim.generation))
+        };
+        match write_png(Path::new(&path), &frame) {
+            Ok(()) => info!("已导出 {}（{}×{} 像素）", path, frame.width, frame.height),
+            Err(e) => error!("导出 PNG 失败: {}", e),
+        }
+    }
+    if svg {
+        let text = render_svg(
+            &cell_states.states,
+            min,
+            max,
+            options,
+            lines.rulers,
+            &palette,
+        );
+        let path = format!("lifegame_{}.svg", sim.generation);
+        match fs::write(&path, text) {
+            Ok(()) => info!("已导出 {}", path),
+            Err(e) => error!("导出 SVG 失败: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn palette() -> ExportPalette {
+        ExportPalette::new(&Theme::light(), &Rule::default())
+    }
+
+    fn options(scale: u32, grid_lines: bool) -> RenderOptions {
+        RenderOptions {
+            scale,
+            grid_lines,
+            major_lines: true,
+        }
+    }
+
+    fn pixel(frame: &Frame, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * frame.width + x) * 4) as usize;
+        frame.pixels[i..i + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn frame_size_and_border() {
+        let grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+        let (min, max) = (IVec2::new(1, 2), IVec2::new(3, 3));
+        let frame = render_frame(&grid, min, max, options(4, false), &palette());
+        assert_eq!((frame.width, frame.height), (12, 8));
+        assert_eq!(frame.pixels.len(), 12 * 8 * 4);
+        // 网格线画在格子的第一行、第一列，右下多出一条边线
+        let frame = render_frame(&grid, min, max, options(4, true), &palette());
+        assert_eq!((frame.width, frame.height), (13, 9));
+        let palette = palette();
+        assert_eq!(pixel(&frame, 0, 1), palette.line);
+        assert_eq!(pixel(&frame, 12, 8), palette.line);
+        assert_eq!(pixel(&frame, 1, 1), palette.state(DEAD));
+    }
+
+    #[test]
+    fn frame_major_lines_and_offset() {
+        let mut grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+        grid[5][9] = ALIVE;
+        let palette = palette();
+        let (min, max) = (IVec2::new(8, 5), IVec2::new(12, 6));
+        let frame = render_frame(&grid, min, max, options(2, true), &palette);
+        // 第 10 列为粗线，第 9 列为细线
+        assert_eq!(pixel(&frame, 4, 1), palette.major);
+        assert_eq!(pixel(&frame, 2, 1), palette.line);
+        // 选区左上角为 (8, 5)，第 9 列第 5 行的细胞画在第二格
+        assert_eq!(pixel(&frame, 3, 1), palette.state(ALIVE));
+        assert_eq!(pixel(&frame, 1, 1), palette.state(DEAD));
+
+        let plain = RenderOptions {
+            major_lines: false,
+            ..options(2, true)
+        };
+        let frame = render_frame(&grid, min, max, plain, &palette);
+        assert_eq!(pixel(&frame, 4, 1), palette.line);
+    }
+
+    #[test]
+    fn svg_merges_runs_per_state() {
+        let mut grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+        for x in [2, 3, 5] {
+            grid[3][x] = ALIVE;
+        }
+        grid[3][6] = 2;
+        grid[4][2] = ALIVE;
+        let palette = ExportPalette::new(&Theme::light(), &Rule::parse("B2/S/C3").unwrap());
+        let (min, max) = (IVec2::new(2, 3), IVec2::new(6, 3));
+        let svg = render_svg(&grid, min, max, options(1, false), false, &palette);
+        assert!(svg.contains(r#"width="5" height="1""#));
+        // 坐标相对选区左上角，选区外的细胞不画
+        assert!(svg.contains(r#"d="M0 0h2v1h-2zM3 0h1v1h-1z""#));
+        assert!(svg.contains(r#"d="M4 0h1v1h-1z""#));
+        assert_eq!(svg.matches("<path").count(), 2);
+
+        let svg = render_svg(&grid, min, max, options(3, false), false, &palette);
+        assert!(svg.contains(r#"d="M0 0h6v3h-6zM9 0h3v3h-3z""#));
+    }
+
+    #[test]
+    fn svg_grid_lines_and_labels() {
+        let grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+        let (min, max) = (IVec2::new(8, 0), IVec2::new(11, 0));
+        let svg = render_svg(&grid, min, max, options(10, true), false, &palette());
+        // 第 10 列与第 0 行为粗线
+        assert!(svg.contains(r#"stroke-width="2" d="M20 0V10M0 0H40""#));
+        assert!(svg.contains(r#"stroke-width="1" d="M0 0V10M10 0V10M30 0V10M40 0V10M0 10H40""#));
+
+        let svg = render_svg(&grid, min, max, options(10, false), true, &palette());
+        let size = 40 + SVG_LABEL_MARGIN;
+        assert!(svg.contains(&format!(r#"width="{}""#, size)));
+        assert!(svg.contains(&format!(r#"translate({0} {0})"#, SVG_LABEL_MARGIN)));
+        assert!(svg.contains(">10</text>"));
+    }
+}
@@ -0,0 +1,281 @@
+use crate::i18n::Localized;
+use crate::keybindings::Action;
+use crate::theme::{Theme, ThemeColor, Themed, ThemedText};
+use crate::{GRID_SIZE, GridCamera};
+use bevy::prelude::*;
+
+/// 格子小于该像素时自动隐藏普通网格线
+const MIN_LINE_CELL_PIXELS: f32 = 6.0;
+/// 每隔多少格画一条粗线
+pub const MAJOR_EVERY: usize = 10;
+/// 标尺宽度（列标尺的高度、行标尺的宽度）
+const RULER_SIZE: f32 = 16.0;
+/// 标尺上相邻两个数字之间至少的像素
+const MIN_LABEL_SPACING: f32 = 24.0;
+
+/// 网格线与标尺的显示设置
+#[derive(Resource)]
+pub struct GridLines {
+    pub visible: bool,
+    pub major: bool,
+    pub rulers: bool,
+}
+
+impl Default for GridLines {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            major: true,
+            rulers: false,
+        }
+    }
+}
+
+/// 一条网格线：vertical 为竖线（位于第 index 列左侧），否则为横线
+#[derive(Component)]
+pub struct GridLine {
+    index: usize,
+    vertical: bool,
+}
+
+/// 标尺条（列标尺在上，行标尺在左）
+#[derive(Component)]
+pub struct Ruler;
+
+/// 标尺上的坐标数字
+#[derive(Component)]
+pub struct RulerLabel {
+    index: usize,
+    column: bool,
+}
+
+/// 网格线开关按钮
+#[derive(Component)]
+pub struct GridLinesButton;
+
+#[derive(Component)]
+pub struct GridLinesButtonText;
+
+/// 坐标数字的间隔：选择 1、2、5、10… 中最小的间隔，使数字不重叠
+pub fn label_step(cell: f32) -> usize {
+    [1, 2, 5, 10, 20]
+        .into_iter()
+        .find(|&s| s as f32 * cell >= MIN_LABEL_SPACING)
+        .unwrap_or(GRID_SIZE)
+}
+
+/// 网格线节点，作为网格内容的子节点随相机平移
+pub fn grid_lines_overlay(parent: &mut ChildBuilder) {
+    for vertical in [true, false] {
+        for index in 0..=GRID_SIZE {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        ..default()
+                    },
+                    z_index: ZIndex::Local(1),
+                    ..default()
+                },
+                GridLine { index, vertical },
+            ));
+        }
+    }
+}
+
+/// 沿网格外框上边与左边的坐标标尺，默认隐藏
+pub fn rulers(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    for column in [true, false] {
+        parent
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        display: Display::None,
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(0.0),
+                        top: Val::Px(0.0),
+                        width: if column {
+                            Val::Percent(100.0)
+                        } else {
+                            Val::Px(RULER_SIZE)
+                        },
+                        height: if column {
+                            Val::Px(RULER_SIZE)
+                        } else {
+                            Val::Percent(100.0)
+                        },
+                        overflow: Overflow::clip(),
+                        ..default()
+                    },
+                    z_index: ZIndex::Local(3),
+                    ..default()
+                },
+                Ruler,
+                Themed(ThemeColor::Panel),
+            ))
+            .with_children(|ruler| {
+                for index in 0..GRID_SIZE {
+                    ruler.spawn((
+                        TextBundle::from_section(
+                            index.to_string(),
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 11.0,
+                                color: Color::BLACK,
+                            },
+                        )
+                        .with_style(Style {
+                            position_type: PositionType::Absolute,
+                            ..default()
+                        }),
+                        RulerLabel { index, column },
+                        ThemedText(ThemeColor::Text),
+                    ));
+                }
+            });
+    }
+}
+
+fn button_label(lines: &GridLines) -> &'static str {
+    if lines.visible {
+        "button.grid_lines_on"
+    } else {
+        "button.grid_lines_off"
+    }
+}
+
+pub fn grid_lines_ui(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(140.0),
+                    height: Val::Px(40.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            GridLinesButton,
+            Themed(ThemeColor::ModeButton),
+        ))
+        .with_children(|r| {
+            r.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 18.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                GridLinesButtonText,
+                Localized(button_label(&GridLines::default())),
+                ThemedText(ThemeColor::ButtonText),
+            ));
+        });
+}
+
+/// 点击按钮或按快捷键切换网格线、粗线与标尺
+pub fn grid_lines_toggle_system(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<GridLinesButton>)>,
+    mut actions: EventReader<Action>,
+    mut lines: ResMut<GridLines>,
+    mut text: Query<&mut Localized, With<GridLinesButtonText>>,
+) {
+    for interaction in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            lines.visible = !lines.visible;
+        }
+    }
+    for action in actions.read() {
+        match action {
+            Action::ToggleGridLines => lines.visible = !lines.visible,
+            Action::ToggleMajorLines => lines.major = !lines.major,
+            Action::ToggleRulers => lines.rulers = !lines.rulers,
+            _ => {}
+        }
+    }
+
+    if lines.is_changed() {
+        for mut text in &mut text {
+            text.0 = button_label(&lines);
+        }
+    }
+}
+
+/// 根据缩放、平移与设置摆放网格线和标尺
+/// 格子太小时隐藏普通网格线，只保留每 10 格一条的粗线；六边形显示时不画网格线
+#[allow(clippy::type_complexity)]
+pub fn grid_lines_system(
+    camera: Res<GridCamera>,
+    settings: Res<GridLines>,
+    theme: Res<Theme>,
+    mut lines: Query<(&GridLine, &mut Style, &mut BackgroundColor)>,
+    mut rulers: Query<&mut Style, (With<Ruler>, Without<GridLine>, Without<RulerLabel>)>,
+    mut labels: Query<(&RulerLabel, &mut Style), (Without<GridLine>, Without<Ruler>)>,
+) {
+    if !camera.is_changed() && !settings.is_changed() && !theme.is_changed() {
+        return;
+    }
+    let cell = camera.cell_size();
+    let extent = cell * GRID_SIZE as f32;
+    let visible = settings.visible && !camera.hex;
+    let show_minor = visible && cell >= MIN_LINE_CELL_PIXELS;
+
+    for (line, mut style, mut color) in &mut lines {
+        let major = visible && settings.major && line.index % MAJOR_EVERY == 0;
+        if !major && !show_minor {
+            style.display = Display::None;
+            continue;
+        }
+        let width = if major { 2.0 } else { 1.0 };
+        let pos = line.index as f32 * cell - width / 2.0;
+        style.display = Display::Flex;
+        if line.vertical {
+            style.left = Val::Px(pos);
+            style.top = Val::Px(0.0);
+            style.width = Val::Px(width);
+            style.height = Val::Px(extent);
+        } else {
+            style.left = Val::Px(0.0);
+            style.top = Val::Px(pos);
+            style.width = Val::Px(extent);
+            style.height = Val::Px(width);
+        }
+        color.0 = theme.color(if major {
+            ThemeColor::GridMajor
+        } else {
+            ThemeColor::GridLine
+        });
+    }
+
+    for mut style in &mut rulers {
+        style.display = if settings.rulers {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+    if !settings.rulers {
+        return;
+    }
+
+    let step = label_step(cell);
+    for (label, mut style) in &mut labels {
+        if label.index % step != 0 {
+            style.display = Display::None;
+            continue;
+        }
+        style.display = Display::Flex;
+        let pos = label.index as f32 * cell;
+        if label.column {
+            style.left = Val::Px(pos + camera.offset.x + 2.0);
+            style.top = Val::Px(1.0);
+        } else {
+            style.left = Val::Px(2.0);
+            style.top = Val::Px(pos + camera.offset.y + 1.0);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use crate::export::{ExportPalette, FrameOutput, FrameSink, RenderOptions};
+use crate::metrics::Metrics;
+use crate::rules::RuleLibrary;
+use crate::session::Session;
+use crate::theme::Theme;
+use crate::{
+    ALIVE, CellStates, DEAD, GRID_SIZE, History, Simulation, clamp_states, set_cell_state,
+    step_generation,
+};
+use std::fs;
+use std::path::PathBuf;
+
+/// 命令行用法
+const USAGE: &str = "用法: lifegame [选项]
+  --headless          不打开窗口，直接演化并输出
+  --generations N     无窗口模式下演化的代数（默认 100）
+  --session FILE      无窗口模式下从会话文件读入网格与规则（默认随机填充）
+  --rule RULE         无窗口模式下使用的规则（覆盖会话中的规则）
+  --frames DIR        每代输出一张编号 PNG 到 DIR
+  --raw               每代输出一帧原始 RGB 到标准输出
+  --scale N           输出帧每格的像素（默认 8）
+  --grid-lines        输出帧画上网格线
+  --metrics FILE      无窗口模式结束时把每代指标写到 FILE（.json 为 JSON，否则为 CSV）";
+
+/// 命令行参数；--frames 与 --raw 在窗口模式下同样有效，随演化逐代输出
+pub struct Args {
+    pub headless: bool,
+    generations: u64,
+    session: Option<PathBuf>,
+    rule: Option<String>,
+    frames: Option<PathBuf>,
+    raw: bool,
+    scale: u32,
+    grid_lines: bool,
+    metrics: Option<String>,
+}
+
+impl Args {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args {
+            headless: false,
+            generations: 100,
+            session: None,
+            rule: None,
+            frames: None,
+            raw: false,
+            scale: 8,
+            grid_lines: false,
+            metrics: None,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("{} 缺少参数\n{}", arg, USAGE))
+            };
+            match arg.as_str() {
+                "--headless" => parsed.headless = true,
+                "--generations" => {
+                    parsed.generations = value()?.parse().map_err(|_| "代数无效".to_string())?
+                }
+                "--session" => parsed.session = Some(value()?.into()),
+                "--rule" => parsed.rule = Some(value()?),
+                "--frames" => parsed.frames = Some(value()?.into()),
+                "--raw" => parsed.raw = true,
+                "--scale" => {
+                    parsed.scale = value()?
+                        .parse()
+                        .ok()
+                        .filter(|s| (1..=64).contains(s))
+                        .ok_or("每格像素应为 1 到 64")?
+                }
+                "--grid-lines" => parsed.grid_lines = true,
+                "--metrics" => parsed.metrics = Some(value()?),
+                "--help" | "-h" => return Err(USAGE.to_string()),
+                _ => return Err(format!("未知参数: {}\n{}", arg, USAGE)),
+            }
+        }
+        if parsed.frames.is_some() && parsed.raw {
+            return Err("--frames 与 --raw 只能选一个".to_string());
+        }
+        Ok(parsed)
+    }
+
+    /// 按参数打开逐代输出的帧
+    pub fn frame_output(&self) -> Result<FrameOutput, String> {
+        let sink = match (&self.frames, self.raw) {
+            (Some(dir), _) => Some(FrameSink::sequence(dir.clone())?),
+            (None, true) => Some(FrameSink::raw()),
+            (None, false) => None,
+        };
+        Ok(FrameOutput {
+            sink,
+            options: RenderOptions {
+                scale: self.scale,
+                grid_lines: self.grid_lines,
+                major_lines: self.grid_lines,
+            },
+        })
+    }
+}
+
+/// 无窗口模式：读入初始网格，用与界面相同的 step_generation 演化若干代，每代输出一帧
+/// 标准输出可能被原始帧流占用，提示信息一律写到标准错误
+pub fn run_headless(args: &Args) -> Result<(), String> {
+    let mut rules = RuleLibrary::default();
+    rules.load_files();
+
+    let mut sim = Simulation::default();
+    let mut history = History::default();
+    let mut cell_states = CellStates::default();
+    let mut metrics = Metrics::default();
+    match &args.session {
+        Some(path) => {
+            let text =
+                fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let session = Session::from_text(&text, &rules)?;
+            for y in 0..GRID_SIZE {
+                for x in 0..GRID_SIZE {
+                    set_cell_state(&mut cell_states, x, y, session.grid[y][x]);
+                }
+            }
+            sim.generation = session.generation;
+            sim.rule = session.rule;
+            sim.neighborhood = session.neighborhood;
+            sim.topology = session.topology;
+        }
+        None => {
+            for y in 0..GRID_SIZE {
+                for x in 0..GRID_SIZE {
+                    let state = if rand::random() { ALIVE } else { DEAD };
+                    set_cell_state(&mut cell_states, x, y, state);
+                }
+            }
+        }
+    }
+    if let Some(rule) = &args.rule {
+        sim.rule = rules
+            .parse(rule)
+            .ok_or_else(|| format!("规则无效: {}", rule))?;
+        clamp_states(&mut cell_states, sim.rule.states());
+    }
+
+    let mut output = args.frame_output()?;
+    let palette = ExportPalette::new(&Theme::default(), &sim.rule);
+    if output.sink.is_some() {
+        let side = GRID_SIZE as u32 * args.scale + u32::from(args.grid_lines);
+        eprintln!("每帧 {}×{} 像素", side, side);
+    }
+    output.write(&cell_states.states, &palette)?;
+    for _ in 0..args.generations {
+        step_generation(&mut sim, &mut history, &mut cell_states, &mut metrics, None);
+        output.write(&cell_states.states, &palette)?;
+    }
+
+    if let Some(path) = &args.metrics {
+        metrics.save(path)?;
+        eprintln!("已写出 {} 代的指标到 {}", metrics.samples.len(), path);
+    }
+
+    let alive = cell_states
+        .states
+        .iter()
+        .flatten()
+        .filter(|s| **s == ALIVE)
+        .count();
+    eprintln!(
+        "规则 {}：演化到第 {} 代，活细胞 {} 个",
+        sim.rule, sim.generation, alive
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Args, String> {
+        Args::parse(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn defaults_and_options() {
+        let args = parse("").unwrap();
+        assert!(!args.headless && !args.raw && !args.grid_lines);
+        assert_eq!((args.generations, args.scale), (100, 8));
+
+        let args = parse(
+            "--headless --generations 50 --rule B36/S23 --frames out --scale 2 \
+             --grid-lines --session s.txt --metrics m.json",
+        )
+        .unwrap();
+        assert!(args.headless && args.grid_lines);
+        assert_eq!((args.generations, args.scale), (50, 2));
+        assert_eq!(args.rule.as_deref(), Some("B36/S23"));
+        assert_eq!(args.frames, Some(PathBuf::from("out")));
+        assert_eq!(args.session, Some(PathBuf::from("s.txt")));
+        assert_eq!(args.metrics.as_deref(), Some("m.json"));
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        for args in [
+            "--generations",
+            "--generations -1",
+            "--scale 0",
+            "--scale 65",
+            "--frames out --raw",
+            "--bogus",
+            "--help",
+        ] {
+            assert!(parse(args).is_err(), "{}", args);
+        }
+        assert!(parse("--scale 64 --raw").is_ok());
+    }
+}
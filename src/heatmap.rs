@@ -0,0 +1,314 @@
+use crate::i18n::Language;
+use crate::keybindings::Action;
+use crate::theme::{ThemeColor, Themed, ThemedText};
+use crate::{ALIVE, CellStates, GRID_SIZE, Grid, Simulation};
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+/// 统计窗口的可选长度（代）
+const WINDOWS: [usize; 4] = [16, 64, 256, 1024];
+
+/// 热力图统计的内容
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeatmapMode {
+    Off,
+    // 窗口内存活的代数占比
+    Alive,
+    // 窗口内状态发生变化的次数占比
+    Changes,
+}
+
+impl HeatmapMode {
+    fn key(self) -> &'static str {
+        match self {
+            HeatmapMode::Off => "heatmap.off",
+            HeatmapMode::Alive => "heatmap.alive",
+            HeatmapMode::Changes => "heatmap.changes",
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            HeatmapMode::Off => HeatmapMode::Alive,
+            HeatmapMode::Alive => HeatmapMode::Changes,
+            HeatmapMode::Changes => HeatmapMode::Off,
+        }
+    }
+}
+
+/// 最近若干代的网格与由此统计出的热度（0..=1），打开时叠加在细胞颜色上显示
+/// 关闭时也照常记录网格，打开后立即有完整窗口的统计
+#[derive(Resource)]
+pub struct Heatmap {
+    pub mode: HeatmapMode,
+    window: usize,
+    grids: VecDeque<Grid>,
+    last: Option<u64>,
+    pub heat: [[f32; GRID_SIZE]; GRID_SIZE],
+}
+
+impl Default for Heatmap {
+    fn default() -> Self {
+        Self {
+            mode: HeatmapMode::Off,
+            window: 1,
+            grids: VecDeque::new(),
+            last: None,
+            heat: [[0.0; GRID_SIZE]; GRID_SIZE],
+        }
+    }
+}
+
+impl Heatmap {
+    pub fn enabled(&self) -> bool {
+        self.mode != HeatmapMode::Off
+    }
+
+    fn window_len(&self) -> usize {
+        WINDOWS[self.window]
+    }
+
+    /// 记录新的一代；代数不连续（清空、撤销、跳转、打开会话）时重新开始统计
+    fn push(&mut self, generation: u64, grid: &Grid) {
+        if self.last.is_some_and(|last| generation != last + 1) {
+            self.grids.clear();
+        }
+        self.last = Some(generation);
+        self.grids.push_back(*grid);
+        // 变化次数需要窗口外再多一代作比较
+        while self.grids.len() > self.window_len() + 1 {
+            self.grids.pop_front();
+        }
+    }
+
+    fn recompute(&mut self) {
+        // 缩短窗口后，多出的旧网格要到下一代才丢弃，这里先跳过
+        let len = self.grids.len();
+        let mut counts = [[0u32; GRID_SIZE]; GRID_SIZE];
+        let total = match self.mode {
+            HeatmapMode::Off => return,
+            HeatmapMode::Alive => {
+                let grids = self.grids.range(len.saturating_sub(self.window_len())..);
+                let mut total = 0;
+                for grid in grids {
+                    total += 1;
+                    for (count, state) in counts.iter_mut().flatten().zip(grid.iter().flatten()) {
+                        *count += u32::from(*state == ALIVE);
+                    }
+                }
+                total
+            }
+            HeatmapMode::Changes => {
+                let grids = self
+                    .grids
+                    .range(len.saturating_sub(self.window_len() + 1)..);
+                let mut total = 0;
+                for (before, after) in grids.clone().zip(grids.skip(1)) {
+                    total += 1;
+                    let cells = before.iter().flatten().zip(after.iter().flatten());
+                    for (count, (a, b)) in counts.iter_mut().flatten().zip(cells) {
+                        *count += u32::from(a != b);
+                    }
+                }
+                total
+            }
+        };
+        for (heat, count) in self.heat.iter_mut().flatten().zip(counts.iter().flatten()) {
+            *heat = if total == 0 {
+                0.0
+            } else {
+                *count as f32 / total as f32
+            };
+        }
+    }
+}
+
+/// 热力图按钮
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum HeatmapButton {
+    Mode,
+    Window,
+}
+
+#[derive(Component)]
+pub struct HeatmapButtonText(HeatmapButton);
+
+/// 左上方的热力图按钮：统计内容与窗口长度，两个一行
+pub fn heatmap_ui(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(144.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|row| {
+            for button in [HeatmapButton::Mode, HeatmapButton::Window] {
+                row.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(68.0),
+                            height: Val::Px(24.0),
+                            margin: UiRect::all(Val::Px(2.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    button,
+                    Themed(ThemeColor::ModeButton),
+                ))
+                .with_children(|btn| {
+                    btn.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 13.0,
+                                color: Color::WHITE,
+                            },
+                        ),
+                        HeatmapButtonText(button),
+                        ThemedText(ThemeColor::ButtonText),
+                    ));
+                });
+            }
+        });
+}
+
+/// 切换热力图、记录每一代并刷新热度
+#[allow(clippy::too_many_arguments)]
+pub fn heatmap_system(
+    interaction_query: Query<(&Interaction, &HeatmapButton), Changed<Interaction>>,
+    mut actions: EventReader<Action>,
+    mut heatmap: ResMut<Heatmap>,
+    cell_states: Res<CellStates>,
+    sim: Res<Simulation>,
+    language: Res<Language>,
+    mut texts: Query<(&HeatmapButtonText, &mut Text)>,
+) {
+    let mut settings_changed = false;
+    for action in actions.read() {
+        if *action == Action::CycleHeatmap {
+            heatmap.mode = heatmap.mode.next();
+            settings_changed = true;
+        }
+    }
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            HeatmapButton::Mode => heatmap.mode = heatmap.mode.next(),
+            HeatmapButton::Window => heatmap.window = (heatmap.window + 1) % WINDOWS.len(),
+        }
+        settings_changed = true;
+    }
+    if settings_changed {
+        info!(
+            "热力图: {:?}，窗口 {} 代",
+            heatmap.mode,
+            heatmap.window_len()
+        );
+    }
+
+    // 只在出现新的一代时记录，关闭时不触发变更检测
+    let new_generation = heatmap.last != Some(sim.generation);
+    if new_generation {
+        heatmap
+            .bypass_change_detection()
+            .push(sim.generation, &cell_states.states);
+    }
+    if heatmap.enabled() && (new_generation || settings_changed) {
+        heatmap.recompute();
+    }
+
+    if settings_changed || language.is_changed() {
+        for (text, mut value) in &mut texts {
+            value.sections[0].value = match text.0 {
+                HeatmapButton::Mode => language.tr(heatmap.mode.key()).to_string(),
+                HeatmapButton::Window => language.trf("heatmap.window", heatmap.window_len()),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEAD;
+
+    const EMPTY: Grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+
+    fn grid(cells: &[(usize, usize)]) -> Grid {
+        let mut grid = EMPTY;
+        for &(x, y) in cells {
+            grid[y][x] = ALIVE;
+        }
+        grid
+    }
+
+    #[test]
+    fn alive_share() {
+        let mut heatmap = Heatmap {
+            mode: HeatmapMode::Alive,
+            window: 0,
+            ..default()
+        };
+        heatmap.push(0, &grid(&[(0, 0), (1, 1)]));
+        heatmap.push(1, &grid(&[(0, 0)]));
+        heatmap.push(2, &grid(&[(0, 0)]));
+        heatmap.push(3, &EMPTY);
+        heatmap.recompute();
+        assert_eq!(heatmap.heat[0][0], 0.75);
+        assert_eq!(heatmap.heat[1][1], 0.25);
+        assert_eq!(heatmap.heat[2][2], 0.0);
+    }
+
+    #[test]
+    fn change_share() {
+        let mut heatmap = Heatmap {
+            mode: HeatmapMode::Changes,
+            window: 0,
+            ..default()
+        };
+        heatmap.push(0, &EMPTY);
+        heatmap.push(1, &grid(&[(0, 0)]));
+        heatmap.push(2, &EMPTY);
+        heatmap.push(3, &grid(&[(1, 1)]));
+        heatmap.push(4, &grid(&[(1, 1)]));
+        heatmap.recompute();
+        // 四次比较中 (0,0) 变化两次，(1,1) 变化一次
+        assert_eq!(heatmap.heat[0][0], 0.5);
+        assert_eq!(heatmap.heat[1][1], 0.25);
+        assert_eq!(heatmap.heat[2][2], 0.0);
+    }
+
+    #[test]
+    fn discontinuity_resets() {
+        let mut heatmap = Heatmap {
+            mode: HeatmapMode::Alive,
+            window: 0,
+            ..default()
+        };
+        heatmap.push(0, &grid(&[(0, 0)]));
+        heatmap.push(1, &grid(&[(0, 0)]));
+        // 向前跳转与回退都不能混入之前的网格
+        heatmap.push(5, &EMPTY);
+        heatmap.recompute();
+        assert_eq!(heatmap.heat[0][0], 0.0);
+        heatmap.push(6, &grid(&[(0, 0)]));
+        heatmap.push(2, &grid(&[(1, 1)]));
+        heatmap.recompute();
+        assert_eq!(heatmap.heat[0][0], 0.0);
+        assert_eq!(heatmap.heat[1][1], 1.0);
+
+        // 没有相邻的网格时变化次数为零
+        heatmap.mode = HeatmapMode::Changes;
+        heatmap.recompute();
+        assert_eq!(heatmap.heat[1][1], 0.0);
+    }
+}
@@ -0,0 +1,529 @@
+use crate::keybindings::Action;
+use crate::theme::{ThemeColor, Themed, ThemedText};
+use bevy::prelude::*;
+use std::fmt::Display;
+
+/// 界面语言
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Language {
+    #[default]
+    ZhCn,
+    EnUs,
+}
+
+impl Language {
+    const ALL: [Language; 2] = [Language::ZhCn, Language::EnUs];
+
+    /// 语言代码（BCP 47）
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::ZhCn => "zh-CN",
+            Language::EnUs => "en-US",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|l| l.code().eq_ignore_ascii_case(code))
+    }
+
+    fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|l| *l == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    fn table(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::ZhCn => ZH_CN,
+            Language::EnUs => EN_US,
+        }
+    }
+
+    /// 查找翻译；当前语言缺少时回退到中文，仍找不到则原样返回 key
+    /// （用户主题名等不在字符串表中的文字因此会直接显示）
+    pub fn tr(self, key: &str) -> &str {
+        let find = |table: &'static [(&'static str, &'static str)]| {
+            table.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+        };
+        find(self.table()).or_else(|| find(ZH_CN)).unwrap_or(key)
+    }
+
+    /// 查找翻译并用 arg 替换其中的 `{}`
+    pub fn trf(self, key: &str, arg: impl Display) -> String {
+        self.tr(key).replacen("{}", &arg.to_string(), 1)
+    }
+}
+
+/// 简体中文
+const ZH_CN: &[(&str, &str)] = &[
+    ("language.name", "中文"),
+    ("label.language", "语言：{}"),
+    (
+        "label.rules",
+        "游戏规则：\n1. 存活细胞周围有 2 或 3 个邻居则继续存活。\n2. 死亡细胞周围有 3 个邻居则复活。",
+    ),
+    ("button.start", "开始演化"),
+    ("button.stop", "停止演化"),
+    ("button.clear", "清空网格"),
+    ("button.save_session", "保存会话"),
+    ("button.open_session", "打开会话"),
+    ("button.grid_lines_on", "网格线：开"),
+    ("button.grid_lines_off", "网格线：关"),
+    ("button.lenia_on", "连续模式：开"),
+    ("button.lenia_off", "连续模式：关"),
+    ("lenia.radius", "核半径：{}"),
+    ("lenia.mu", "生长中心 μ：{}"),
+    ("lenia.sigma", "生长宽度 σ：{}"),
+    ("lenia.dt", "时间步长 Δt：{}"),
+    ("record.start", "录制动画"),
+    ("record.stop", "停止录制（{} 帧）"),
+    ("record.delay", "{} 毫秒"),
+    ("record.length", "{} 代"),
+    ("record.length_unlimited", "不限代数"),
+    ("record.loops", "播放 {} 次"),
+    ("record.loop_forever", "无限循环"),
+    ("metrics.csv", "指标 CSV"),
+    ("metrics.json", "指标 JSON"),
+    ("heatmap.off", "热力图：关"),
+    ("heatmap.alive", "热力：存活"),
+    ("heatmap.changes", "热力：变化"),
+    ("heatmap.window", "窗口 {} 代"),
+    ("census.title", "物体普查（从第 {} 代开始）"),
+    ("census.rule", "规则：{}"),
+    ("census.settled", "稳定用时：{} 代"),
+    ("census.period", "整体周期：{}"),
+    ("census.empty", "没有物体"),
+    ("census.unknown", "无法识别"),
+    ("census.heat", "热度 {}"),
+    ("census.failed", "普查失败：{}"),
+    ("census.export", "导出文本"),
+    ("census.close", "关闭"),
+    ("identify.title", "图案识别（第 {} 代）"),
+    ("identify.title_selection", "图案识别（第 {} 代，选区内）"),
+    ("identify.name", "名称：{}"),
+    ("identify.unnamed", "名称：未收录"),
+    ("identify.code", "代码：{}"),
+    ("identify.transient", "进入周期前演化：{} 代"),
+    ("identify.failed", "无法识别：{}"),
+    ("timeline.generation", "第 {} 代"),
+    ("timeline.branch", "分支 {}"),
+    ("compare.population", "人口：{}"),
+    ("compare.bounded", "有界"),
+    ("compare.torus", "环面"),
+    ("compare.diff", "不同的格子：{}"),
+    ("compare.only_here", "只在这一侧存活"),
+    ("compare.only_there", "只在另一侧存活"),
+    ("compare.rule", "B 换规则"),
+    ("compare.topology", "B 换边界"),
+    ("compare.close", "关闭"),
+    ("object.kind.still_life", "静物"),
+    ("object.kind.oscillator", "振荡器"),
+    ("object.kind.spaceship", "飞船"),
+    ("object.block", "方块"),
+    ("object.beehive", "蜂巢"),
+    ("object.loaf", "面包"),
+    ("object.boat", "小船"),
+    ("object.ship", "船"),
+    ("object.tub", "浴缸"),
+    ("object.pond", "池塘"),
+    ("object.barge", "驳船"),
+    ("object.long_boat", "长船"),
+    ("object.eater", "食者"),
+    ("object.blinker", "闪烁器"),
+    ("object.toad", "蟾蜍"),
+    ("object.beacon", "信标"),
+    ("object.pulsar", "脉冲星"),
+    ("object.pentadecathlon", "十五项振荡器"),
+    ("object.glider", "滑翔机"),
+    ("object.lwss", "轻型飞船"),
+    ("object.mwss", "中型飞船"),
+    ("object.hwss", "重型飞船"),
+    ("label.color_mode", "配色：{}"),
+    ("color_mode.binary", "黑白"),
+    ("palette.fire", "火焰"),
+    ("palette.ocean", "海洋"),
+    ("palette.forest", "森林"),
+    ("label.theme", "主题：{}"),
+    ("theme.light", "浅色"),
+    ("theme.dark", "深色"),
+    ("theme.high_contrast", "高对比度"),
+    ("label.neighborhood", "邻域：{}"),
+    ("neighborhood.moore", "摩尔"),
+    ("neighborhood.von_neumann", "冯·诺依曼"),
+    ("neighborhood.hex", "六边形"),
+    ("neighborhood.custom", "自定义"),
+    ("label.rule", "规则：{}"),
+    ("rule.conway", "康威"),
+    ("rule.highlife", "HighLife"),
+    ("rule.brians_brain", "Brian's Brain"),
+    ("rule.star_wars", "星球大战"),
+    ("rule.just_friends", "Just Friends"),
+    ("rule.bosco", "Bosco"),
+    ("rule.majority", "多数表决"),
+    ("label.patterns", "示例图案："),
+    ("pattern.block", "方块"),
+    ("pattern.glider", "滑翔机"),
+    ("pattern.pulsar", "脉冲星"),
+    ("pattern.kaiying", "慨影"),
+    ("pattern.shuttle", "穿梭机"),
+    ("pattern.cshuttle", "环状穿梭机"),
+    ("label.tools", "绘图工具："),
+    ("tool.pencil", "画笔"),
+    ("tool.line", "直线"),
+    ("tool.rect", "矩形"),
+    ("tool.filled_rect", "实心矩形"),
+    ("tool.ellipse", "椭圆"),
+    ("tool.select", "选择"),
+    ("label.selection", "选区："),
+    ("selection.copy", "复制"),
+    ("selection.cut", "剪切"),
+    ("selection.paste", "粘贴"),
+    ("selection.random_fill", "随机填充"),
+    ("selection.clear_inside", "清除内部"),
+    ("selection.clear_outside", "清除外部"),
+    ("selection.rotate", "旋转90°"),
+    ("selection.step", "单独演化"),
+    ("selection.flip_horizontal", "水平翻转"),
+    ("selection.flip_vertical", "垂直翻转"),
+    ("label.export", "导出："),
+    ("export.png", "PNG"),
+    ("export.svg", "SVG"),
+    ("export.scale", "{} 像素/格"),
+    ("help.title", "快捷键（可在 keybindings.cfg 中修改）"),
+    (
+        "help.mouse",
+        "鼠标左键拖拽：绘制 / 擦除（由起点格子决定）\n鼠标右键拖拽：擦除\n鼠标滚轮：以光标为中心缩放",
+    ),
+    ("help.patterns", "按顺序加载示例图案"),
+    ("action.toggle_run", "开始 / 停止演化"),
+    ("action.step", "单步演化一代"),
+    ("action.clear", "清空网格"),
+    ("action.save", "保存会话"),
+    ("action.open", "打开会话"),
+    ("action.undo", "撤销"),
+    ("action.redo", "重做"),
+    ("action.copy", "复制选区"),
+    ("action.cut", "剪切选区"),
+    ("action.paste", "粘贴"),
+    ("action.clear_selection", "清除选区内部"),
+    ("action.clear_outside", "清除选区外部"),
+    ("action.deselect", "取消选区"),
+    ("action.pattern", "加载第 {} 个示例图案"),
+    ("action.pan_up", "视图上移"),
+    ("action.pan_down", "视图下移"),
+    ("action.pan_left", "视图左移"),
+    ("action.pan_right", "视图右移"),
+    ("action.zoom_in", "放大"),
+    ("action.zoom_out", "缩小"),
+    ("action.speed_up", "加快演化"),
+    ("action.speed_down", "减慢演化"),
+    ("action.cycle_color_mode", "切换配色（黑白 / 按年龄）"),
+    ("action.cycle_theme", "切换主题"),
+    ("action.cycle_language", "切换语言"),
+    ("action.cycle_neighborhood", "切换邻域"),
+    ("action.cycle_rule", "切换规则"),
+    ("action.toggle_lenia", "开关连续模式（Lenia）"),
+    ("action.export_png", "导出 PNG 图片（有选区时只导出选区）"),
+    ("action.export_svg", "导出 SVG 矢量图（有选区时只导出选区）"),
+    ("action.export_metrics", "把每代的人口等指标导出为 CSV"),
+    ("action.cycle_heatmap", "切换热力图（关 / 存活 / 变化）"),
+    ("action.census", "演化到稳定后普查各类物体"),
+    ("action.identify", "识别当前图案（有选区时只看选区）"),
+    ("action.toggle_timeline", "显示 / 隐藏时间轴"),
+    ("action.timeline_back", "时间轴后退一代"),
+    ("action.timeline_forward", "时间轴前进一代"),
+    ("action.toggle_compare", "显示 / 隐藏对比视图"),
+    ("action.toggle_record", "开始 / 停止录制 GIF 或 APNG 动画"),
+    ("action.toggle_grid_lines", "显示 / 隐藏网格线"),
+    ("action.toggle_major_lines", "显示 / 隐藏每 10 格的粗线"),
+    ("action.toggle_rulers", "显示 / 隐藏坐标标尺"),
+    ("action.toggle_help", "显示 / 隐藏帮助"),
+];
+
+/// 美式英语；按钮较窄，部分文字用了缩写
+const EN_US: &[(&str, &str)] = &[
+    ("language.name", "English"),
+    ("label.language", "Lang: {}"),
+    (
+        "label.rules",
+        "Rules:\n1. A live cell with 2 or 3 live neighbours survives.\n2. A dead cell with exactly 3 live neighbours comes alive.",
+    ),
+    ("button.start", "Start"),
+    ("button.stop", "Stop"),
+    ("button.clear", "Clear grid"),
+    ("button.save_session", "Save session"),
+    ("button.open_session", "Open session"),
+    ("button.grid_lines_on", "Grid lines: on"),
+    ("button.grid_lines_off", "Grid lines: off"),
+    ("button.lenia_on", "Continuous: on"),
+    ("button.lenia_off", "Continuous: off"),
+    ("lenia.radius", "Kernel radius: {}"),
+    ("lenia.mu", "Growth center μ: {}"),
+    ("lenia.sigma", "Growth width σ: {}"),
+    ("lenia.dt", "Time step Δt: {}"),
+    ("record.start", "Record"),
+    ("record.stop", "Stop ({} frames)"),
+    ("record.delay", "{} ms"),
+    ("record.length", "{} gens"),
+    ("record.length_unlimited", "No limit"),
+    ("record.loops", "Play ×{}"),
+    ("record.loop_forever", "Loop forever"),
+    ("metrics.csv", "Stats CSV"),
+    ("metrics.json", "Stats JSON"),
+    ("heatmap.off", "Heatmap: off"),
+    ("heatmap.alive", "Heat: alive"),
+    ("heatmap.changes", "Heat: changes"),
+    ("heatmap.window", "{} gens"),
+    ("census.title", "Object census (from generation {})"),
+    ("census.rule", "Rule: {}"),
+    ("census.settled", "Settled after {} generations"),
+    ("census.period", "Overall period: {}"),
+    ("census.empty", "No objects"),
+    ("census.unknown", "unrecognised"),
+    ("census.heat", "heat {}"),
+    ("census.failed", "Census failed: {}"),
+    ("census.export", "Export text"),
+    ("census.close", "Close"),
+    ("identify.title", "Pattern identification (generation {})"),
+    ("identify.title_selection", "Pattern identification (generation {}, selection)"),
+    ("identify.name", "Name: {}"),
+    ("identify.unnamed", "Name: not in the library"),
+    ("identify.code", "Code: {}"),
+    ("identify.transient", "Generations before periodic: {}"),
+    ("identify.failed", "Cannot identify: {}"),
+    ("timeline.generation", "Gen {}"),
+    ("timeline.branch", "Branch {}"),
+    ("compare.population", "Population: {}"),
+    ("compare.bounded", "Bounded"),
+    ("compare.torus", "Torus"),
+    ("compare.diff", "Differing cells: {}"),
+    ("compare.only_here", "Alive only on this side"),
+    ("compare.only_there", "Alive only on the other side"),
+    ("compare.rule", "B: next rule"),
+    ("compare.topology", "B: edges"),
+    ("compare.close", "Close"),
+    ("object.kind.still_life", "still life"),
+    ("object.kind.oscillator", "oscillator"),
+    ("object.kind.spaceship", "spaceship"),
+    ("object.block", "block"),
+    ("object.beehive", "beehive"),
+    ("object.loaf", "loaf"),
+    ("object.boat", "boat"),
+    ("object.ship", "ship"),
+    ("object.tub", "tub"),
+    ("object.pond", "pond"),
+    ("object.barge", "barge"),
+    ("object.long_boat", "long boat"),
+    ("object.eater", "eater 1"),
+    ("object.blinker", "blinker"),
+    ("object.toad", "toad"),
+    ("object.beacon", "beacon"),
+    ("object.pulsar", "pulsar"),
+    ("object.pentadecathlon", "pentadecathlon"),
+    ("object.glider", "glider"),
+    ("object.lwss", "lightweight spaceship"),
+    ("object.mwss", "middleweight spaceship"),
+    ("object.hwss", "heavyweight spaceship"),
+    ("label.color_mode", "Colors: {}"),
+    ("color_mode.binary", "B/W"),
+    ("palette.fire", "Fire"),
+    ("palette.ocean", "Ocean"),
+    ("palette.forest", "Forest"),
+    ("label.theme", "Theme: {}"),
+    ("theme.light", "Light"),
+    ("theme.dark", "Dark"),
+    ("theme.high_contrast", "Hi-contrast"),
+    ("label.neighborhood", "{}"),
+    ("neighborhood.moore", "Moore"),
+    ("neighborhood.von_neumann", "von Neumann"),
+    ("neighborhood.hex", "Hexagonal"),
+    ("neighborhood.custom", "Custom kernel"),
+    ("label.rule", "Rule: {}"),
+    ("rule.conway", "Conway"),
+    ("rule.highlife", "HighLife"),
+    ("rule.brians_brain", "Brian's Brain"),
+    ("rule.star_wars", "Star Wars"),
+    ("rule.just_friends", "Just Friends"),
+    ("rule.bosco", "Bosco"),
+    ("rule.majority", "Majority"),
+    ("label.patterns", "Patterns:"),
+    ("pattern.block", "Block"),
+    ("pattern.glider", "Glider"),
+    ("pattern.pulsar", "Pulsar"),
+    ("pattern.kaiying", "Kaiying"),
+    ("pattern.shuttle", "Shuttle"),
+    ("pattern.cshuttle", "Ring shuttle"),
+    ("label.tools", "Tools:"),
+    ("tool.pencil", "Pencil"),
+    ("tool.line", "Line"),
+    ("tool.rect", "Rectangle"),
+    ("tool.filled_rect", "Filled rectangle"),
+    ("tool.ellipse", "Ellipse"),
+    ("tool.select", "Select"),
+    ("label.selection", "Selection:"),
+    ("selection.copy", "Copy"),
+    ("selection.cut", "Cut"),
+    ("selection.paste", "Paste"),
+    ("selection.random_fill", "Random"),
+    ("selection.clear_inside", "Clear in"),
+    ("selection.clear_outside", "Clear out"),
+    ("selection.rotate", "Rotate 90°"),
+    ("selection.step", "Step"),
+    ("selection.flip_horizontal", "Flip H"),
+    ("selection.flip_vertical", "Flip V"),
+    ("label.export", "Export:"),
+    ("export.png", "PNG"),
+    ("export.svg", "SVG"),
+    ("export.scale", "{} px/cell"),
+    ("help.title", "Shortcuts (edit keybindings.cfg to change)"),
+    (
+        "help.mouse",
+        "Left drag: draw / erase (decided by the first cell)\nRight drag: erase\nMouse wheel: zoom around the cursor",
+    ),
+    ("help.patterns", "load example patterns in order"),
+    ("action.toggle_run", "Start / stop evolution"),
+    ("action.step", "Advance one generation"),
+    ("action.clear", "Clear the grid"),
+    ("action.save", "Save session"),
+    ("action.open", "Open session"),
+    ("action.undo", "Undo"),
+    ("action.redo", "Redo"),
+    ("action.copy", "Copy selection"),
+    ("action.cut", "Cut selection"),
+    ("action.paste", "Paste"),
+    ("action.clear_selection", "Clear inside selection"),
+    ("action.clear_outside", "Clear outside selection"),
+    ("action.deselect", "Deselect"),
+    ("action.pattern", "Load example pattern {}"),
+    ("action.pan_up", "Pan up"),
+    ("action.pan_down", "Pan down"),
+    ("action.pan_left", "Pan left"),
+    ("action.pan_right", "Pan right"),
+    ("action.zoom_in", "Zoom in"),
+    ("action.zoom_out", "Zoom out"),
+    ("action.speed_up", "Speed up"),
+    ("action.speed_down", "Slow down"),
+    ("action.cycle_color_mode", "Cycle colors (B/W / by age)"),
+    ("action.cycle_theme", "Cycle theme"),
+    ("action.cycle_language", "Switch language"),
+    ("action.cycle_neighborhood", "Cycle neighbourhood"),
+    ("action.cycle_rule", "Cycle rule"),
+    ("action.toggle_lenia", "Toggle continuous (Lenia) mode"),
+    ("action.export_png", "Export a PNG image (selection only if any)"),
+    ("action.export_svg", "Export an SVG drawing (selection only if any)"),
+    ("action.export_metrics", "Export per-generation metrics as CSV"),
+    ("action.cycle_heatmap", "Cycle heatmap (off / alive / changes)"),
+    ("action.census", "Run until stable and count the objects"),
+    ("action.identify", "What is this? Identify the pattern (or selection)"),
+    ("action.toggle_timeline", "Show / hide the timeline"),
+    ("action.timeline_back", "Timeline: one generation back"),
+    ("action.timeline_forward", "Timeline: one generation forward"),
+    ("action.toggle_compare", "Show / hide the comparison view"),
+    ("action.toggle_record", "Start / stop recording a GIF or APNG animation"),
+    ("action.toggle_grid_lines", "Show / hide grid lines"),
+    (
+        "action.toggle_major_lines",
+        "Show / hide major lines every 10 cells",
+    ),
+    ("action.toggle_rulers", "Show / hide coordinate rulers"),
+    ("action.toggle_help", "Show / hide this help"),
+];
+
+/// 文字内容跟随界面语言，参数为字符串表中的 key
+/// 修改 key 后文字会自动刷新（如开始 / 停止按钮）
+#[derive(Component, Clone, Copy)]
+pub struct Localized(pub &'static str);
+
+/// 语言切换按钮
+#[derive(Component)]
+pub struct LanguageButton;
+
+#[derive(Component)]
+pub struct LanguageButtonText;
+
+/// 启动时读取环境变量 LIFEGAME_LANG（如 en-US），未设置时使用中文
+pub fn load_language(mut language: ResMut<Language>) {
+    let Ok(code) = std::env::var("LIFEGAME_LANG") else {
+        return;
+    };
+    match Language::from_code(&code) {
+        Some(lang) => *language = lang,
+        None => warn!("未知的语言代码 {}，使用 {}", code, language.code()),
+    }
+}
+
+pub fn language_ui(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(140.0),
+                    height: Val::Px(40.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            LanguageButton,
+            Themed(ThemeColor::ModeButton),
+        ))
+        .with_children(|r| {
+            r.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 18.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                LanguageButtonText,
+                ThemedText(ThemeColor::ButtonText),
+            ));
+        });
+}
+
+/// 点击按钮或按快捷键循环切换语言
+pub fn language_switch_system(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<LanguageButton>)>,
+    mut actions: EventReader<Action>,
+    mut language: ResMut<Language>,
+) {
+    let pressed = interaction_query
+        .iter()
+        .filter(|i| **i == Interaction::Pressed)
+        .count();
+    let cycles = pressed
+        + actions
+            .read()
+            .filter(|a| **a == Action::CycleLanguage)
+            .count();
+    if cycles == 0 {
+        return;
+    }
+    for _ in 0..cycles {
+        *language = language.next();
+    }
+    info!("界面语言: {}", language.code());
+}
+
+/// 语言变化时刷新全部 Localized 文字，新生成或修改了 key 的节点也在这里更新
+pub fn localize_system(
+    language: Res<Language>,
+    mut texts: Query<(Ref<Localized>, &mut Text)>,
+    mut button: Query<&mut Text, (With<LanguageButtonText>, Without<Localized>)>,
+) {
+    let all = language.is_changed();
+    for (localized, mut text) in &mut texts {
+        if all || localized.is_changed() {
+            text.sections[0].value = language.tr(localized.0).to_string();
+        }
+    }
+    if all {
+        for mut text in &mut button {
+            text.sections[0].value = language.trf("label.language", language.tr("language.name"));
+        }
+    }
+}
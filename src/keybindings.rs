@@ -0,0 +1,681 @@
+use crate::i18n::Language;
+use crate::theme::{ThemeColor, Themed, ThemedText};
+use bevy::prelude::*;
+use std::fs;
+
+/// 按键配置文件，位于当前工作目录；不存在时使用默认按键
+pub const KEYBINDINGS_FILE: &str = "keybindings.cfg";
+
+/// 可以绑定按键的操作，按键与界面按钮都以 Action 事件的形式发出
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    ToggleRun,
+    Step,
+    Clear,
+    Save,
+    Open,
+    Undo,
+    Redo,
+    Copy,
+    Cut,
+    Paste,
+    ClearSelection,
+    ClearOutside,
+    Deselect,
+    // 按右侧列表顺序加载第 n 个示例图案（从 0 开始）
+    Pattern(usize),
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    ZoomIn,
+    ZoomOut,
+    SpeedUp,
+    SpeedDown,
+    CycleColorMode,
+    CycleTheme,
+    CycleLanguage,
+    CycleNeighborhood,
+    CycleRule,
+    ToggleLenia,
+    ExportPng,
+    ExportSvg,
+    ExportMetrics,
+    CycleHeatmap,
+    Census,
+    Identify,
+    ToggleTimeline,
+    TimelineBack,
+    TimelineForward,
+    ToggleCompare,
+    ToggleRecord,
+    ToggleGridLines,
+    ToggleMajorLines,
+    ToggleRulers,
+    ToggleHelp,
+}
+
+const PATTERN_SLOTS: usize = 9;
+
+impl Action {
+    /// 所有操作，顺序即帮助界面中的显示顺序
+    pub fn all() -> Vec<Action> {
+        let mut actions = vec![
+            Action::ToggleRun,
+            Action::Step,
+            Action::Clear,
+            Action::SpeedUp,
+            Action::SpeedDown,
+            Action::PanUp,
+            Action::PanDown,
+            Action::PanLeft,
+            Action::PanRight,
+            Action::ZoomIn,
+            Action::ZoomOut,
+            Action::Undo,
+            Action::Redo,
+            Action::Copy,
+            Action::Cut,
+            Action::Paste,
+            Action::ClearSelection,
+            Action::ClearOutside,
+            Action::Deselect,
+            Action::Save,
+            Action::Open,
+            Action::CycleColorMode,
+            Action::CycleTheme,
+            Action::CycleLanguage,
+            Action::CycleNeighborhood,
+            Action::CycleRule,
+            Action::ToggleLenia,
+            Action::ExportPng,
+            Action::ExportSvg,
+            Action::ExportMetrics,
+            Action::CycleHeatmap,
+            Action::Census,
+            Action::Identify,
+            Action::ToggleTimeline,
+            Action::TimelineBack,
+            Action::TimelineForward,
+            Action::ToggleCompare,
+            Action::ToggleRecord,
+            Action::ToggleGridLines,
+            Action::ToggleMajorLines,
+            Action::ToggleRulers,
+            Action::ToggleHelp,
+        ];
+        actions.extend((0..PATTERN_SLOTS).map(Action::Pattern));
+        actions
+    }
+
+    /// 配置文件中使用的名称
+    pub fn name(self) -> String {
+        let name = match self {
+            Action::ToggleRun => "toggle_run",
+            Action::Step => "step",
+            Action::Clear => "clear",
+            Action::Save => "save",
+            Action::Open => "open",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Copy => "copy",
+            Action::Cut => "cut",
+            Action::Paste => "paste",
+            Action::ClearSelection => "clear_selection",
+            Action::ClearOutside => "clear_outside",
+            Action::Deselect => "deselect",
+            Action::Pattern(n) => return format!("pattern_{}", n + 1),
+            Action::PanUp => "pan_up",
+            Action::PanDown => "pan_down",
+            Action::PanLeft => "pan_left",
+            Action::PanRight => "pan_right",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::SpeedUp => "speed_up",
+            Action::SpeedDown => "speed_down",
+            Action::CycleColorMode => "cycle_color_mode",
+            Action::CycleTheme => "cycle_theme",
+            Action::CycleLanguage => "cycle_language",
+            Action::CycleNeighborhood => "cycle_neighborhood",
+            Action::CycleRule => "cycle_rule",
+            Action::ToggleLenia => "toggle_lenia",
+            Action::ExportPng => "export_png",
+            Action::ExportSvg => "export_svg",
+            Action::ExportMetrics => "export_metrics",
+            Action::CycleHeatmap => "cycle_heatmap",
+            Action::Census => "census",
+            Action::Identify => "identify",
+            Action::ToggleTimeline => "toggle_timeline",
+            Action::TimelineBack => "timeline_back",
+            Action::TimelineForward => "timeline_forward",
+            Action::ToggleCompare => "toggle_compare",
+            Action::ToggleRecord => "toggle_record",
+            Action::ToggleGridLines => "toggle_grid_lines",
+            Action::ToggleMajorLines => "toggle_major_lines",
+            Action::ToggleRulers => "toggle_rulers",
+            Action::ToggleHelp => "toggle_help",
+        };
+        name.to_string()
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::all().into_iter().find(|a| a.name() == name)
+    }
+
+    /// 帮助界面中的说明（见 i18n.rs 中的 action.* 字符串）
+    pub fn description(self, language: Language) -> String {
+        match self {
+            Action::Pattern(n) => language.trf("action.pattern", n + 1),
+            _ => language.tr(&format!("action.{}", self.name())).to_string(),
+        }
+    }
+
+    /// 按住时每帧触发（平移），其余操作只在按下时触发一次
+    fn repeats(self) -> bool {
+        matches!(
+            self,
+            Action::PanUp | Action::PanDown | Action::PanLeft | Action::PanRight
+        )
+    }
+}
+
+/// 一个按键组合：修饰键必须与实际按下的完全一致
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub key: KeyCode,
+    pub ctrl: bool,
+    pub shift: bool,
+}
+
+impl KeyBinding {
+    const fn key(key: KeyCode) -> Self {
+        Self {
+            key,
+            ctrl: false,
+            shift: false,
+        }
+    }
+
+    const fn ctrl(key: KeyCode) -> Self {
+        Self {
+            key,
+            ctrl: true,
+            shift: false,
+        }
+    }
+
+    const fn ctrl_shift(key: KeyCode) -> Self {
+        Self {
+            key,
+            ctrl: true,
+            shift: true,
+        }
+    }
+
+    const fn shift(key: KeyCode) -> Self {
+        Self {
+            key,
+            ctrl: false,
+            shift: true,
+        }
+    }
+
+    /// 解析 "Ctrl+Shift+Z" 形式
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut binding = KeyBinding::key(KeyCode::Space);
+        let mut key = None;
+        for part in text.split('+').map(str::trim) {
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => binding.ctrl = true,
+                "shift" => binding.shift = true,
+                _ => key = Some(key_from_name(part)?),
+            }
+        }
+        binding.key = key?;
+        Some(binding)
+    }
+}
+
+impl std::fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        match KEY_NAMES.iter().find(|(_, key)| *key == self.key) {
+            Some((name, _)) => write!(f, "{}", name),
+            None => write!(f, "{:?}", self.key),
+        }
+    }
+}
+
+/// 配置文件中可用的按键名
+const KEY_NAMES: &[(&str, KeyCode)] = &[
+    ("A", KeyCode::KeyA),
+    ("B", KeyCode::KeyB),
+    ("C", KeyCode::KeyC),
+    ("D", KeyCode::KeyD),
+    ("E", KeyCode::KeyE),
+    ("F", KeyCode::KeyF),
+    ("G", KeyCode::KeyG),
+    ("H", KeyCode::KeyH),
+    ("I", KeyCode::KeyI),
+    ("J", KeyCode::KeyJ),
+    ("K", KeyCode::KeyK),
+    ("L", KeyCode::KeyL),
+    ("M", KeyCode::KeyM),
+    ("N", KeyCode::KeyN),
+    ("O", KeyCode::KeyO),
+    ("P", KeyCode::KeyP),
+    ("Q", KeyCode::KeyQ),
+    ("R", KeyCode::KeyR),
+    ("S", KeyCode::KeyS),
+    ("T", KeyCode::KeyT),
+    ("U", KeyCode::KeyU),
+    ("V", KeyCode::KeyV),
+    ("W", KeyCode::KeyW),
+    ("X", KeyCode::KeyX),
+    ("Y", KeyCode::KeyY),
+    ("Z", KeyCode::KeyZ),
+    ("0", KeyCode::Digit0),
+    ("1", KeyCode::Digit1),
+    ("2", KeyCode::Digit2),
+    ("3", KeyCode::Digit3),
+    ("4", KeyCode::Digit4),
+    ("5", KeyCode::Digit5),
+    ("6", KeyCode::Digit6),
+    ("7", KeyCode::Digit7),
+    ("8", KeyCode::Digit8),
+    ("9", KeyCode::Digit9),
+    ("F1", KeyCode::F1),
+    ("F2", KeyCode::F2),
+    ("F3", KeyCode::F3),
+    ("F4", KeyCode::F4),
+    ("F5", KeyCode::F5),
+    ("F6", KeyCode::F6),
+    ("F7", KeyCode::F7),
+    ("F8", KeyCode::F8),
+    ("F9", KeyCode::F9),
+    ("F10", KeyCode::F10),
+    ("F11", KeyCode::F11),
+    ("F12", KeyCode::F12),
+    ("Space", KeyCode::Space),
+    ("Enter", KeyCode::Enter),
+    ("Escape", KeyCode::Escape),
+    ("Tab", KeyCode::Tab),
+    ("Backspace", KeyCode::Backspace),
+    ("Delete", KeyCode::Delete),
+    ("Insert", KeyCode::Insert),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("Up", KeyCode::ArrowUp),
+    ("Down", KeyCode::ArrowDown),
+    ("Left", KeyCode::ArrowLeft),
+    ("Right", KeyCode::ArrowRight),
+    // 主键盘上的 = / + 键与 - 键
+    ("Equal", KeyCode::Equal),
+    ("Minus", KeyCode::Minus),
+    ("NumpadAdd", KeyCode::NumpadAdd),
+    ("NumpadSubtract", KeyCode::NumpadSubtract),
+];
+
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    KEY_NAMES
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, key)| *key)
+}
+
+/// 操作与按键的对应表，一个操作可以有多个按键
+#[derive(Resource)]
+pub struct KeyBindings {
+    bindings: Vec<(Action, KeyBinding)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        use KeyCode::*;
+        let mut bindings = vec![
+            (Action::ToggleRun, KeyBinding::key(Space)),
+            (Action::Step, KeyBinding::key(KeyN)),
+            (Action::Clear, KeyBinding::key(KeyC)),
+            (Action::SpeedUp, KeyBinding::key(Equal)),
+            (Action::SpeedUp, KeyBinding::shift(Equal)),
+            (Action::SpeedUp, KeyBinding::key(NumpadAdd)),
+            (Action::SpeedDown, KeyBinding::key(Minus)),
+            (Action::SpeedDown, KeyBinding::key(NumpadSubtract)),
+            (Action::PanUp, KeyBinding::key(ArrowUp)),
+            (Action::PanDown, KeyBinding::key(ArrowDown)),
+            (Action::PanLeft, KeyBinding::key(ArrowLeft)),
+            (Action::PanRight, KeyBinding::key(ArrowRight)),
+            (Action::ZoomIn, KeyBinding::ctrl(Equal)),
+            (Action::ZoomOut, KeyBinding::ctrl(Minus)),
+            (Action::Undo, KeyBinding::ctrl(KeyZ)),
+            (Action::Redo, KeyBinding::ctrl_shift(KeyZ)),
+            (Action::Copy, KeyBinding::ctrl(KeyC)),
+            (Action::Cut, KeyBinding::ctrl(KeyX)),
+            (Action::Paste, KeyBinding::ctrl(KeyV)),
+            (Action::ClearSelection, KeyBinding::key(Delete)),
+            (Action::ClearOutside, KeyBinding::shift(Delete)),
+            (Action::Deselect, KeyBinding::key(Escape)),
+            (Action::Save, KeyBinding::ctrl(KeyS)),
+            (Action::Open, KeyBinding::ctrl(KeyO)),
+            (Action::CycleColorMode, KeyBinding::key(KeyM)),
+            (Action::CycleTheme, KeyBinding::key(KeyT)),
+            (Action::CycleLanguage, KeyBinding::key(KeyL)),
+            (Action::CycleNeighborhood, KeyBinding::key(KeyK)),
+            (Action::CycleRule, KeyBinding::key(KeyU)),
+            (Action::ToggleLenia, KeyBinding::key(KeyF)),
+            (Action::ExportPng, KeyBinding::key(KeyP)),
+            (Action::ExportSvg, KeyBinding::shift(KeyP)),
+            (Action::ExportMetrics, KeyBinding::key(KeyX)),
+            (Action::CycleHeatmap, KeyBinding::key(KeyH)),
+            (Action::Census, KeyBinding::key(KeyI)),
+            (Action::Identify, KeyBinding::shift(KeyI)),
+            (Action::ToggleTimeline, KeyBinding::key(KeyB)),
+            (Action::TimelineBack, KeyBinding::shift(ArrowLeft)),
+            (Action::TimelineForward, KeyBinding::shift(ArrowRight)),
+            (Action::ToggleCompare, KeyBinding::key(KeyD)),
+            (Action::ToggleRecord, KeyBinding::key(KeyV)),
+            (Action::ToggleGridLines, KeyBinding::key(KeyG)),
+            (Action::ToggleMajorLines, KeyBinding::shift(KeyG)),
+            (Action::ToggleRulers, KeyBinding::key(KeyR)),
+            (Action::ToggleHelp, KeyBinding::key(F1)),
+        ];
+        let digits = [
+            Digit1, Digit2, Digit3, Digit4, Digit5, Digit6, Digit7, Digit8, Digit9,
+        ];
+        for (n, key) in digits.into_iter().enumerate() {
+            bindings.push((Action::Pattern(n), KeyBinding::key(key)));
+        }
+        Self { bindings }
+    }
+}
+
+impl KeyBindings {
+    pub fn keys_for(&self, action: Action) -> impl Iterator<Item = &KeyBinding> {
+        self.bindings
+            .iter()
+            .filter(move |(a, _)| *a == action)
+            .map(|(_, binding)| binding)
+    }
+
+    /// 读取配置：每行 `操作名 = 按键[, 按键...]`，覆盖该操作的默认按键
+    /// 写成 `操作名 =`（右侧为空）表示取消该操作的按键
+    pub fn apply_config(&mut self, text: &str) {
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((name, keys)) = line.split_once('=') else {
+                warn!("{} 第 {} 行缺少 '=': {}", KEYBINDINGS_FILE, index + 1, line);
+                continue;
+            };
+            let Some(action) = Action::from_name(name.trim()) else {
+                warn!(
+                    "{} 第 {} 行: 未知操作 {}",
+                    KEYBINDINGS_FILE,
+                    index + 1,
+                    name.trim()
+                );
+                continue;
+            };
+
+            let mut parsed = Vec::new();
+            for key in keys.split(',').map(str::trim).filter(|k| !k.is_empty()) {
+                match KeyBinding::parse(key) {
+                    Some(binding) => parsed.push(binding),
+                    None => warn!(
+                        "{} 第 {} 行: 无法识别按键 {}",
+                        KEYBINDINGS_FILE,
+                        index + 1,
+                        key
+                    ),
+                }
+            }
+            self.bindings.retain(|(a, _)| *a != action);
+            self.bindings
+                .extend(parsed.into_iter().map(|b| (action, b)));
+        }
+    }
+}
+
+/// 启动时读取按键配置文件
+pub fn load_keybindings(mut bindings: ResMut<KeyBindings>) {
+    match fs::read_to_string(KEYBINDINGS_FILE) {
+        Ok(text) => {
+            bindings.apply_config(&text);
+            info!("已读取按键配置 {}", KEYBINDINGS_FILE);
+        }
+        Err(_) => info!("未找到 {}，使用默认按键", KEYBINDINGS_FILE),
+    }
+}
+
+/// 把按键转换为 Action 事件
+pub fn keybinding_system(
+    keys: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut actions: EventWriter<Action>,
+) {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    for (action, binding) in &bindings.bindings {
+        if binding.ctrl != ctrl || binding.shift != shift {
+            continue;
+        }
+        let triggered = if action.repeats() {
+            keys.pressed(binding.key)
+        } else {
+            keys.just_pressed(binding.key)
+        };
+        if triggered {
+            actions.send(*action);
+        }
+    }
+}
+
+/// 帮助界面根节点
+#[derive(Component)]
+pub struct HelpOverlay;
+
+/// 帮助文本分两栏显示，避免超出窗口高度
+#[derive(Component)]
+pub struct HelpText(usize);
+
+const HELP_COLUMNS: usize = 2;
+
+/// 覆盖整个窗口的帮助界面，默认隐藏
+pub fn help_overlay(commands: &mut Commands, font: &Handle<Font>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                z_index: ZIndex::Global(100),
+                ..default()
+            },
+            HelpOverlay,
+            Themed(ThemeColor::HelpBackdrop),
+        ))
+        .with_children(|p| {
+            p.spawn((
+                NodeBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(20.0)),
+                        column_gap: Val::Px(32.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                Themed(ThemeColor::HelpPanel),
+            ))
+            .with_children(|q| {
+                for column in 0..HELP_COLUMNS {
+                    q.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 16.0,
+                                color: Color::BLACK,
+                            },
+                        ),
+                        HelpText(column),
+                        ThemedText(ThemeColor::Text),
+                    ));
+                }
+            });
+        });
+}
+
+/// 切换帮助界面；按键配置或界面语言变化时刷新帮助文本
+pub fn help_overlay_system(
+    mut actions: EventReader<Action>,
+    bindings: Res<KeyBindings>,
+    language: Res<Language>,
+    mut overlay: Query<&mut Style, With<HelpOverlay>>,
+    mut text: Query<(&HelpText, &mut Text)>,
+) {
+    for action in actions.read() {
+        if *action == Action::ToggleHelp {
+            for mut style in &mut overlay {
+                style.display = match style.display {
+                    Display::None => Display::Flex,
+                    _ => Display::None,
+                };
+            }
+        }
+    }
+
+    if !bindings.is_changed() && !language.is_changed() {
+        return;
+    }
+    let mut help = format!(
+        "{}\n\n{}\n\n",
+        language.tr("help.title"),
+        language.tr("help.mouse")
+    );
+    let mut pattern_keys = Vec::new();
+    for action in Action::all() {
+        let keys: Vec<String> = bindings.keys_for(action).map(|b| b.to_string()).collect();
+        if keys.is_empty() {
+            continue;
+        }
+        // 示例图案的按键合并成一行显示
+        if let Action::Pattern(_) = action {
+            pattern_keys.push(keys.join("/"));
+            continue;
+        }
+        help += &format!(
+            "{}  —  {}\n",
+            keys.join(" / "),
+            action.description(*language)
+        );
+    }
+    if !pattern_keys.is_empty() {
+        help += &format!(
+            "{}  —  {}\n",
+            pattern_keys.join(" "),
+            language.tr("help.patterns")
+        );
+    }
+    // 按行数平均分到各栏
+    let lines: Vec<&str> = help.lines().collect();
+    let per_column = lines.len().div_ceil(HELP_COLUMNS);
+    for (column, mut text) in &mut text {
+        text.sections[0].value = lines
+            .chunks(per_column)
+            .nth(column.0)
+            .unwrap_or_default()
+            .join("\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_display() {
+        let redo = KeyBinding::parse("ctrl + SHIFT + z").unwrap();
+        assert_eq!(redo, KeyBinding::ctrl_shift(KeyCode::KeyZ));
+        assert_eq!(redo.to_string(), "Ctrl+Shift+Z");
+        assert_eq!(
+            KeyBinding::parse("Control+PageUp"),
+            Some(KeyBinding::ctrl(KeyCode::PageUp))
+        );
+        for text in ["", "Ctrl", "Ctrl+Shift", "Hyper+A", "Ctrl+Nope"] {
+            assert_eq!(KeyBinding::parse(text), None, "{}", text);
+        }
+        for (name, key) in KEY_NAMES {
+            assert_eq!(KeyBinding::parse(name), Some(KeyBinding::key(*key)));
+        }
+    }
+
+    #[test]
+    fn action_names_round_trip() {
+        for action in Action::all() {
+            assert_eq!(Action::from_name(&action.name()), Some(action));
+        }
+        assert_eq!(Action::from_name("pattern_1"), Some(Action::Pattern(0)));
+        assert_eq!(Action::from_name("pattern_10"), None);
+    }
+
+    #[test]
+    fn default_bindings_do_not_conflict() {
+        let bindings = KeyBindings::default();
+        for (i, (action, binding)) in bindings.bindings.iter().enumerate() {
+            for (other, other_binding) in &bindings.bindings[i + 1..] {
+                assert!(
+                    binding != other_binding,
+                    "{:?} / {:?}: {}",
+                    action,
+                    other,
+                    binding
+                );
+            }
+        }
+        assert!(
+            Action::all()
+                .iter()
+                .all(|a| bindings.keys_for(*a).next().is_some())
+        );
+    }
+
+    #[test]
+    fn config_overrides_defaults() {
+        let mut bindings = KeyBindings::default();
+        bindings.apply_config(
+            "# 注释\n\
+             step = S, Ctrl+Enter\n\
+             clear =\n\
+             undo = Nope, Ctrl+U\n\
+             unknown_action = A\n\
+             missing equals sign\n",
+        );
+        let keys = |action| bindings.keys_for(action).copied().collect::<Vec<_>>();
+        assert_eq!(
+            keys(Action::Step),
+            [
+                KeyBinding::key(KeyCode::KeyS),
+                KeyBinding::ctrl(KeyCode::Enter)
+            ]
+        );
+        assert!(keys(Action::Clear).is_empty());
+        // 无法识别的按键跳过，同一行的其余按键照常生效
+        assert_eq!(keys(Action::Undo), [KeyBinding::ctrl(KeyCode::KeyU)]);
+        assert_eq!(keys(Action::Redo), [KeyBinding::ctrl_shift(KeyCode::KeyZ)]);
+    }
+}
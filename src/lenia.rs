@@ -0,0 +1,528 @@
+use crate::evolution::Topology;
+use crate::export::FrameOutput;
+use crate::i18n::{Language, Localized};
+use crate::keybindings::Action;
+use crate::metrics::Metrics;
+use crate::theme::{Theme, ThemeColor, Themed, ThemedText};
+use crate::{
+    ALIVE, CellStates, DEAD, EvolutionTimer, GRID_SIZE, Grid, History, RulesLabel, Simulation,
+    advance_generation,
+};
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+
+/// 连续状态网格：field[y][x] 取值 0..=1
+pub type Field = [[f32; GRID_SIZE]; GRID_SIZE];
+
+/// 连续值不低于该阈值的格子在离散网格中视为存活
+const ALIVE_THRESHOLD: f32 = 0.5;
+
+/// 可用滑块调节的参数
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LeniaParam {
+    // 卷积核半径（格）
+    Radius,
+    // 生长函数的中心与宽度
+    Mu,
+    Sigma,
+    // 每代的时间步长
+    Dt,
+}
+
+impl LeniaParam {
+    const ALL: [LeniaParam; 4] = [
+        LeniaParam::Radius,
+        LeniaParam::Mu,
+        LeniaParam::Sigma,
+        LeniaParam::Dt,
+    ];
+
+    /// 滑块两端对应的取值
+    fn range(self) -> (f32, f32) {
+        match self {
+            LeniaParam::Radius => (2.0, 12.0),
+            LeniaParam::Mu => (0.05, 0.5),
+            LeniaParam::Sigma => (0.005, 0.1),
+            LeniaParam::Dt => (0.01, 0.5),
+        }
+    }
+
+    /// i18n 字符串表中的 key
+    fn key(self) -> &'static str {
+        match self {
+            LeniaParam::Radius => "lenia.radius",
+            LeniaParam::Mu => "lenia.mu",
+            LeniaParam::Sigma => "lenia.sigma",
+            LeniaParam::Dt => "lenia.dt",
+        }
+    }
+}
+
+/// Lenia 风格的连续细胞自动机：
+/// 每代先用环形卷积核求邻域的加权平均 u，再按生长函数 G(u) 更新，A ← clamp(A + Δt·G(u), 0, 1)
+/// 开启时由离散网格初始化；之后离散网格保存阈值化的结果，
+/// 手动编辑、撤销、粘贴等仍作用于离散网格，再同步到连续网格
+#[derive(Resource)]
+pub struct Lenia {
+    pub enabled: bool,
+    pub field: Field,
+    pub radius: u8,
+    pub mu: f32,
+    pub sigma: f32,
+    pub dt: f32,
+    // 卷积核 (dx, dy, 权重)，权重之和为 1；半径变化时重新计算
+    kernel: Vec<(isize, isize, f32)>,
+    // 上次同步时的离散网格，与当前不同的格子即为手动编辑
+    seen: Grid,
+}
+
+impl Default for Lenia {
+    fn default() -> Self {
+        let radius = 8;
+        Self {
+            enabled: false,
+            field: [[0.0; GRID_SIZE]; GRID_SIZE],
+            radius,
+            mu: 0.15,
+            sigma: 0.017,
+            dt: 0.1,
+            kernel: build_kernel(radius),
+            seen: [[DEAD; GRID_SIZE]; GRID_SIZE],
+        }
+    }
+}
+
+/// 环形卷积核：按到中心的相对距离 r ∈ (0, 1) 取 exp(4 - 1 / (r(1 - r)))，在 r = 0.5 处最大
+fn build_kernel(radius: u8) -> Vec<(isize, isize, f32)> {
+    let r = radius as isize;
+    let mut kernel = Vec::new();
+    for dy in -r..=r {
+        for dx in -r..=r {
+            let d = ((dx * dx + dy * dy) as f32).sqrt() / radius as f32;
+            if d > 0.0 && d < 1.0 {
+                kernel.push((dx, dy, (4.0 - 1.0 / (d * (1.0 - d))).exp()));
+            }
+        }
+    }
+    let total: f32 = kernel.iter().map(|k| k.2).sum();
+    for k in &mut kernel {
+        k.2 /= total;
+    }
+    kernel
+}
+
+impl Lenia {
+    fn get(&self, param: LeniaParam) -> f32 {
+        match param {
+            LeniaParam::Radius => self.radius as f32,
+            LeniaParam::Mu => self.mu,
+            LeniaParam::Sigma => self.sigma,
+            LeniaParam::Dt => self.dt,
+        }
+    }
+
+    fn set(&mut self, param: LeniaParam, value: f32) {
+        let (min, max) = param.range();
+        let value = value.clamp(min, max);
+        match param {
+            LeniaParam::Radius => {
+                let radius = value.round() as u8;
+                if radius != self.radius {
+                    self.radius = radius;
+                    self.kernel = build_kernel(radius);
+                }
+            }
+            LeniaParam::Mu => self.mu = value,
+            LeniaParam::Sigma => self.sigma = value,
+            LeniaParam::Dt => self.dt = value,
+        }
+    }
+
+    /// 生长函数：u 接近 mu 时为正（生长），偏离超过若干个 sigma 时趋于 -1（衰减）
+    fn growth(&self, u: f32) -> f32 {
+        let d = (u - self.mu) / self.sigma;
+        2.0 * (-d * d / 2.0).exp() - 1.0
+    }
+
+    /// (x, y) 处卷积核下的加权平均；环面拓扑时跨越边界，有界时边界外视为 0
+    fn potential(&self, x: usize, y: usize, topology: Topology) -> f32 {
+        let size = GRID_SIZE as isize;
+        let mut u = 0.0;
+        for &(dx, dy, weight) in &self.kernel {
+            let (mut nx, mut ny) = (x as isize + dx, y as isize + dy);
+            if topology == Topology::Torus {
+                nx = nx.rem_euclid(size);
+                ny = ny.rem_euclid(size);
+            } else if nx < 0 || nx >= size || ny < 0 || ny >= size {
+                continue;
+            }
+            u += weight * self.field[ny as usize][nx as usize];
+        }
+        u
+    }
+
+    /// 演化一代
+    fn step(&mut self, topology: Topology) {
+        let mut next = [[0.0; GRID_SIZE]; GRID_SIZE];
+        for (y, row) in next.iter_mut().enumerate() {
+            for (x, value) in row.iter_mut().enumerate() {
+                let growth = self.growth(self.potential(x, y, topology));
+                *value = (self.field[y][x] + self.dt * growth).clamp(0.0, 1.0);
+            }
+        }
+        self.field = next;
+    }
+
+    /// 阈值化得到的离散网格
+    fn threshold(&self) -> Grid {
+        let mut grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+        for (states, row) in grid.iter_mut().zip(&self.field) {
+            for (state, value) in states.iter_mut().zip(row) {
+                if *value >= ALIVE_THRESHOLD {
+                    *state = ALIVE;
+                }
+            }
+        }
+        grid
+    }
+
+    /// 由离散网格初始化：存活为 1，其余为 0
+    fn load(&mut self, grid: &Grid) {
+        for (row, states) in self.field.iter_mut().zip(grid) {
+            for (value, state) in row.iter_mut().zip(states) {
+                *value = if *state == ALIVE { 1.0 } else { 0.0 };
+            }
+        }
+        self.seen = *grid;
+    }
+}
+
+/// 连续模式开关按钮
+#[derive(Component)]
+pub struct LeniaButton;
+
+#[derive(Component)]
+pub struct LeniaButtonText;
+
+/// 参数面板，仅在连续模式下显示（替换左上方的规则说明）
+#[derive(Component)]
+pub struct LeniaPanel;
+
+/// 滑块轨道，点击或拖动设置参数
+#[derive(Component)]
+pub struct LeniaSlider(LeniaParam);
+
+#[derive(Component)]
+pub struct LeniaSliderFill(LeniaParam);
+
+#[derive(Component)]
+pub struct LeniaSliderText(LeniaParam);
+
+fn button_label(enabled: bool) -> &'static str {
+    if enabled {
+        "button.lenia_on"
+    } else {
+        "button.lenia_off"
+    }
+}
+
+pub fn lenia_ui(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(140.0),
+                    height: Val::Px(40.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            LeniaButton,
+            Themed(ThemeColor::ModeButton),
+        ))
+        .with_children(|r| {
+            r.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 18.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                LeniaButtonText,
+                Localized(button_label(false)),
+                ThemedText(ThemeColor::ButtonText),
+            ));
+        });
+}
+
+/// 参数滑块：每个参数一行文字加一条轨道
+pub fn lenia_panel(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    parent
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                ..default()
+            },
+            LeniaPanel,
+        ))
+        .with_children(|panel| {
+            for param in LeniaParam::ALL {
+                panel.spawn((
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 14.0,
+                            color: Color::BLACK,
+                        },
+                    ),
+                    LeniaSliderText(param),
+                    ThemedText(ThemeColor::Text),
+                ));
+                panel
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(140.0),
+                                height: Val::Px(10.0),
+                                margin: UiRect::bottom(Val::Px(4.0)),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        LeniaSlider(param),
+                        RelativeCursorPosition::default(),
+                        Themed(ThemeColor::ToolButton),
+                    ))
+                    .with_children(|track| {
+                        track.spawn((
+                            NodeBundle {
+                                style: Style {
+                                    height: Val::Percent(100.0),
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            LeniaSliderFill(param),
+                            Themed(ThemeColor::ToolSelected),
+                        ));
+                    });
+            }
+        });
+}
+
+/// 点击按钮或按快捷键开关连续模式；开启时由当前网格初始化，并显示参数面板
+#[allow(clippy::type_complexity)]
+pub fn lenia_toggle_system(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<LeniaButton>)>,
+    mut actions: EventReader<Action>,
+    mut lenia: ResMut<Lenia>,
+    cell_states: Res<CellStates>,
+    mut text: Query<&mut Localized, With<LeniaButtonText>>,
+    mut panel: Query<&mut Style, (With<LeniaPanel>, Without<RulesLabel>)>,
+    mut rules: Query<&mut Style, (With<RulesLabel>, Without<LeniaPanel>)>,
+) {
+    let pressed = interaction_query
+        .iter()
+        .filter(|i| **i == Interaction::Pressed)
+        .count();
+    let toggles = pressed
+        + actions
+            .read()
+            .filter(|a| **a == Action::ToggleLenia)
+            .count();
+    if toggles % 2 == 0 {
+        return;
+    }
+
+    lenia.enabled = !lenia.enabled;
+    if lenia.enabled {
+        lenia.load(&cell_states.states);
+    }
+    info!("连续模式: {}", if lenia.enabled { "开" } else { "关" });
+
+    for mut text in &mut text {
+        text.0 = button_label(lenia.enabled);
+    }
+    let (shown, hidden) = if lenia.enabled {
+        (Display::Flex, Display::None)
+    } else {
+        (Display::None, Display::Flex)
+    };
+    for mut style in &mut panel {
+        style.display = shown;
+    }
+    for mut style in &mut rules {
+        style.display = hidden;
+    }
+}
+
+/// 连续模式下的演化：与离散模式共用开始 / 停止、单步、清空与速度
+/// 先把离散网格上的手动编辑同步到连续网格，演化后再把阈值化结果写回离散网格
+/// 阈值化的每一代与离散演化一样记录历史、指标与输出帧
+#[allow(clippy::too_many_arguments)]
+pub fn lenia_step_system(
+    time: Res<Time>,
+    mut actions: EventReader<Action>,
+    mut timer: ResMut<EvolutionTimer>,
+    mut lenia: ResMut<Lenia>,
+    mut sim: ResMut<Simulation>,
+    mut cell_states: ResMut<CellStates>,
+    mut history: ResMut<History>,
+    mut metrics: ResMut<Metrics>,
+    theme: Res<Theme>,
+    mut frame_output: ResMut<FrameOutput>,
+) {
+    if !lenia.enabled {
+        return;
+    }
+
+    let mut steps = 0;
+    for action in actions.read() {
+        match action {
+            Action::Step => steps += 1,
+            Action::Clear => lenia.field = [[0.0; GRID_SIZE]; GRID_SIZE],
+            _ => {}
+        }
+    }
+
+    if cell_states.is_changed() {
+        for y in 0..GRID_SIZE {
+            for x in 0..GRID_SIZE {
+                let state = cell_states.states[y][x];
+                if state != lenia.seen[y][x] {
+                    lenia.field[y][x] = if state == ALIVE { 1.0 } else { 0.0 };
+                    lenia.seen[y][x] = state;
+                }
+            }
+        }
+    }
+
+    if sim.running {
+        timer.0.tick(time.delta());
+        if timer.0.just_finished() {
+            steps += 1;
+        }
+    }
+    if steps == 0 {
+        return;
+    }
+
+    for _ in 0..steps {
+        lenia.step(sim.topology);
+        let next = lenia.threshold();
+        advance_generation(
+            &mut sim,
+            &mut history,
+            &mut cell_states,
+            &mut metrics,
+            next,
+            Some((&mut frame_output, &theme)),
+        );
+        lenia.seen = next;
+    }
+}
+
+/// 按住滑块轨道时按光标的水平位置设置参数；参数或语言变化时刷新文字与进度
+pub fn lenia_slider_system(
+    sliders: Query<(&Interaction, &RelativeCursorPosition, &LeniaSlider)>,
+    mut lenia: ResMut<Lenia>,
+    language: Res<Language>,
+    mut fills: Query<(&LeniaSliderFill, &mut Style)>,
+    mut texts: Query<(&LeniaSliderText, &mut Text)>,
+) {
+    for (interaction, cursor, slider) in &sliders {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(position) = cursor.normalized else {
+            continue;
+        };
+        let (min, max) = slider.0.range();
+        let mut value = min + position.x.clamp(0.0, 1.0) * (max - min);
+        if slider.0 == LeniaParam::Radius {
+            value = value.round();
+        }
+        // 只在取值变化时写入，避免每帧触发变更检测
+        if lenia.get(slider.0) != value {
+            lenia.set(slider.0, value);
+        }
+    }
+
+    if !lenia.is_changed() && !language.is_changed() {
+        return;
+    }
+    for (fill, mut style) in &mut fills {
+        let (min, max) = fill.0.range();
+        let t = (lenia.get(fill.0) - min) / (max - min);
+        style.width = Val::Percent(t * 100.0);
+    }
+    for (text, mut value) in &mut texts {
+        let v = lenia.get(text.0);
+        let v = match text.0 {
+            LeniaParam::Radius => format!("{}", v),
+            LeniaParam::Sigma => format!("{:.3}", v),
+            _ => format!("{:.2}", v),
+        };
+        value.sections[0].value = language.trf(text.0.key(), v);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_kernel() {
+        for radius in [2, 8, 12] {
+            let kernel = build_kernel(radius);
+            let total: f32 = kernel.iter().map(|k| k.2).sum();
+            assert!((total - 1.0).abs() < 1e-4);
+            // 不含中心，旋转 90° 后权重不变
+            assert!(kernel.iter().all(|k| (k.0, k.1) != (0, 0)));
+            let weight = |dx, dy| kernel.iter().find(|k| (k.0, k.1) == (dx, dy)).map(|k| k.2);
+            for &(dx, dy, w) in &kernel {
+                assert_eq!(weight(-dy, dx), Some(w));
+            }
+        }
+        // 权重在半径的一半处最大，靠近中心与边缘时很小
+        let kernel = build_kernel(8);
+        let weight = |dx| kernel.iter().find(|k| (k.0, k.1) == (dx, 0)).unwrap().2;
+        assert!(weight(4) > weight(2) && weight(4) > weight(6));
+        assert!(weight(1) < weight(4) / 10.0);
+    }
+
+    #[test]
+    fn growth_peaks_at_mu() {
+        let lenia = Lenia::default();
+        assert!((lenia.growth(lenia.mu) - 1.0).abs() < 1e-6);
+        let near = lenia.growth(lenia.mu + lenia.sigma);
+        assert!(near > 0.0 && near < 1.0);
+        assert!((near - lenia.growth(lenia.mu - lenia.sigma)).abs() < 1e-5);
+        assert!(lenia.growth(0.0) < -0.99 && lenia.growth(1.0) < -0.99);
+    }
+
+    #[test]
+    fn step_and_threshold() {
+        let mut lenia = Lenia::default();
+        // 空场保持为空；孤立的一格在负生长下衰减
+        lenia.step(Topology::Torus);
+        assert_eq!(lenia.threshold(), [[DEAD; GRID_SIZE]; GRID_SIZE]);
+        let mut grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+        grid[10][10] = ALIVE;
+        lenia.load(&grid);
+        assert_eq!(lenia.threshold(), grid);
+        lenia.step(Topology::Bounded);
+        assert!((lenia.field[10][10] - (1.0 - lenia.dt)).abs() < 1e-4);
+    }
+}
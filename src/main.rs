@@ -1,16 +1,119 @@
 pub mod evolution;
 use bevy::prelude::*;
-use evolution::next_generation;
+use evolution::{Neighborhood, Rule, Topology, next_generation};
+use std::collections::VecDeque;
 
 mod patterns;
 use patterns::*;
 
+mod census;
+use census::*;
+
+mod coloring;
+use coloring::*;
+
+mod compare;
+use compare::*;
+
+mod draw;
+use draw::*;
+
+mod edit;
+use edit::*;
+
+mod export;
+use export::*;
+
+mod heatmap;
+use heatmap::*;
+
+mod headless;
+use headless::*;
+
+mod grid_lines;
+use grid_lines::*;
+
+mod i18n;
+use i18n::*;
+
+mod metrics;
+use metrics::*;
+
+mod neighborhood;
+use neighborhood::*;
+
+mod keybindings;
+use keybindings::*;
+
+mod lenia;
+use lenia::*;
+
+mod rule_table;
+mod rules;
+use rules::*;
+
+mod recording;
+use recording::*;
+
+mod objects;
+mod rle;
+mod selection;
+use selection::*;
+mod session;
+use session::*;
+
+mod theme;
+use theme::*;
+
+mod timeline;
+use timeline::*;
+
 pub const GRID_SIZE: usize = 35;
-const CELL_SIZE: f32 = 20.0;
-const BORDER_SIZE: f32 = 1.0; // 黑色网格线宽度
+const CELL_SIZE: f32 = 22.0; // 缩放为 1 时每个格子的像素（网格线画在格子之上）
+const VIEWPORT_SIZE: f32 = GRID_SIZE as f32 * CELL_SIZE; // 网格外框边长
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 4.0;
+const ZOOM_STEP: f32 = 1.25; // 每次缩放的倍数（滚轮一格或一次快捷键）
 const STEP_INTERVAL: f32 = 0.2; // 每代间隔（秒）
+const HISTORY_LIMIT: usize = 100; // 保留的历史代数上限
+const PAN_SPEED: f32 = 400.0; // 方向键平移速度（像素/秒）
+const MIN_STEP_INTERVAL: f32 = 0.025; // 最快速度
+const MAX_STEP_INTERVAL: f32 = 1.6; // 最慢速度
+
+/// 网格逻辑状态：grid[row][col] => grid[y][x]
+/// 每格为一个状态：0 死亡，1 存活，2 及以上为 Generations 规则中的衰亡状态
+pub type Grid = [[u8; GRID_SIZE]; GRID_SIZE];
+
+pub const DEAD: u8 = 0;
+pub const ALIVE: u8 = 1;
+
+/// 从未存活或被手动清除的格子的年龄（不显示残影）
+const LONG_DEAD: u32 = u32::MAX;
 
 fn main() {
+    // 命令行参数：--headless 时不开窗口，直接演化并输出帧
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    if args.headless {
+        if let Err(e) = run_headless(&args) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    let frame_output = match args.frame_output() {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
@@ -21,27 +124,101 @@ fn main() {
             }),
             ..default()
         }))
-        .insert_resource(ClearColor(Color::WHITE))
+        .insert_resource(ClearColor(Theme::default().color(ThemeColor::Background)))
         .insert_resource(CellStates::default())
-        .insert_resource(Simulation { running: false })
+        .insert_resource(Simulation::default())
+        .insert_resource(History::default())
+        .insert_resource(GridCamera::default())
+        .insert_resource(EditHistory::default())
+        .insert_resource(DrawTool::default())
+        .insert_resource(DrawState::default())
+        .insert_resource(Selection::default())
+        .insert_resource(KeyBindings::default())
+        .insert_resource(ColorMode::default())
+        .insert_resource(Theme::default())
+        .insert_resource(ThemeLibrary::default())
+        .insert_resource(GridLines::default())
+        .insert_resource(Language::default())
+        .insert_resource(NeighborhoodLibrary::default())
+        .insert_resource(RuleLibrary::default())
+        .insert_resource(Lenia::default())
+        .insert_resource(ExportSettings::default())
+        .insert_resource(Recorder::default())
+        .insert_resource(Metrics::default())
+        .insert_resource(Heatmap::default())
+        .insert_resource(CensusReport::default())
+        .insert_resource(Timeline::default())
+        .insert_resource(Comparison::default())
+        .insert_resource(frame_output)
+        .add_event::<CellEdit>()
+        .add_event::<Action>()
         .insert_resource(EvolutionTimer(Timer::from_seconds(
             STEP_INTERVAL,
             TimerMode::Repeating,
         )))
-        .add_systems(Startup, setup)
+        .add_systems(Startup, (
+                setup,
+                load_keybindings,
+                load_themes,
+                load_language,
+                load_kernel,
+                load_rule_files,
+                frame_output_start_system.after(load_themes),
+            ),)
         .add_systems(
             Update,
             (
                 spawn_grid_cells,
-                cell_click_system,
+                keybinding_system,
+                control_action_system,
+                help_overlay_system,
+                draw_system.before(edit_system),
+                render_cells_system.after(edit_system),
+                color_mode_system,
+                theme_switch_system,
+                apply_theme_system.after(spawn_grid_cells),
+                tool_button_system,
+                selection_mouse_system,
+                selection_action_system.before(edit_system),
+                selection_overlay_system,
                 button_system,
                 evolution_runner_debug,
                 pattern_button_system,
+                session_system,
+                edit_system,
+                apply_grid_camera,
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                zoom_wheel_system,
+                grid_lines_toggle_system,
+                grid_lines_system.after(apply_grid_camera),
+                language_switch_system,
+                localize_system.after(language_switch_system),
+                neighborhood_switch_system.before(apply_grid_camera),
+                rule_switch_system.after(localize_system),
+                lenia_toggle_system,
+                lenia_step_system.after(edit_system),
+                lenia_slider_system,
+                export_system,
+                recording_system.after(edit_system),
+                metrics_export_system,
+                heatmap_system.after(edit_system).before(render_cells_system),
+                census_system,
+                timeline_system.after(edit_system).before(render_cells_system),
+                compare_system.after(timeline_system),
             ),
         )
         .run();
 }
 
+/// 网格外框：固定大小并裁剪超出部分
+#[derive(Component)]
+struct GridViewport;
+
+/// 网格内容：随 GridCamera 平移
 #[derive(Component)]
 struct GridArea;
 
@@ -61,26 +238,80 @@ enum ControlButton {
 #[derive(Component)]
 struct StartButtonText;
 
+/// 左上方的规则说明（连续模式下换成参数面板）
+#[derive(Component)]
+struct RulesLabel;
+
 #[derive(Resource)]
 struct CellStates {
     // 逻辑状态：states[row][col] => states[y][x]
-    states: [[bool; GRID_SIZE]; GRID_SIZE],
-    // 映射到实际Button实体，便于直接更新 UI
-    entities: [[Option<Entity>; GRID_SIZE]; GRID_SIZE],
+    states: Grid,
+    // 年龄：存活时为已存活的代数（新生为 1），死亡时为距离死亡的代数
+    ages: [[u32; GRID_SIZE]; GRID_SIZE],
 }
 
 impl Default for CellStates {
     fn default() -> Self {
         Self {
-            states: [[false; GRID_SIZE]; GRID_SIZE],
-            entities: [[None; GRID_SIZE]; GRID_SIZE],
+            states: [[DEAD; GRID_SIZE]; GRID_SIZE],
+            ages: [[LONG_DEAD; GRID_SIZE]; GRID_SIZE],
         }
     }
 }
 
-#[derive(Resource)]
+#[derive(Resource, Default)]
 struct Simulation {
     running: bool,
+    // 当前代数
+    generation: u64,
+    rule: Rule,
+    neighborhood: Neighborhood,
+    topology: Topology,
+}
+
+/// 最近若干代的网格，从旧到新排列
+#[derive(Resource, Default)]
+struct History {
+    generations: VecDeque<Grid>,
+}
+
+/// 网格视图的相机：平移（网格内容相对外框的像素偏移）与缩放
+#[derive(Resource)]
+struct GridCamera {
+    offset: Vec2,
+    zoom: f32,
+    // 六边形邻域下奇数行向右错开半格显示
+    hex: bool,
+}
+
+impl Default for GridCamera {
+    fn default() -> Self {
+        Self {
+            offset: Vec2::ZERO,
+            zoom: 1.0,
+            hex: false,
+        }
+    }
+}
+
+impl GridCamera {
+    /// 当前缩放下每个格子的像素
+    fn cell_size(&self) -> f32 {
+        CELL_SIZE * self.zoom
+    }
+
+    /// 第 x 列、第 y 行格子左上角相对网格内容的像素位置
+    fn cell_position(&self, x: usize, y: usize) -> Vec2 {
+        let shift = if self.hex && y % 2 == 1 { 0.5 } else { 0.0 };
+        Vec2::new(x as f32 + shift, y as f32) * self.cell_size()
+    }
+
+    /// 以外框内的 pivot 点为中心缩放，该点下的格子保持不动
+    fn zoom_by(&mut self, factor: f32, pivot: Vec2) {
+        let zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.offset = pivot - (pivot - self.offset) * (zoom / self.zoom);
+        self.zoom = zoom;
+    }
 }
 
 #[derive(Resource)]
@@ -92,6 +323,12 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
 
     let font = asset_server.load("fonts/ZCOOLKuaiLe-Regular.ttf");
 
+    // 快捷键帮助（F1）
+    help_overlay(&mut commands, &font);
+
+    // 物体普查结果
+    census_overlay(&mut commands, &font);
+
     // 根节点（水平布局）
     commands
         .spawn(NodeBundle {
@@ -102,9 +339,9 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                 flex_direction: FlexDirection::Row,
                 ..default()
             },
-            background_color: Color::WHITE.into(),
             ..default()
         })
+        .insert(Themed(ThemeColor::Background))
         .with_children(|parent| {
             // 左侧控制栏（竖直布局）
             parent
@@ -118,17 +355,19 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         padding: UiRect::all(Val::Px(10.0)),
                         ..default()
                     },
-                    background_color: Color::srgb(0.95, 0.95, 0.95).into(),
                     ..default()
                 })
+                .insert(Themed(ThemeColor::Panel))
                 .with_children(|p| {
-                    // 上 1/3：规则说明
+                    // 上 1/3：规则说明、录制、指标与热力图按钮
                     p.spawn(NodeBundle {
                         style: Style {
                             height: Val::Percent(33.0),
                             width: Val::Percent(100.0),
+                            flex_direction: FlexDirection::Column,
                             justify_content: JustifyContent::Center,
                             align_items: AlignItems::Center,
+                            row_gap: Val::Px(4.0),
                             ..default()
                         },
                         background_color: Color::NONE.into(),
@@ -136,13 +375,26 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                     })
                     .with_children(|q| {
                         q.spawn(TextBundle::from_section(
-                            "游戏规则：\n1. 存活细胞周围有 2 或 3 个邻居则继续存活。\n2. 死亡细胞周围有 3 个邻居则复活。",
+                            "",
                             TextStyle {
                                 font: font.clone(),
                                 font_size: 16.0,
                                 color: Color::BLACK,
                             },
-                        ));
+                        ))
+                        .insert(Localized("label.rules"))
+                        .insert(RulesLabel)
+                        .insert(ThemedText(ThemeColor::Text));
+
+                        // 连续模式的参数滑块
+                        lenia_panel(q, &font);
+
+                        // 录制动画与导出指标
+                        recording_panel(q, &font);
+                        metrics_ui(q, &font);
+
+                        // 热力图
+                        heatmap_ui(q, &font);
                     });
 
                     // 下 2/3：按钮区（垂直排列）
@@ -153,7 +405,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                             flex_direction: FlexDirection::Column,
                             justify_content: JustifyContent::Center,
                             align_items: AlignItems::Center,
-                            row_gap: Val::Px(12.0),
+                            row_gap: Val::Px(6.0),
                             ..default()
                         },
                         background_color: Color::NONE.into(),
@@ -170,20 +422,22 @@ q.spawn(ButtonBundle {
         align_items: AlignItems::Center,
         ..default()
     },
-    background_color: Color::srgb(0.2, 0.6, 0.9).into(),
     ..default()
 })
 .insert(ControlButton::Start)
+.insert(Themed(ThemeColor::StartButton))
 .with_children(|r| {
     r.spawn(TextBundle::from_section(
-        "开始演化",
+        "",
         TextStyle {
             font: font.clone(),
             font_size: 18.0,
             color: Color::WHITE,
         },
     ))
-    .insert(StartButtonText); 
+    .insert(StartButtonText)
+    .insert(Localized("button.start"))
+    .insert(ThemedText(ThemeColor::ButtonText));
 });
 
 
@@ -196,20 +450,42 @@ q.spawn(ButtonBundle {
                                 align_items: AlignItems::Center,
                                 ..default()
                             },
-                            background_color: Color::srgb(0.85, 0.2, 0.2).into(),
                             ..default()
                         })
                         .insert(ControlButton::Clear)
+                        .insert(Themed(ThemeColor::ClearButton))
                         .with_children(|r| {
                             r.spawn(TextBundle::from_section(
-                                "清空网格",
+                                "",
                                 TextStyle {
                                     font: font.clone(),
                                     font_size: 18.0,
                                     color: Color::WHITE,
                                 },
-                            ));
+                            ))
+                            .insert(Localized("button.clear"))
+                            .insert(ThemedText(ThemeColor::ButtonText));
                         });
+
+                        // 保存 / 打开会话
+                        session_ui(q, &font);
+
+                        // 配色与主题切换
+                        color_mode_ui(q, &font);
+                        theme_ui(q, &font);
+
+                        // 规则与邻域切换
+                        rule_ui(q, &font);
+                        neighborhood_ui(q, &font);
+
+                        // 连续模式开关
+                        lenia_ui(q, &font);
+
+                        // 网格线开关
+                        grid_lines_ui(q, &font);
+
+                        // 界面语言
+                        language_ui(q, &font);
                     });
                 });
 
@@ -217,20 +493,47 @@ q.spawn(ButtonBundle {
 parent
     .spawn(NodeBundle {
         style: Style {
-            width: Val::Px(GRID_SIZE as f32 * (CELL_SIZE + 2.0 * BORDER_SIZE)),
-            height: Val::Px(GRID_SIZE as f32 * (CELL_SIZE + 2.0 * BORDER_SIZE)),
-            // 关键：使子节点 absolute 定位基于此容器
+            width: Val::Px(VIEWPORT_SIZE),
+            height: Val::Px(VIEWPORT_SIZE),
             position_type: PositionType::Relative,
-            // 不要使用 flex_wrap 了
-            justify_content: JustifyContent::FlexStart,
-            align_items: AlignItems::FlexStart,
+            overflow: Overflow::clip(),
             margin: UiRect::all(Val::Px(10.0)),
             ..default()
         },
-        background_color: Color::WHITE.into(),
         ..default()
     })
-    .insert(GridArea);
+    .insert(GridViewport)
+    .insert(Themed(ThemeColor::Background))
+    .with_children(|viewport| {
+        viewport
+            .spawn(NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    // 关键：使子节点 absolute 定位基于此容器
+                    position_type: PositionType::Absolute,
+                    // 不要使用 flex_wrap 了
+                    justify_content: JustifyContent::FlexStart,
+                    align_items: AlignItems::FlexStart,
+                    ..default()
+                },
+                ..default()
+            })
+            .insert(GridArea)
+            .with_children(|area| {
+                selection_overlay(area);
+                grid_lines_overlay(area);
+            });
+
+        // 坐标标尺（默认隐藏）
+        rulers(viewport, &font);
+
+        // 对比视图（默认隐藏，时间轴显示在它上面）
+        compare_panel(viewport, &font);
+
+        // 时间轴（默认隐藏）
+        timeline_bar(viewport, &font);
+    });
 
 
 parent
@@ -244,32 +547,37 @@ parent
             padding: UiRect::all(Val::Px(10.0)),
             ..default()
         },
-        background_color: Color::srgb(0.95, 0.95, 0.95).into(),
         ..default()
     })
+    .insert(Themed(ThemeColor::Panel))
     .with_children(|p| {
         patterns_ui(p, &asset_server);
+        tools_ui(p, &font);
+        selection_ui(p, &font);
+        export_ui(p, &font);
     });
         });
 }
 
-// 统一设置某个格子状态并更新对应实体颜色
-fn set_cell_state(
-    commands: &mut Commands,
-    cell_states: &mut CellStates,
-    x: usize,
-    y: usize,
-    alive: bool,
-) {
+// 统一设置某个格子状态（颜色由 render_cells_system 统一刷新）
+// 手动编辑出生的细胞算作新生，手动清除的细胞不留残影
+fn set_cell_state(cell_states: &mut CellStates, x: usize, y: usize, state: u8) {
+    let alive = state == ALIVE;
+    if (cell_states.states[y][x] == ALIVE) != alive {
+        cell_states.ages[y][x] = if alive { 1 } else { LONG_DEAD };
+    }
     // 更新逻辑数组（states[y][x]）
-    cell_states.states[y][x] = alive;
-    // 通过实体映射更新 UI（若存在）
-    if let Some(ent) = cell_states.entities[y][x] {
-        commands.entity(ent).insert(BackgroundColor(if alive {
-            Color::BLACK
-        } else {
-            Color::WHITE
-        }));
+    cell_states.states[y][x] = state;
+}
+
+// 超出规则状态数的格子按存活处理（换规则或读入的状态多于规则时）
+fn clamp_states(cell_states: &mut CellStates, states: u8) {
+    for y in 0..GRID_SIZE {
+        for x in 0..GRID_SIZE {
+            if cell_states.states[y][x] >= states {
+                set_cell_state(cell_states, x, y, ALIVE);
+            }
+        }
     }
 }
 
@@ -278,6 +586,7 @@ fn spawn_grid_cells(
     mut commands: Commands,
     query: Query<Entity, With<GridArea>>,
     existing: Query<&Cell>,
+    camera: Res<GridCamera>,
     mut cell_states: ResMut<CellStates>,
 ) {
     if !existing.is_empty() {
@@ -286,126 +595,194 @@ fn spawn_grid_cells(
 
     let grid_entity = query.single();
 
-    // 每个格子占用的像素（含黑色边框）
-    let cell_total = CELL_SIZE + 2.0 * BORDER_SIZE;
-
+    // 网格线由 grid_lines.rs 单独绘制
     for y in 0..GRID_SIZE {
         for x in 0..GRID_SIZE {
-            // 计算左上相对于容器的像素偏移（UI 的 top 从容器上边向下算）
-            let left = x as f32 * cell_total;
-            let top = y as f32 * cell_total;
-
-            
             commands.entity(grid_entity).with_children(|parent| {
                 parent
-                    .spawn(NodeBundle {
-                        style: Style {
-                            
-                            position_type: PositionType::Absolute,
-                            left: Val::Px(left),
-                            top: Val::Px(top),
-                            width: Val::Px(cell_total),
-                            height: Val::Px(cell_total),
-                            justify_content: JustifyContent::Center,
-                            align_items: AlignItems::Center,
-                            ..default()
-                        },
-                        background_color: Color::srgb(0.8, 0.8, 0.8).into(),
+                    .spawn(ButtonBundle {
+                        style: cell_style(x, y, &camera),
                         ..default()
                     })
-                    .with_children(|parent| {
-                        // 内层白色 Button
-                        let btn = parent
-                            .spawn(ButtonBundle {
-                                style: Style {
-                                    width: Val::Px(CELL_SIZE),
-                                    height: Val::Px(CELL_SIZE),
-                                    ..default()
-                                },
-                                background_color: Color::WHITE.into(),
-                                ..default()
-                            })
-                            .insert(Cell { x, y })
-                            .id();
-
-                        // 记录实体映射
-                        cell_states.entities[y][x] = Some(btn);
-                    });
+                    .insert(Cell { x, y });
             });
 
             // 初始化逻辑状态
-            cell_states.states[y][x] = false;
+            cell_states.states[y][x] = DEAD;
+            cell_states.ages[y][x] = LONG_DEAD;
         }
     }
 }
 
 
-// 点击单个格子
-// 点击时统一通过 set_cell_state 更新状态与 UI
-fn cell_click_system(
-    mut interaction_query: Query<(&Interaction, &Cell), Changed<Interaction>>,
-    mut cell_states: ResMut<CellStates>,
-    mut commands: Commands,
-) {
-    for (interaction, cell) in &mut interaction_query {
-        if *interaction == Interaction::Pressed {
-            // 打印调试坐标（行 y，列 x），便于核对视觉与数组映射
-            info!("Clicked cell at array coords: (x={}, y={})", cell.x, cell.y);
-
-            // 先读取当前状态
-            let current = cell_states.states[cell.y][cell.x];
-            let new_state = !current;
-
-            // 再以可变借用的方式更新状态和 UI
-            set_cell_state(&mut commands, &mut *cell_states, cell.x, cell.y, new_state);
-        }
+// 格子相对网格内容左上角的位置与大小（UI 的 top 从容器上边向下算）
+fn cell_style(x: usize, y: usize, camera: &GridCamera) -> Style {
+    let position = camera.cell_position(x, y);
+    Style {
+        position_type: PositionType::Absolute,
+        left: Val::Px(position.x),
+        top: Val::Px(position.y),
+        width: Val::Px(camera.cell_size()),
+        height: Val::Px(camera.cell_size()),
+        ..default()
     }
 }
 
-// Start 切换运行态，Clear 清空并把所有方块设为白色
+// Start / Clear 按钮转换为对应的 Action，与快捷键走同一条路径
 fn button_system(
     mut interaction_query: Query<(&Interaction, &ControlButton), Changed<Interaction>>,
-    mut start_text_query: Query<&mut Text, With<StartButtonText>>,
-    mut cell_states: ResMut<CellStates>,
-    mut sim: ResMut<Simulation>,
-    mut commands: Commands,
+    mut actions: EventWriter<Action>,
 ) {
     for (interaction, button) in &mut interaction_query {
         if *interaction == Interaction::Pressed {
-            match button {
-                ControlButton::Start => {
-                    sim.running = !sim.running;
-                    info!("演化状态: {}", if sim.running { "运行" } else { "停止" });
-
-                    if let Ok(mut text) = start_text_query.get_single_mut() {
-                        text.sections[0].value = if sim.running {
-                            "停止演化".to_string()
-                        } else {
-                            "开始演化".to_string()
-                        };
-                    }
+            actions.send(match button {
+                ControlButton::Start => Action::ToggleRun,
+                ControlButton::Clear => Action::Clear,
+            });
+        }
+    }
+}
+
+// 处理运行控制类操作：开始/停止、单步、清空、调速与平移
+#[allow(clippy::too_many_arguments)]
+fn control_action_system(
+    mut actions: EventReader<Action>,
+    time: Res<Time>,
+    mut start_text_query: Query<&mut Localized, With<StartButtonText>>,
+    mut sim: ResMut<Simulation>,
+    mut timer: ResMut<EvolutionTimer>,
+    mut camera: ResMut<GridCamera>,
+    mut history: ResMut<History>,
+    mut cell_states: ResMut<CellStates>,
+    mut edits: EventWriter<CellEdit>,
+    lenia: Res<Lenia>,
+    mut metrics: ResMut<Metrics>,
+    theme: Res<Theme>,
+    mut frame_output: ResMut<FrameOutput>,
+) {
+    let pan = PAN_SPEED * time.delta_seconds();
+    for action in actions.read() {
+        match action {
+            Action::ToggleRun => {
+                sim.running = !sim.running;
+                info!("演化状态: {}", if sim.running { "运行" } else { "停止" });
+
+                // 文字由 localize_system 按当前语言刷新
+                if let Ok(mut text) = start_text_query.get_single_mut() {
+                    text.0 = if sim.running {
+                        "button.stop"
+                    } else {
+                        "button.start"
+                    };
                 }
-                ControlButton::Clear => {
-                    info!("清空网格");
-                    for y in 0..GRID_SIZE {
-                        for x in 0..GRID_SIZE {
-                            set_cell_state(&mut commands, &mut *cell_states, x, y, false);
-                        }
-                    }
+            }
+            Action::Step if !lenia.enabled => {
+                step_generation(
+                    &mut sim,
+                    &mut history,
+                    &mut cell_states,
+                    &mut metrics,
+                    Some((&mut frame_output, &theme)),
+                );
+            }
+            Action::Clear => {
+                info!("清空网格");
+                edits.send(CellEdit::clear());
+            }
+            Action::SpeedUp | Action::SpeedDown => {
+                let current = timer.0.duration().as_secs_f32();
+                let interval = if *action == Action::SpeedUp {
+                    current / 2.0
+                } else {
+                    current * 2.0
                 }
+                .clamp(MIN_STEP_INTERVAL, MAX_STEP_INTERVAL);
+                timer.0.set_duration(std::time::Duration::from_secs_f32(interval));
+                info!("每代间隔: {:.3} 秒", interval);
             }
+            // 视图向某方向移动，即网格内容反向移动
+            Action::PanUp => camera.offset.y += pan,
+            Action::PanDown => camera.offset.y -= pan,
+            Action::PanLeft => camera.offset.x += pan,
+            Action::PanRight => camera.offset.x -= pan,
+            // 快捷键缩放以外框中心为基准
+            Action::ZoomIn => camera.zoom_by(ZOOM_STEP, Vec2::splat(VIEWPORT_SIZE / 2.0)),
+            Action::ZoomOut => camera.zoom_by(1.0 / ZOOM_STEP, Vec2::splat(VIEWPORT_SIZE / 2.0)),
+            _ => {}
         }
     }
 }
 
+// 鼠标在网格外框上滚动滚轮时，以光标为中心缩放
+fn zoom_wheel_system(
+    mut wheel: EventReader<bevy::input::mouse::MouseWheel>,
+    windows: Query<&Window, With<bevy::window::PrimaryWindow>>,
+    viewport: Query<(&Node, &GlobalTransform), With<GridViewport>>,
+    mut camera: ResMut<GridCamera>,
+) {
+    let steps: f32 = wheel.read().map(|e| e.y.signum()).sum();
+    if steps == 0.0 {
+        return;
+    }
+    let (Ok(window), Ok((node, transform))) = (windows.get_single(), viewport.get_single()) else {
+        return;
+    };
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+    let top_left = transform.translation().truncate() - node.size() / 2.0;
+    let pivot = cursor - top_left;
+    if pivot.cmplt(Vec2::ZERO).any() || pivot.cmpgt(node.size()).any() {
+        return;
+    }
+    camera.zoom_by(ZOOM_STEP.powf(steps), pivot);
+}
+
+// 相机变化时平移网格内容；缩放或六边形显示变化时重新摆放格子
+// 六边形显示时格子画成圆形，错行排列后呈蜂窝状
+fn apply_grid_camera(
+    camera: Res<GridCamera>,
+    mut query: Query<&mut Style, With<GridArea>>,
+    mut cells: Query<(&Cell, &mut Style, &mut BorderRadius), Without<GridArea>>,
+    mut last_layout: Local<Option<(f32, bool)>>,
+) {
+    if !camera.is_changed() {
+        return;
+    }
+    for mut style in &mut query {
+        style.left = Val::Px(camera.offset.x);
+        style.top = Val::Px(camera.offset.y);
+    }
+
+    let layout = (camera.zoom, camera.hex);
+    if *last_layout == Some(layout) {
+        return;
+    }
+    *last_layout = Some(layout);
+    for (cell, mut style, mut radius) in &mut cells {
+        *style = cell_style(cell.x, cell.y, &camera);
+        *radius = if camera.hex {
+            BorderRadius::MAX
+        } else {
+            BorderRadius::ZERO
+        };
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn evolution_runner_debug(
     time: Res<Time>,
     mut timer: ResMut<EvolutionTimer>,
     mut sim: ResMut<Simulation>,
+    mut history: ResMut<History>,
     mut cell_states: ResMut<CellStates>,
-    mut commands: Commands,
+    lenia: Res<Lenia>,
+    theme: Res<Theme>,
+    mut frame_output: ResMut<FrameOutput>,
+    mut metrics: ResMut<Metrics>,
 ) {
-    if !sim.running {
+    // 连续模式由 lenia_step_system 演化
+    if !sim.running || lenia.enabled {
         return;
     }
 
@@ -414,26 +791,70 @@ fn evolution_runner_debug(
         return;
     }
 
+    step_generation(
+        &mut sim,
+        &mut history,
+        &mut cell_states,
+        &mut metrics,
+        Some((&mut frame_output, &theme)),
+    );
+}
 
+// 演化一代：按规则计算下一代，再由 advance_generation 记录
+fn step_generation(
+    sim: &mut Simulation,
+    history: &mut History,
+    cell_states: &mut CellStates,
+    metrics: &mut Metrics,
+    frame_output: Option<(&mut FrameOutput, &Theme)>,
+) {
+    // 计算下一代（来自 evolution.rs）
+    let next = next_generation(&cell_states.states, &sim.rule, &sim.neighborhood, sim.topology);
+    advance_generation(sim, history, cell_states, metrics, next, frame_output);
+}
+
+// 进入下一代 next：更新年龄并记录历史与指标（离散演化与连续模式共用）
+// 命令行打开了逐代输出时，frame_output 为输出与配色，每代都在这里输出一帧
+fn advance_generation(
+    sim: &mut Simulation,
+    history: &mut History,
+    cell_states: &mut CellStates,
+    metrics: &mut Metrics,
+    next: Grid,
+    frame_output: Option<(&mut FrameOutput, &Theme)>,
+) {
     let current = cell_states.states;
 
-    // 计算下一代（来自 evolution.rs）
-    let next = next_generation(&current);
-
-    // 对比 current 与 next 全表的活细胞数量（快速 sanity check）
-    let cur_alive = current.iter().flatten().filter(|b| **b).count();
-    let next_alive = next.iter().flatten().filter(|b| **b).count();
-    println!(
-        "DEBUG: alive count current = {}, next = {}",
-        cur_alive, next_alive
-    );
+    // 记录这一代的人口、新生、死亡、边界框与哈希
+    metrics.record_step(sim.generation, &current, &next);
+    if let Some(m) = metrics.samples.last() {
+        debug!(
+            "第 {} 代: 活细胞 {}，新生 {}，死亡 {}",
+            m.generation, m.population, m.births, m.deaths
+        );
+    }
 
-    // 应用 next 到资源 & UI（使用统一 helper）
+    // 应用 next 到资源，同时更新年龄：存活与否不变则加一，翻转则从 1 重新计数
+    // （衰亡状态都算作非存活，年龄即距离开始衰亡的代数）
     for y in 0..GRID_SIZE {
         for x in 0..GRID_SIZE {
-            set_cell_state(&mut commands, &mut *cell_states, x, y, next[y][x]);
+            let age = &mut cell_states.ages[y][x];
+            *age = if (current[y][x] == ALIVE) == (next[y][x] == ALIVE) {
+                age.saturating_add(1)
+            } else {
+                1
+            };
         }
     }
+    cell_states.states = next;
+
+    history.generations.push_back(current);
+    if history.generations.len() > HISTORY_LIMIT {
+        history.generations.pop_front();
+    }
+    sim.generation += 1;
 
-    println!("DEBUG: applied next generation to UI");
+    if let Some((output, theme)) = frame_output {
+        output.emit(&cell_states.states, theme, &sim.rule);
+    }
 }
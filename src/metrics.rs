@@ -0,0 +1,299 @@
+use crate::i18n::Localized;
+use crate::keybindings::Action;
+use crate::theme::{ThemeColor, Themed, ThemedText};
+use crate::{ALIVE, GRID_SIZE, Grid};
+use bevy::prelude::*;
+use std::fmt::Write as _;
+use std::fs;
+
+/// 指标文件保存在当前工作目录下
+pub const METRICS_CSV_FILE: &str = "lifegame_metrics.csv";
+pub const METRICS_JSON_FILE: &str = "lifegame_metrics.json";
+
+/// 一代的统计：人口只计存活细胞，新生 / 死亡相对上一代
+/// 边界框为存活细胞的最小、最大坐标（含边界），没有存活细胞时为 None
+pub struct GenerationMetrics {
+    pub generation: u64,
+    pub population: usize,
+    pub births: usize,
+    pub deaths: usize,
+    pub bbox: Option<(usize, usize, usize, usize)>,
+    pub hash: u64,
+}
+
+impl GenerationMetrics {
+    pub fn measure(generation: u64, previous: Option<&Grid>, grid: &Grid) -> Self {
+        let mut metrics = GenerationMetrics {
+            generation,
+            population: 0,
+            births: 0,
+            deaths: 0,
+            bbox: None,
+            hash: grid_hash(grid),
+        };
+        for y in 0..GRID_SIZE {
+            for x in 0..GRID_SIZE {
+                let alive = grid[y][x] == ALIVE;
+                if let Some(previous) = previous {
+                    match (previous[y][x] == ALIVE, alive) {
+                        (false, true) => metrics.births += 1,
+                        (true, false) => metrics.deaths += 1,
+                        _ => {}
+                    }
+                }
+                if alive {
+                    metrics.population += 1;
+                    metrics.bbox = Some(match metrics.bbox {
+                        Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                        None => (x, y, x, y),
+                    });
+                }
+            }
+        }
+        metrics
+    }
+}
+
+/// 网格的 64 位 FNV-1a 哈希，跨平台、跨版本稳定，可用来比对两次运行或找周期
+pub fn grid_hash(grid: &Grid) -> u64 {
+    grid.iter()
+        .flatten()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, state| {
+            (hash ^ *state as u64).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// 每代指标的时间序列，按代数从小到大排列
+#[derive(Resource, Default)]
+pub struct Metrics {
+    pub samples: Vec<GenerationMetrics>,
+}
+
+impl Metrics {
+    /// 记录从 current（第 generation 代）演化到 next 的一步
+    /// 代数回退（清空、撤销、打开会话）时丢弃之后的旧记录，保证序列连续；
+    /// 当前这一代的记录与网格不符（如两代之间手动编辑过）时重新统计
+    pub fn record_step(&mut self, generation: u64, current: &Grid, next: &Grid) {
+        let keep = self.samples.partition_point(|s| s.generation < generation);
+        let current_hash = grid_hash(current);
+        match self.samples.get(keep) {
+            Some(s) if s.generation == generation && s.hash == current_hash => {
+                self.samples.truncate(keep + 1)
+            }
+            _ => {
+                self.samples.truncate(keep);
+                self.samples
+                    .push(GenerationMetrics::measure(generation, None, current));
+            }
+        }
+        self.samples.push(GenerationMetrics::measure(
+            generation + 1,
+            Some(current),
+            next,
+        ));
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out =
+            String::from("generation,population,births,deaths,min_x,min_y,max_x,max_y,hash\n");
+        for s in &self.samples {
+            let bbox = match s.bbox {
+                Some((x0, y0, x1, y1)) => format!("{},{},{},{}", x0, y0, x1, y1),
+                None => ",,,".to_string(),
+            };
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{},{:016x}",
+                s.generation, s.population, s.births, s.deaths, bbox, s.hash
+            );
+        }
+        out
+    }
+
+    /// 哈希写成十六进制字符串，避免超出 JSON 数字的安全整数范围
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("[\n");
+        for (i, s) in self.samples.iter().enumerate() {
+            let bbox = match s.bbox {
+                Some((x0, y0, x1, y1)) => format!("[{}, {}, {}, {}]", x0, y0, x1, y1),
+                None => "null".to_string(),
+            };
+            let _ = write!(
+                out,
+                "  {{\"generation\": {}, \"population\": {}, \"births\": {}, \"deaths\": {}, \"bbox\": {}, \"hash\": \"{:016x}\"}}",
+                s.generation, s.population, s.births, s.deaths, bbox, s.hash
+            );
+            out += if i + 1 < self.samples.len() {
+                ",\n"
+            } else {
+                "\n"
+            };
+        }
+        out += "]\n";
+        out
+    }
+
+    /// 按扩展名选择格式：.json 写 JSON，其余写 CSV
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let text = if path.ends_with(".json") {
+            self.to_json()
+        } else {
+            self.to_csv()
+        };
+        fs::write(path, text).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+/// 指标导出按钮
+#[derive(Component, Clone, Copy)]
+pub enum MetricsButton {
+    Csv,
+    Json,
+}
+
+/// 左上方的指标导出按钮（与录制按钮同样大小，两个一行）
+pub fn metrics_ui(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(144.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|row| {
+            for (label, button) in [
+                ("metrics.csv", MetricsButton::Csv),
+                ("metrics.json", MetricsButton::Json),
+            ] {
+                row.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(68.0),
+                            height: Val::Px(24.0),
+                            margin: UiRect::all(Val::Px(2.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    button,
+                    Themed(ThemeColor::SessionButton),
+                ))
+                .with_children(|btn| {
+                    btn.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 13.0,
+                                color: Color::WHITE,
+                            },
+                        ),
+                        Localized(label),
+                        ThemedText(ThemeColor::ButtonText),
+                    ));
+                });
+            }
+        });
+}
+
+/// 点击按钮或按快捷键把已记录的指标写成 CSV / JSON
+pub fn metrics_export_system(
+    interaction_query: Query<(&Interaction, &MetricsButton), Changed<Interaction>>,
+    mut actions: EventReader<Action>,
+    metrics: Res<Metrics>,
+) {
+    let mut files = Vec::new();
+    for action in actions.read() {
+        if *action == Action::ExportMetrics {
+            files.push(METRICS_CSV_FILE);
+        }
+    }
+    for (interaction, button) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            files.push(match button {
+                MetricsButton::Csv => METRICS_CSV_FILE,
+                MetricsButton::Json => METRICS_JSON_FILE,
+            });
+        }
+    }
+    for path in files {
+        match metrics.save(path) {
+            Ok(()) => info!("已导出 {} 代的指标到 {}", metrics.samples.len(), path),
+            Err(e) => error!("导出指标失败: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEAD;
+
+    const EMPTY: Grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+
+    fn grid(cells: &[(usize, usize)]) -> Grid {
+        let mut grid = EMPTY;
+        for &(x, y) in cells {
+            grid[y][x] = ALIVE;
+        }
+        grid
+    }
+
+    fn generations(metrics: &Metrics) -> Vec<u64> {
+        metrics.samples.iter().map(|s| s.generation).collect()
+    }
+
+    #[test]
+    fn record_steps() {
+        // 闪烁器：横竖交替
+        let across = grid(&[(1, 2), (2, 2), (3, 2)]);
+        let down = grid(&[(2, 1), (2, 2), (2, 3)]);
+        let mut metrics = Metrics::default();
+        metrics.record_step(0, &across, &down);
+        metrics.record_step(1, &down, &across);
+        assert_eq!(generations(&metrics), [0, 1, 2]);
+        let last = &metrics.samples[2];
+        assert_eq!((last.population, last.births, last.deaths), (3, 2, 2));
+        assert_eq!(last.bbox, Some((1, 2, 3, 2)));
+
+        // 两代之间的编辑：第 2 代按编辑后的网格重新统计
+        let edited = grid(&[(1, 2), (2, 2), (3, 2), (10, 10)]);
+        metrics.record_step(2, &edited, &EMPTY);
+        assert_eq!(generations(&metrics), [0, 1, 2, 3]);
+        assert_eq!(metrics.samples[2].population, 4);
+        assert_eq!(metrics.samples[2].hash, grid_hash(&edited));
+        assert_eq!(metrics.samples[3].deaths, 4);
+
+        // 代数回退后丢弃之后的记录
+        metrics.record_step(1, &down, &across);
+        assert_eq!(generations(&metrics), [0, 1, 2]);
+        assert_eq!(metrics.samples[2].hash, grid_hash(&across));
+    }
+
+    #[test]
+    fn csv_and_json() {
+        let mut metrics = Metrics::default();
+        metrics.record_step(0, &grid(&[(4, 5), (6, 7)]), &EMPTY);
+        let empty_hash = format!("{:016x}", grid_hash(&EMPTY));
+        assert_eq!(empty_hash.len(), 16);
+        assert_ne!(grid_hash(&EMPTY), grid_hash(&grid(&[(0, 0)])));
+
+        let csv = metrics.to_csv();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("0,2,0,0,4,5,6,7,"));
+        assert_eq!(lines[2], format!("1,0,0,2,,,,,{}", empty_hash));
+
+        let json = metrics.to_json();
+        assert!(json.contains("\"bbox\": [4, 5, 6, 7]"));
+        assert!(json.contains(&format!(
+            "\"bbox\": null, \"hash\": \"{}\"}}\n]",
+            empty_hash
+        )));
+        assert_eq!(json.matches("},\n").count(), 1);
+    }
+}
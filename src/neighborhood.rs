@@ -0,0 +1,148 @@
+use crate::evolution::{Kernel, Neighborhood};
+use crate::i18n::Language;
+use crate::keybindings::Action;
+use crate::theme::{ThemeColor, Themed, ThemedText};
+use crate::{GridCamera, Simulation};
+use bevy::prelude::*;
+use std::fs;
+
+/// 用户自定义邻域文件，位于当前工作目录
+pub const KERNEL_FILE: &str = "kernel.cfg";
+
+/// 可切换的邻域列表：内置预设加上 kernel.cfg 中的自定义邻域
+#[derive(Resource)]
+pub struct NeighborhoodLibrary {
+    neighborhoods: Vec<Neighborhood>,
+    current: usize,
+}
+
+impl Default for NeighborhoodLibrary {
+    fn default() -> Self {
+        Self {
+            neighborhoods: vec![
+                Neighborhood::Moore(1),
+                Neighborhood::VonNeumann(1),
+                Neighborhood::Hexagonal,
+                Neighborhood::Moore(2),
+                Neighborhood::VonNeumann(2),
+                Neighborhood::Moore(3),
+                Neighborhood::VonNeumann(3),
+            ],
+            current: 0,
+        }
+    }
+}
+
+/// 启动时读取自定义邻域：每行一组权重，中心为正中的格子
+pub fn load_kernel(mut library: ResMut<NeighborhoodLibrary>) {
+    let Ok(text) = fs::read_to_string(KERNEL_FILE) else {
+        return;
+    };
+    match Kernel::parse(&text) {
+        Ok(kernel) => {
+            info!(
+                "从 {} 读取了 {}×{} 的自定义邻域",
+                KERNEL_FILE, kernel.width, kernel.height
+            );
+            library.neighborhoods.push(Neighborhood::Custom(kernel));
+        }
+        Err(e) => warn!("{} 无效: {}", KERNEL_FILE, e),
+    }
+}
+
+/// 按钮上显示的名称
+fn label(neighborhood: &Neighborhood, language: Language) -> String {
+    let name = match neighborhood {
+        Neighborhood::Moore(1) => language.tr("neighborhood.moore").to_string(),
+        Neighborhood::Moore(r) => format!("{} r{}", language.tr("neighborhood.moore"), r),
+        Neighborhood::VonNeumann(1) => language.tr("neighborhood.von_neumann").to_string(),
+        Neighborhood::VonNeumann(r) => {
+            format!("{} r{}", language.tr("neighborhood.von_neumann"), r)
+        }
+        Neighborhood::Hexagonal => language.tr("neighborhood.hex").to_string(),
+        Neighborhood::Custom(_) => language.tr("neighborhood.custom").to_string(),
+    };
+    language.trf("label.neighborhood", name)
+}
+
+/// 邻域切换按钮
+#[derive(Component)]
+pub struct NeighborhoodButton;
+
+#[derive(Component)]
+pub struct NeighborhoodButtonText;
+
+pub fn neighborhood_ui(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(140.0),
+                    height: Val::Px(40.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            NeighborhoodButton,
+            Themed(ThemeColor::ModeButton),
+        ))
+        .with_children(|r| {
+            r.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 18.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                NeighborhoodButtonText,
+                ThemedText(ThemeColor::ButtonText),
+            ));
+        });
+}
+
+/// 点击按钮或按快捷键循环切换邻域；六边形邻域同时切换网格为错行显示
+#[allow(clippy::too_many_arguments)]
+pub fn neighborhood_switch_system(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<NeighborhoodButton>)>,
+    mut actions: EventReader<Action>,
+    mut library: ResMut<NeighborhoodLibrary>,
+    mut sim: ResMut<Simulation>,
+    mut camera: ResMut<GridCamera>,
+    language: Res<Language>,
+    mut text: Query<&mut Text, With<NeighborhoodButtonText>>,
+    mut last: Local<Option<Neighborhood>>,
+) {
+    let pressed = interaction_query
+        .iter()
+        .filter(|i| **i == Interaction::Pressed)
+        .count();
+    let cycles = pressed
+        + actions
+            .read()
+            .filter(|a| **a == Action::CycleNeighborhood)
+            .count();
+    if cycles > 0 {
+        library.current = (library.current + cycles) % library.neighborhoods.len();
+        sim.neighborhood = library.neighborhoods[library.current].clone();
+        info!("切换邻域: {}", sim.neighborhood.name());
+    }
+
+    // 邻域也可能由打开的会话改变，这里统一同步显示
+    let changed = last.as_ref() != Some(&sim.neighborhood);
+    if changed {
+        *last = Some(sim.neighborhood.clone());
+        let hex = sim.neighborhood.is_hexagonal();
+        if camera.hex != hex {
+            camera.hex = hex;
+        }
+    }
+    if changed || language.is_changed() {
+        for mut text in &mut text {
+            text.sections[0].value = label(&sim.neighborhood, *language);
+        }
+    }
+}
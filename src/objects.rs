@@ -0,0 +1,573 @@
+use crate::evolution::{Neighborhood, Rule, Topology, next_generation};
+use crate::{ALIVE, DEAD, GRID_SIZE, Grid};
+use std::collections::{HashMap, VecDeque};
+
+/// 等待网格进入周期状态的最多代数
+pub const SETTLE_LIMIT: u64 = 5000;
+/// 单个物体识别时的最大周期
+pub const MAX_PERIOD: usize = 64;
+/// 分离物体时合并的代数：振荡器各相位的细胞连在一起算同一个物体
+const UNION_GENERATIONS: u64 = 8;
+/// 单独演化物体时四周留出的空白
+const MARGIN: i32 = 4;
+/// apgcode 中的字符：前 32 个表示一列 5 格的取值，y 之后的空列数按 36 进制书写
+const CODE_CHARS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// 已知物体（仅对康威规则、摩尔邻域有效）：(apgcode, i18n 字符串表中的 key)
+const CATALOG: &[(&str, &str)] = &[
+    ("xs4_33", "object.block"),
+    ("xs6_696", "object.beehive"),
+    ("xs7_2596", "object.loaf"),
+    ("xs5_253", "object.boat"),
+    ("xs6_356", "object.ship"),
+    ("xs4_252", "object.tub"),
+    ("xs8_6996", "object.pond"),
+    ("xs6_25a4", "object.barge"),
+    ("xs7_25ac", "object.long_boat"),
+    ("xs7_178c", "object.eater"),
+    ("xp2_7", "object.blinker"),
+    ("xp2_7e", "object.toad"),
+    ("xp2_318c", "object.beacon"),
+    (
+        "xp3_co9nas0san9oczgoldlo0oldlogz1047210127401",
+        "object.pulsar",
+    ),
+    ("xp15_4r4z4r4", "object.pentadecathlon"),
+    ("xq4_153", "object.glider"),
+    ("xq4_6frc", "object.lwss"),
+    ("xq4_27dee6", "object.mwss"),
+    ("xq4_27deee6", "object.hwss"),
+];
+
+/// 活细胞坐标 (x, y)
+pub type Cells = Vec<(i32, i32)>;
+
+/// 演化环境：规则、邻域与拓扑
+pub struct World<'a> {
+    pub rule: &'a Rule,
+    pub neighborhood: &'a Neighborhood,
+    pub topology: Topology,
+}
+
+impl World<'_> {
+    pub fn step(&self, grid: &Grid) -> Grid {
+        next_generation(grid, self.rule, self.neighborhood, self.topology)
+    }
+
+    /// 同样的规则与邻域，边界改为有界
+    fn bounded(&self) -> World<'_> {
+        World {
+            rule: self.rule,
+            neighborhood: self.neighborhood,
+            topology: Topology::Bounded,
+        }
+    }
+
+    /// 目录中的名称只对康威规则、摩尔邻域成立
+    pub fn name_key(&self, code: &str) -> Option<&'static str> {
+        let conway = Rule::parse("B3/S23").is_some_and(|r| r == *self.rule)
+            && *self.neighborhood == Neighborhood::default();
+        conway
+            .then(|| CATALOG.iter().find(|(c, _)| *c == code).map(|(_, k)| *k))
+            .flatten()
+    }
+}
+
+/// 物体的类别
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ObjectKind {
+    StillLife,
+    Oscillator,
+    Spaceship,
+}
+
+impl ObjectKind {
+    pub fn key(self) -> &'static str {
+        match self {
+            ObjectKind::StillLife => "object.kind.still_life",
+            ObjectKind::Oscillator => "object.kind.oscillator",
+            ObjectKind::Spaceship => "object.kind.spaceship",
+        }
+    }
+}
+
+/// 单个物体的识别结果
+/// heat 为一个周期内平均每代改变状态的格子数；name 为目录中的 i18n key
+pub struct Object {
+    pub code: String,
+    pub name: Option<&'static str>,
+    pub kind: ObjectKind,
+    pub period: usize,
+    pub displacement: (i32, i32),
+    pub heat: f32,
+}
+
+fn live_cells(grid: &Grid) -> Cells {
+    let mut cells = Vec::new();
+    for (y, row) in grid.iter().enumerate() {
+        for (x, &state) in row.iter().enumerate() {
+            if state != DEAD {
+                cells.push((x as i32, y as i32));
+            }
+        }
+    }
+    cells
+}
+
+/// 平移到左上角为原点并排序，返回原来的左上角
+fn normalize(cells: &[(i32, i32)]) -> (Cells, (i32, i32)) {
+    let x0 = cells.iter().map(|c| c.0).min().unwrap_or(0);
+    let y0 = cells.iter().map(|c| c.1).min().unwrap_or(0);
+    let mut out: Cells = cells.iter().map(|&(x, y)| (x - x0, y - y0)).collect();
+    out.sort_unstable_by_key(|&(x, y)| (y, x));
+    (out, (x0, y0))
+}
+
+/// 扩展 Wechsler 记法：每 5 行为一条，逐列写出 5 位的值，条之间用 z 分隔
+/// 连续的空列压缩为 w（2 列）、x（3 列）、yN（4 + N 列），每条末尾的空列省略
+fn wechsler(cells: &[(i32, i32)]) -> String {
+    let (cells, _) = normalize(cells);
+    let width = cells.iter().map(|c| c.0 + 1).max().unwrap_or(0);
+    let height = cells.iter().map(|c| c.1 + 1).max().unwrap_or(0);
+    let mut columns = vec![vec![0u8; width as usize]; (height as usize).div_ceil(5)];
+    for &(x, y) in &cells {
+        columns[y as usize / 5][x as usize] |= 1 << (y % 5);
+    }
+
+    let mut strips = Vec::new();
+    for strip in columns {
+        let mut out = String::new();
+        let mut zeros = 0;
+        for value in strip {
+            if value == 0 {
+                zeros += 1;
+                continue;
+            }
+            while zeros > 0 {
+                match zeros {
+                    1 => out.push('0'),
+                    2 => out.push('w'),
+                    3 => out.push('x'),
+                    n => {
+                        let extra = (n - 4).min(CODE_CHARS.len() - 1);
+                        out.push('y');
+                        out.push(CODE_CHARS[extra] as char);
+                        zeros -= extra + 4;
+                        continue;
+                    }
+                }
+                zeros = 0;
+            }
+            out.push(CODE_CHARS[value as usize] as char);
+        }
+        strips.push(out);
+    }
+    strips.join("z")
+}
+
+/// 8 种旋转 / 翻转
+fn transform(cells: &[(i32, i32)], flags: u8) -> Cells {
+    cells
+        .iter()
+        .map(|&(x, y)| {
+            let (x, y) = if flags & 1 != 0 { (y, x) } else { (x, y) };
+            let x = if flags & 2 != 0 { -x } else { x };
+            let y = if flags & 4 != 0 { -y } else { y };
+            (x, y)
+        })
+        .collect()
+}
+
+/// 所有相位、所有旋转翻转中最短（同长取字典序最小）的记法
+fn canonical(phases: &[Cells]) -> String {
+    phases
+        .iter()
+        .flat_map(|phase| (0..8).map(move |flags| wechsler(&transform(phase, flags))))
+        .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+        .unwrap_or_default()
+}
+
+/// 规范化后放到空网格中央，返回网格、规范化的细胞与放置的偏移
+/// 四周留不出 MARGIN 的空白时返回 None
+fn place(cells: &[(i32, i32)]) -> Option<(Grid, Cells, (i32, i32))> {
+    let (start, _) = normalize(cells);
+    let width = start.iter().map(|c| c.0 + 1).max()?;
+    let height = start.iter().map(|c| c.1 + 1).max()?;
+    let limit = GRID_SIZE as i32 - 2 * MARGIN;
+    if width > limit || height > limit {
+        return None;
+    }
+
+    let mut grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+    let offset = (
+        (GRID_SIZE as i32 - width) / 2,
+        (GRID_SIZE as i32 - height) / 2,
+    );
+    for &(x, y) in &start {
+        grid[(y + offset.1) as usize][(x + offset.0) as usize] = ALIVE;
+    }
+    Some((grid, start, offset))
+}
+
+/// 是否有细胞到了网格最外一圈（再演化就会受边界影响）
+fn touches_edge(cells: &[(i32, i32)]) -> bool {
+    let edge = GRID_SIZE as i32 - 1;
+    cells
+        .iter()
+        .any(|&(x, y)| x == 0 || y == 0 || x == edge || y == edge)
+}
+
+/// 把物体单独放在空网格中央（有界）演化，找出最小周期与位移
+/// 物体消亡、碰到边界或在 MAX_PERIOD 代内不重复时返回 None；只支持两状态规则
+pub fn analyse(cells: &[(i32, i32)], world: &World) -> Option<Object> {
+    if cells.is_empty() || world.rule.states() != 2 {
+        return None;
+    }
+    let (mut grid, start, offset) = place(cells)?;
+    let bounded = world.bounded();
+
+    let mut phases = vec![start.clone()];
+    let mut changes = 0;
+    for period in 1..=MAX_PERIOD {
+        let next = bounded.step(&grid);
+        changes += grid
+            .iter()
+            .flatten()
+            .zip(next.iter().flatten())
+            .filter(|(a, b)| a != b)
+            .count();
+        grid = next;
+        let cells = live_cells(&grid);
+        if cells.is_empty() || touches_edge(&cells) {
+            return None;
+        }
+        let (normalized, origin) = normalize(&cells);
+        if normalized == start {
+            let displacement = (origin.0 - offset.0, origin.1 - offset.1);
+            let kind = if displacement != (0, 0) {
+                ObjectKind::Spaceship
+            } else if period == 1 {
+                ObjectKind::StillLife
+            } else {
+                ObjectKind::Oscillator
+            };
+            let prefix = match kind {
+                ObjectKind::StillLife => format!("xs{}", start.len()),
+                ObjectKind::Oscillator => format!("xp{}", period),
+                ObjectKind::Spaceship => format!("xq{}", period),
+            };
+            let code = format!("{}_{}", prefix, canonical(&phases));
+            return Some(Object {
+                name: world.name_key(&code),
+                code,
+                kind,
+                period,
+                displacement,
+                heat: changes as f32 / period as f32,
+            });
+        }
+        phases.push(normalized);
+    }
+    None
+}
+
+/// 图案识别的结果：进入周期之前经过的代数，以及周期部分的识别结果
+pub struct Identified {
+    pub transient: u64,
+    pub object: Object,
+}
+
+/// 把图案单独放在空网格中央（有界）演化，直到形状重复（允许平移），再识别重复的部分
+pub fn identify(cells: &[(i32, i32)], world: &World) -> Result<Identified, String> {
+    if world.rule.states() != 2 {
+        return Err("识别只支持两状态规则".to_string());
+    }
+    if cells.is_empty() {
+        return Err("没有活细胞".to_string());
+    }
+    let (mut grid, start, _) = place(cells).ok_or("图案太大，四周留不出空白")?;
+    let bounded = world.bounded();
+
+    // 形状 -> 第一次出现的代数
+    let mut seen: HashMap<Cells, u64> = HashMap::from([(start, 0)]);
+    for generation in 1..=SETTLE_LIMIT {
+        grid = bounded.step(&grid);
+        let cells = live_cells(&grid);
+        if cells.is_empty() {
+            return Err(format!("图案在第 {} 代消亡", generation));
+        }
+        if touches_edge(&cells) {
+            return Err(format!("图案在第 {} 代碰到边界", generation));
+        }
+        let (shape, _) = normalize(&cells);
+        if let Some(&first) = seen.get(&shape) {
+            let object = analyse(&shape, world).ok_or(format!(
+                "周期 {} 超过 {}",
+                generation - first,
+                MAX_PERIOD
+            ))?;
+            return Ok(Identified {
+                transient: first,
+                object,
+            });
+        }
+        seen.insert(shape, generation);
+    }
+    Err(format!("{} 代内没有进入周期", SETTLE_LIMIT))
+}
+
+/// 演化到网格状态重复为止
+pub struct Settled {
+    pub grid: Grid,
+    // 从起点演化的代数
+    pub generations: u64,
+    pub period: u64,
+}
+
+/// 从 grid 演化，直到出现重复的网格；SETTLE_LIMIT 代内没有重复时返回 None
+pub fn settle(grid: &Grid, world: &World) -> Option<Settled> {
+    let mut seen: HashMap<Grid, u64> = HashMap::new();
+    let mut grid = *grid;
+    for generation in 0..=SETTLE_LIMIT {
+        if let Some(first) = seen.insert(grid, generation) {
+            return Some(Settled {
+                grid,
+                generations: generation,
+                period: generation - first,
+            });
+        }
+        grid = world.step(&grid);
+    }
+    None
+}
+
+/// 把稳定后的网格分成互不相连的物体
+/// 合并若干代的活细胞后按 8 邻接求连通块，环面上跨边界相连的块展开成连续坐标
+pub fn separate(settled: &Settled, world: &World) -> Vec<Cells> {
+    let mut union = settled.grid;
+    let mut grid = settled.grid;
+    for _ in 1..settled.period.min(UNION_GENERATIONS) {
+        grid = world.step(&grid);
+        for (u, s) in union.iter_mut().flatten().zip(grid.iter().flatten()) {
+            if *s != DEAD {
+                *u = ALIVE;
+            }
+        }
+    }
+
+    let n = GRID_SIZE as i32;
+    let torus = world.topology == Topology::Torus;
+    let mut visited = [[false; GRID_SIZE]; GRID_SIZE];
+    let mut objects = Vec::new();
+    for (sy, sx) in (0..GRID_SIZE).flat_map(|y| (0..GRID_SIZE).map(move |x| (y, x))) {
+        if visited[sy][sx] || union[sy][sx] == DEAD {
+            continue;
+        }
+        visited[sy][sx] = true;
+        let mut object = Vec::new();
+        let mut queue = VecDeque::from([(sx as i32, sy as i32)]);
+        // 队列中为展开后的坐标，取模得到网格中的位置
+        while let Some((x, y)) = queue.pop_front() {
+            let (gx, gy) = (x.rem_euclid(n) as usize, y.rem_euclid(n) as usize);
+            if settled.grid[gy][gx] != DEAD {
+                object.push((x, y));
+            }
+            for (dx, dy) in (-1..=1).flat_map(|dy| (-1..=1).map(move |dx| (dx, dy))) {
+                let (nx, ny) = (x + dx, y + dy);
+                if !torus && (nx < 0 || ny < 0 || nx >= n || ny >= n) {
+                    continue;
+                }
+                let (gx, gy) = (nx.rem_euclid(n) as usize, ny.rem_euclid(n) as usize);
+                if !visited[gy][gx] && union[gy][gx] != DEAD {
+                    visited[gy][gx] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        if !object.is_empty() {
+            objects.push(object);
+        }
+    }
+    objects
+}
+
+/// 普查中的一类物体，未能识别的物体 object 为 None
+pub struct CensusEntry {
+    pub object: Option<Object>,
+    pub count: usize,
+}
+
+/// 普查结果：稳定所用的代数、整体周期，以及按数量从多到少排列的各类物体
+pub struct Census {
+    pub generations: u64,
+    pub period: u64,
+    pub entries: Vec<CensusEntry>,
+}
+
+/// 演化到稳定，分离物体并逐个识别、按 apgcode 归类计数
+pub fn census(grid: &Grid, world: &World) -> Result<Census, String> {
+    if world.rule.states() != 2 {
+        return Err("普查只支持两状态规则".to_string());
+    }
+    let settled = settle(grid, world).ok_or(format!("{} 代内没有稳定", SETTLE_LIMIT))?;
+    let mut entries: Vec<CensusEntry> = Vec::new();
+    for cells in separate(&settled, world) {
+        let object = analyse(&cells, world);
+        let code = object.as_ref().map(|o| o.code.clone());
+        match entries
+            .iter_mut()
+            .find(|e| e.object.as_ref().map(|o| &o.code) == code.as_ref())
+        {
+            Some(entry) => entry.count += 1,
+            None => entries.push(CensusEntry { object, count: 1 }),
+        }
+    }
+    entries.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.object.is_none().cmp(&b.object.is_none()))
+            .then_with(|| {
+                let key = |e: &CensusEntry| e.object.as_ref().map(|o| (o.kind, o.code.clone()));
+                key(a).cmp(&key(b))
+            })
+    });
+    Ok(Census {
+        generations: settled.generations,
+        period: settled.period,
+        entries,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 由字符画构造活细胞坐标，# 为活细胞
+    fn cells(rows: &[&str]) -> Cells {
+        let mut cells = Vec::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '#' {
+                    cells.push((x as i32, y as i32));
+                }
+            }
+        }
+        cells
+    }
+
+    fn conway() -> (Rule, Neighborhood) {
+        (Rule::parse("B3/S23").unwrap(), Neighborhood::default())
+    }
+
+    const GLIDER: [&str; 3] = [".#.", "..#", "###"];
+
+    #[test]
+    fn wechsler_strips_and_gaps() {
+        assert_eq!(wechsler(&cells(&["##", "##"])), "33");
+        assert_eq!(wechsler(&cells(&["#.#"])), "101");
+        // 4 列空白记作 y0，第 6 行起进入下一条
+        assert_eq!(wechsler(&cells(&["#....#"])), "1y01");
+        assert_eq!(wechsler(&cells(&["#", ".", ".", ".", ".", "#"])), "1z1");
+    }
+
+    #[test]
+    fn canonical_ignores_orientation() {
+        let glider = cells(&GLIDER);
+        let code = canonical(std::slice::from_ref(&glider));
+        for flags in 0..8 {
+            assert_eq!(canonical(&[transform(&glider, flags)]), code);
+        }
+    }
+
+    #[test]
+    fn analyse_known_objects() {
+        let (rule, neighborhood) = conway();
+        let world = World {
+            rule: &rule,
+            neighborhood: &neighborhood,
+            topology: Topology::Bounded,
+        };
+        let cases: [(&[&str], &str, ObjectKind, usize); 5] = [
+            (&["##", "##"], "xs4_33", ObjectKind::StillLife, 1),
+            (
+                &[".##.", "#..#", ".##."],
+                "xs6_696",
+                ObjectKind::StillLife,
+                1,
+            ),
+            (&["###"], "xp2_7", ObjectKind::Oscillator, 2),
+            (&GLIDER, "xq4_153", ObjectKind::Spaceship, 4),
+            (
+                &[".#..#", "#....", "#...#", "####."],
+                "xq4_6frc",
+                ObjectKind::Spaceship,
+                4,
+            ),
+        ];
+        for (rows, code, kind, period) in cases {
+            let object = analyse(&cells(rows), &world).unwrap();
+            assert_eq!(object.code, code);
+            assert_eq!(object.kind, kind);
+            assert_eq!(object.period, period);
+            assert!(object.name.is_some());
+        }
+
+        let glider = analyse(&cells(&GLIDER), &world).unwrap();
+        assert_eq!(glider.displacement, (1, 1));
+        assert_eq!(glider.name, Some("object.glider"));
+
+        // 其他规则下不套用康威规则的名称
+        let highlife = Rule::parse("B36/S23").unwrap();
+        let world = World {
+            rule: &highlife,
+            ..world
+        };
+        let block = analyse(&cells(&["##", "##"]), &world).unwrap();
+        assert_eq!(block.code, "xs4_33");
+        assert_eq!(block.name, None);
+    }
+
+    #[test]
+    fn identify_after_transient() {
+        let (rule, neighborhood) = conway();
+        let world = World {
+            rule: &rule,
+            neighborhood: &neighborhood,
+            topology: Topology::Bounded,
+        };
+        // 一行 10 个细胞演化成十五周期振荡器
+        let identified = identify(&cells(&["##########"]), &world).unwrap();
+        assert!(identified.transient > 0);
+        assert_eq!(identified.object.code, "xp15_4r4z4r4");
+        assert!(identify(&cells(&["#"]), &world).is_err());
+    }
+
+    #[test]
+    fn census_counts_objects() {
+        let (rule, neighborhood) = conway();
+        let world = World {
+            rule: &rule,
+            neighborhood: &neighborhood,
+            topology: Topology::Bounded,
+        };
+        let mut grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+        let objects = [
+            (&["##", "##"][..], (2, 2)),
+            (&["##", "##"], (20, 5)),
+            (&["###"], (10, 20)),
+        ];
+        for (rows, (ox, oy)) in objects {
+            for (x, y) in cells(rows) {
+                grid[(y + oy) as usize][(x + ox) as usize] = ALIVE;
+            }
+        }
+        let census = census(&grid, &world).unwrap();
+        assert_eq!(census.period, 2);
+        let counts: Vec<_> = census
+            .entries
+            .iter()
+            .map(|e| (e.object.as_ref().unwrap().code.as_str(), e.count))
+            .collect();
+        assert_eq!(counts, [("xs4_33", 2), ("xp2_7", 1)]);
+    }
+}
@@ -1,8 +1,12 @@
-use crate::{CellStates, GRID_SIZE, set_cell_state};
+use crate::edit::CellEdit;
+use crate::i18n::Localized;
+use crate::keybindings::Action;
+use crate::objects::{World, analyse};
+use crate::theme::{ThemeColor, Themed, ThemedText};
 use bevy::prelude::*;
 
 /// 定义图案按钮的种类
-#[derive(Component)]
+#[derive(Component, Clone, Copy, Debug)]
 pub enum PatternButton {
     Glider,
     Block,
@@ -182,92 +186,125 @@ fn shuttle_pattern() -> Vec<(usize, usize)> {
     ]
 }
 
+impl PatternButton {
+    /// 图案的细胞坐标
+    fn cells(self) -> Vec<(usize, usize)> {
+        match self {
+            PatternButton::Pulsar => pulsar_pattern(),
+            PatternButton::Glider => glider_pattern(),
+            PatternButton::Block => block_pattern(),
+            PatternButton::KaiYing => kaiying_pattern(),
+            PatternButton::Shuttle => shuttle_pattern(),
+            PatternButton::CShuttle => cshuffle_pattern(),
+        }
+    }
+}
+
+/// 在示例图案中查找识别代码为 code 的图案，返回其名称（i18n 字符串表中的 key）
+/// 各图案按当前规则单独识别，不是周期图案的不参与比较
+pub fn library_name(code: &str, world: &World) -> Option<&'static str> {
+    pattern_list()
+        .into_iter()
+        .find(|(_, pattern)| {
+            let cells: Vec<(i32, i32)> = pattern
+                .cells()
+                .iter()
+                .map(|&(x, y)| (x as i32, y as i32))
+                .collect();
+            analyse(&cells, world).is_some_and(|object| object.code == code)
+        })
+        .map(|(label, _)| label)
+}
+
+/// 按钮列表（名称为 i18n 字符串表中的 key），顺序同时决定数字快捷键（1 对应第一个）
+fn pattern_list() -> [(&'static str, PatternButton); 6] {
+    [
+        ("pattern.block", PatternButton::Block),
+        ("pattern.glider", PatternButton::Glider),
+        ("pattern.pulsar", PatternButton::Pulsar),
+        ("pattern.kaiying", PatternButton::KaiYing),
+        ("pattern.shuttle", PatternButton::Shuttle),
+        ("pattern.cshuttle", PatternButton::CShuttle),
+    ]
+}
+
 /// 右侧图案按钮 UI 生成
 pub fn patterns_ui(parent: &mut ChildBuilder, asset_server: &Res<AssetServer>) {
     let font = asset_server.load("fonts/ZCOOLKuaiLe-Regular.ttf");
 
     // “示例图案”标题
-    parent.spawn(TextBundle::from_section(
-        "示例图案：",
-        TextStyle {
-            font: font.clone(),
-            font_size: 18.0,
-            color: Color::BLACK,
-        },
+    parent.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: font.clone(),
+                font_size: 18.0,
+                color: Color::BLACK,
+            },
+        ),
+        Localized("label.patterns"),
+        ThemedText(ThemeColor::Text),
     ));
 
-    // 按钮列表
-    let patterns = vec![
-        ("方块", PatternButton::Block),
-        ("滑翔机", PatternButton::Glider),
-        ("脉冲星", PatternButton::Pulsar),
-        ("慨影", PatternButton::KaiYing),
-        ("穿梭机", PatternButton::Shuttle),
-        ("环状穿梭机", PatternButton::CShuttle),
-    ];
-
-    for (label, pattern_type) in patterns {
+    for (label, pattern_type) in pattern_list() {
         parent
             .spawn((
                 ButtonBundle {
                     style: Style {
                         width: Val::Px(160.0),
                         height: Val::Px(40.0),
-                        margin: UiRect::all(Val::Px(8.0)),
+                        margin: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
                         justify_content: JustifyContent::Center,
                         align_items: AlignItems::Center,
                         ..default()
                     },
-                    background_color: Color::srgb(0.4, 0.4, 0.8).into(),
                     ..default()
                 },
                 pattern_type,
+                Themed(ThemeColor::PatternButton),
             ))
             .with_children(|btn| {
-                btn.spawn(TextBundle::from_section(
-                    label,
-                    TextStyle {
-                        font: font.clone(),
-                        font_size: 20.0,
-                        color: Color::WHITE,
-                    },
+                btn.spawn((
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 20.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    Localized(label),
+                    ThemedText(ThemeColor::ButtonText),
                 ));
             });
     }
 }
 
-/// 点击按钮后加载对应图案
+/// 点击按钮或按数字键后加载对应图案
 pub fn pattern_button_system(
     mut interaction_query: Query<(&Interaction, &PatternButton), Changed<Interaction>>,
-    mut cell_states: ResMut<CellStates>,
-    mut commands: Commands,
+    mut actions: EventReader<Action>,
+    mut edits: EventWriter<CellEdit>,
 ) {
-    for (interaction, pattern) in &mut interaction_query {
-        if *interaction == Interaction::Pressed {
-            let coords = match pattern {
-                PatternButton::Pulsar => pulsar_pattern(),
-                PatternButton::Glider => glider_pattern(),
-                PatternButton::Block => block_pattern(),
-                PatternButton::KaiYing => kaiying_pattern(),
-                PatternButton::Shuttle => shuttle_pattern(),
-                PatternButton::CShuttle => cshuffle_pattern(),
-            };
+    let mut selected: Vec<PatternButton> = interaction_query
+        .iter_mut()
+        .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, pattern)| *pattern)
+        .collect();
+    for action in actions.read() {
+        if let Action::Pattern(n) = action
+            && let Some((_, pattern)) = pattern_list().get(*n)
+        {
+            selected.push(*pattern);
+        }
+    }
 
-            // 清空旧图案
-            for y in 0..GRID_SIZE {
-                for x in 0..GRID_SIZE {
-                    set_cell_state(&mut commands, &mut cell_states, x, y, false);
-                }
-            }
+    for pattern in selected {
+        let coords = pattern.cells();
 
-            // 应用新图案
-            for (x, y) in coords {
-                if x < GRID_SIZE && y < GRID_SIZE {
-                    set_cell_state(&mut commands, &mut cell_states, x, y, true);
-                }
-            }
+        // 清空旧图案并应用新图案（作为一次可撤销的编辑）
+        edits.send(CellEdit::replace_all(&coords));
 
-            info!("已加载图案: {:?}", std::mem::discriminant(pattern));
-        }
+        info!("已加载图案: {:?}", pattern);
     }
 }
@@ -0,0 +1,476 @@
+use crate::export::{ExportPalette, ExportSettings, Frame, RenderOptions, render_frame};
+use crate::grid_lines::GridLines;
+use crate::i18n::Language;
+use crate::keybindings::Action;
+use crate::selection::Selection;
+use crate::theme::{Theme, ThemeColor, Themed, ThemedText};
+use crate::{CellStates, GRID_SIZE, Grid, Simulation};
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+/// 每帧间隔的可选值（毫秒）
+const DELAYS: [u16; 5] = [50, 100, 200, 500, 1000];
+/// 录制代数的可选值，None 为一直录到手动停止
+const LENGTHS: [Option<u64>; 5] = [Some(50), Some(100), Some(200), Some(500), None];
+/// 播放次数的可选值，0 为无限循环
+const LOOPS: [u16; 3] = [0, 1, 3];
+
+/// 动画格式
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    Gif,
+    Apng,
+}
+
+impl RecordFormat {
+    fn name(self) -> &'static str {
+        match self {
+            RecordFormat::Gif => "GIF",
+            RecordFormat::Apng => "APNG",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            RecordFormat::Gif => "gif",
+            RecordFormat::Apng => "png",
+        }
+    }
+}
+
+/// 正在录制的动画：只保存每代的网格，停止时再按开始时的设置统一绘制
+struct Take {
+    min: IVec2,
+    max: IVec2,
+    options: RenderOptions,
+    palette: ExportPalette,
+    start: u64,
+    last: u64,
+    grids: Vec<Grid>,
+}
+
+/// 录制设置与当前录制
+#[derive(Resource)]
+pub struct Recorder {
+    pub format: RecordFormat,
+    delay: usize,
+    length: usize,
+    loops: usize,
+    take: Option<Take>,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self {
+            format: RecordFormat::Gif,
+            delay: 1,
+            length: 1,
+            loops: 0,
+            take: None,
+        }
+    }
+}
+
+impl Recorder {
+    pub fn delay_ms(&self) -> u16 {
+        DELAYS[self.delay]
+    }
+
+    pub fn plays(&self) -> u16 {
+        LOOPS[self.loops]
+    }
+}
+
+/// 写成动画 GIF：所有帧共用一张全局调色板，plays 为播放次数（0 为无限循环）
+/// 帧逐个绘制、逐个编码，不必同时放在内存中
+pub fn write_gif(
+    writer: &mut impl Write,
+    frames: impl Iterator<Item = Frame>,
+    colors: &[[u8; 4]],
+    delay_ms: u16,
+    plays: u16,
+) -> Result<(), String> {
+    let mut frames = frames.peekable();
+    let Some(first) = frames.peek() else {
+        return Err("没有可写入的帧".to_string());
+    };
+    if colors.len() > 256 {
+        return Err(format!("颜色数 {} 超过 GIF 的 256 色上限", colors.len()));
+    }
+    let (Ok(width), Ok(height)) = (u16::try_from(first.width), u16::try_from(first.height)) else {
+        return Err(format!(
+            "图像 {}×{} 超过 GIF 的尺寸上限",
+            first.width, first.height
+        ));
+    };
+    let index: HashMap<[u8; 4], u8> = colors
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (*c, i as u8))
+        .collect();
+    let palette: Vec<u8> = colors
+        .iter()
+        .flat_map(|[r, g, b, _]| [*r, *g, *b])
+        .collect();
+
+    let mut encoder =
+        gif::Encoder::new(writer, width, height, &palette).map_err(|e| e.to_string())?;
+    // 只播放一次时不写循环扩展，其余写重复次数
+    let repeat = match plays {
+        0 => Some(gif::Repeat::Infinite),
+        1 => None,
+        n => Some(gif::Repeat::Finite(n - 1)),
+    };
+    if let Some(repeat) = repeat {
+        encoder.set_repeat(repeat).map_err(|e| e.to_string())?;
+    }
+    for frame in frames {
+        let indices: Vec<u8> = frame
+            .pixels
+            .chunks_exact(4)
+            .map(|p| index.get(p).copied().unwrap_or(0))
+            .collect();
+        let mut frame = gif::Frame::from_indexed_pixels(width, height, indices, None);
+        frame.delay = delay_ms / 10;
+        encoder.write_frame(&frame).map_err(|e| e.to_string())?;
+    }
+    encoder.into_inner().map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// 写成 APNG：每帧间隔 delay_ms 毫秒，plays 为播放次数（0 为无限循环）
+pub fn write_apng(
+    writer: impl Write,
+    frames: impl ExactSizeIterator<Item = Frame>,
+    delay_ms: u16,
+    plays: u16,
+) -> Result<(), String> {
+    let count = frames.len() as u32;
+    let mut frames = frames.peekable();
+    let Some(first) = frames.peek() else {
+        return Err("没有可写入的帧".to_string());
+    };
+    let mut encoder = png::Encoder::new(writer, first.width, first.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(count, plays as u32)
+        .map_err(|e| e.to_string())?;
+    encoder
+        .set_frame_delay(delay_ms, 1000)
+        .map_err(|e| e.to_string())?;
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    for frame in frames {
+        writer
+            .write_image_data(&frame.pixels)
+            .map_err(|e| e.to_string())?;
+    }
+    writer.finish().map_err(|e| e.to_string())
+}
+
+impl Take {
+    /// 绘制所有帧并写入文件，返回文件名
+    fn save(&self, format: RecordFormat, delay_ms: u16, plays: u16) -> Result<String, String> {
+        let frames = self
+            .grids
+            .iter()
+            .map(|grid| render_frame(grid, self.min, self.max, self.options, &self.palette));
+        let path = format!(
+            "lifegame_{}-{}.{}",
+            self.start,
+            self.last,
+            format.extension()
+        );
+        let file = File::create(&path).map_err(|e| e.to_string())?;
+        let mut writer = BufWriter::new(file);
+        match format {
+            RecordFormat::Gif => {
+                write_gif(&mut writer, frames, &self.palette.colors(), delay_ms, plays)?
+            }
+            RecordFormat::Apng => write_apng(&mut writer, frames, delay_ms, plays)?,
+        }
+        writer.flush().map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+}
+
+/// 录制面板上的按钮
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum RecordButton {
+    Toggle,
+    Format,
+    Delay,
+    Length,
+    Loops,
+}
+
+#[derive(Component)]
+pub struct RecordButtonText(RecordButton);
+
+fn button_label(button: RecordButton, recorder: &Recorder, language: Language) -> String {
+    match button {
+        RecordButton::Toggle => match &recorder.take {
+            Some(take) => language.trf("record.stop", take.grids.len()),
+            None => language.tr("record.start").to_string(),
+        },
+        RecordButton::Format => recorder.format.name().to_string(),
+        RecordButton::Delay => language.trf("record.delay", recorder.delay_ms()),
+        RecordButton::Length => match LENGTHS[recorder.length] {
+            Some(n) => language.trf("record.length", n),
+            None => language.tr("record.length_unlimited").to_string(),
+        },
+        RecordButton::Loops => match recorder.plays() {
+            0 => language.tr("record.loop_forever").to_string(),
+            n => language.trf("record.loops", n),
+        },
+    }
+}
+
+/// 左上方的录制按钮：开始 / 停止占一整行，其余设置两个一行
+pub fn recording_panel(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Px(144.0),
+                flex_wrap: FlexWrap::Wrap,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|panel| {
+            for button in [
+                RecordButton::Toggle,
+                RecordButton::Format,
+                RecordButton::Delay,
+                RecordButton::Length,
+                RecordButton::Loops,
+            ] {
+                let width = if button == RecordButton::Toggle {
+                    140.0
+                } else {
+                    68.0
+                };
+                panel
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(width),
+                                height: Val::Px(24.0),
+                                margin: UiRect::all(Val::Px(2.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        button,
+                        Themed(ThemeColor::SessionButton),
+                    ))
+                    .with_children(|btn| {
+                        btn.spawn((
+                            TextBundle::from_section(
+                                "",
+                                TextStyle {
+                                    font: font.clone(),
+                                    font_size: 13.0,
+                                    color: Color::WHITE,
+                                },
+                            ),
+                            RecordButtonText(button),
+                            ThemedText(ThemeColor::ButtonText),
+                        ));
+                    });
+            }
+        });
+}
+
+/// 录制开关与设置，以及录制中每出现新的一代就记下一帧
+/// 区域（有选区时为选区）、每格像素、网格线与配色在开始录制时确定
+#[allow(clippy::too_many_arguments)]
+pub fn recording_system(
+    interaction_query: Query<(&Interaction, &RecordButton), Changed<Interaction>>,
+    mut actions: EventReader<Action>,
+    mut recorder: ResMut<Recorder>,
+    cell_states: Res<CellStates>,
+    sim: Res<Simulation>,
+    theme: Res<Theme>,
+    lines: Res<GridLines>,
+    selection: Res<Selection>,
+    export: Res<ExportSettings>,
+    language: Res<Language>,
+    mut texts: Query<(&RecordButtonText, &mut Text)>,
+) {
+    let mut toggle = actions
+        .read()
+        .filter(|a| **a == Action::ToggleRecord)
+        .count()
+        % 2
+        == 1;
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            RecordButton::Toggle => toggle = !toggle,
+            RecordButton::Format => {
+                recorder.format = match recorder.format {
+                    RecordFormat::Gif => RecordFormat::Apng,
+                    RecordFormat::Apng => RecordFormat::Gif,
+                }
+            }
+            RecordButton::Delay => recorder.delay = (recorder.delay + 1) % DELAYS.len(),
+            RecordButton::Length => recorder.length = (recorder.length + 1) % LENGTHS.len(),
+            RecordButton::Loops => recorder.loops = (recorder.loops + 1) % LOOPS.len(),
+        }
+    }
+
+    let length = LENGTHS[recorder.length];
+    let mut finished = false;
+    if toggle {
+        if recorder.take.is_some() {
+            finished = true;
+        } else {
+            let (min, max) = selection
+                .rect()
+                .unwrap_or((IVec2::ZERO, IVec2::splat(GRID_SIZE as i32 - 1)));
+            recorder.take = Some(Take {
+                min,
+                max,
+                options: RenderOptions {
+                    scale: export.scale(),
+                    grid_lines: lines.visible,
+                    major_lines: lines.major,
+                },
+                palette: ExportPalette::new(&theme, &sim.rule),
+                start: sim.generation,
+                last: sim.generation,
+                grids: vec![cell_states.states],
+            });
+            info!("开始录制（第 {} 代）", sim.generation);
+        }
+    } else if let Some(take) = recorder.bypass_change_detection().take.as_mut()
+        && sim.generation != take.last
+    {
+        take.grids.push(cell_states.states);
+        take.last = sim.generation;
+        // 录满设定的代数后自动停止（帧数比代数多出起始的一帧）
+        finished = length.is_some_and(|n| take.grids.len() as u64 > n);
+        recorder.set_changed();
+    }
+
+    if finished && let Some(take) = recorder.take.take() {
+        match take.save(recorder.format, recorder.delay_ms(), recorder.plays()) {
+            Ok(path) => info!("已保存 {} 帧动画到 {}", take.grids.len(), path),
+            Err(e) => error!("保存动画失败: {}", e),
+        }
+    }
+
+    if recorder.is_changed() || language.is_changed() {
+        for (text, mut value) in &mut texts {
+            value.sections[0].value = button_label(text.0, &recorder, *language);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLORS: [[u8; 4]; 3] = [[0, 0, 0, 255], [255, 255, 255, 255], [200, 40, 40, 255]];
+
+    /// 伪随机上色的一帧，颜色取自 COLORS
+    fn noise(width: u32, height: u32, seed: u32) -> Frame {
+        let mut state = seed | 1;
+        let pixels = (0..width * height)
+            .flat_map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                COLORS[(state % 3) as usize]
+            })
+            .collect();
+        Frame {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// 解码 GIF，返回每帧的 RGBA 像素与重复设置
+    fn decode(data: &[u8]) -> (Vec<Vec<u8>>, gif::Repeat) {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(data).unwrap();
+        let mut frames = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!(frame.delay, 10);
+            frames.push(frame.buffer.to_vec());
+        }
+        (frames, decoder.repeat())
+    }
+
+    #[test]
+    fn gif_round_trip() {
+        // 大帧的编码超过 4096 项，会触发码表重置
+        for (width, height) in [(3, 2), (300, 200)] {
+            let frames = [noise(width, height, 1), noise(width, height, 2)];
+            let mut data = Vec::new();
+            write_gif(
+                &mut data,
+                frames.iter().map(|f| Frame {
+                    pixels: f.pixels.clone(),
+                    ..*f
+                }),
+                &COLORS,
+                100,
+                0,
+            )
+            .unwrap();
+            let (decoded, repeat) = decode(&data);
+            assert_eq!(repeat, gif::Repeat::Infinite);
+            assert_eq!(decoded.len(), 2);
+            for (frame, pixels) in frames.iter().zip(&decoded) {
+                assert_eq!(&frame.pixels, pixels);
+            }
+        }
+    }
+
+    #[test]
+    fn gif_play_count() {
+        for (plays, repeat) in [(1, gif::Repeat::Finite(0)), (3, gif::Repeat::Finite(2))] {
+            let mut data = Vec::new();
+            write_gif(&mut data, [noise(4, 4, 3)].into_iter(), &COLORS, 100, plays).unwrap();
+            assert_eq!(decode(&data).1, repeat);
+        }
+        let mut data = Vec::new();
+        assert!(write_gif(&mut data, std::iter::empty(), &COLORS, 100, 0).is_err());
+    }
+
+    #[test]
+    fn apng_round_trip() {
+        for plays in [0, 3] {
+            let frames = [noise(5, 4, 1), noise(5, 4, 2), noise(5, 4, 3)];
+            let mut data = Vec::new();
+            let copies = frames.iter().map(|f| Frame {
+                pixels: f.pixels.clone(),
+                ..*f
+            });
+            write_apng(&mut data, copies, 250, plays).unwrap();
+
+            let decoder = png::Decoder::new(std::io::Cursor::new(data));
+            let mut reader = decoder.read_info().unwrap();
+            let control = reader.info().animation_control.unwrap();
+            assert_eq!((control.num_frames, control.num_plays), (3, plays as u32));
+            let mut buffer = vec![0; reader.output_buffer_size().unwrap()];
+            for frame in &frames {
+                reader.next_frame(&mut buffer).unwrap();
+                let delay = reader.info().frame_control.unwrap();
+                assert_eq!((delay.delay_num, delay.delay_den), (250, 1000));
+                assert_eq!(&buffer[..frame.pixels.len()], &frame.pixels[..]);
+            }
+        }
+        assert!(write_apng(Vec::new(), std::iter::empty::<Frame>(), 100, 0).is_err());
+    }
+}
@@ -0,0 +1,261 @@
+//! RLE（Run Length Encoded）图案编码，格式与 Golly / LifeWiki 通用
+//! 这里只处理数据体：`b` 死细胞，`o` 活细胞，`$` 换行，`!` 结束
+//! 多状态规则使用 `.` 表示状态 0，`A`..`X` 表示状态 1..24，
+//! 更大的状态在字母前加 `p`..`y` 前缀（`pA` 为 25，`qA` 为 49，依此类推）
+
+use crate::GRID_SIZE;
+
+/// 把 width × height 区域编码为 RLE 数据体（行尾的死细胞与末尾空行省略）
+/// multistate 为 true 时使用多状态记法
+pub fn encode(
+    width: usize,
+    height: usize,
+    multistate: bool,
+    state: impl Fn(usize, usize) -> u8,
+) -> String {
+    let mut out = String::new();
+    let mut pending_rows = 0;
+
+    for y in 0..height {
+        // 本行最后一个非零状态之后的部分不需要输出
+        let Some(last) = (0..width).rev().find(|&x| state(x, y) != 0) else {
+            pending_rows += 1;
+            continue;
+        };
+
+        // 第一个非空行之前只有空行，之后还要加上一行的换行
+        let breaks = if out.is_empty() {
+            pending_rows
+        } else {
+            pending_rows + 1
+        };
+        if breaks > 0 {
+            push_run(&mut out, breaks, "$");
+        }
+        pending_rows = 0;
+
+        let mut x = 0;
+        while x <= last {
+            let current = state(x, y);
+            let start = x;
+            while x <= last && state(x, y) == current {
+                x += 1;
+            }
+            push_run(&mut out, x - start, &state_tag(current, multistate));
+        }
+    }
+
+    out.push('!');
+    out
+}
+
+fn state_tag(state: u8, multistate: bool) -> String {
+    match (state, multistate) {
+        (0, false) => "b".to_string(),
+        (_, false) => "o".to_string(),
+        (0, true) => ".".to_string(),
+        (s, true) => {
+            let index = s as u32 - 1;
+            let letter = char::from_u32('A' as u32 + index % 24).unwrap();
+            match index / 24 {
+                0 => letter.to_string(),
+                prefix => format!(
+                    "{}{}",
+                    char::from_u32('p' as u32 + prefix - 1).unwrap(),
+                    letter
+                ),
+            }
+        }
+    }
+}
+
+fn push_run(out: &mut String, count: usize, tag: &str) {
+    if count > 1 {
+        out.push_str(&count.to_string());
+    }
+    out.push_str(tag);
+}
+
+/// 解析 RLE 数据体，返回非零状态的格子 (x, y, 状态)；两状态的 `o` 记为状态 1
+/// 格子超出 GRID_SIZE × GRID_SIZE 时报错，避免畸形数据占满内存
+pub fn decode(body: &str) -> Result<Vec<(usize, usize, u8)>, String> {
+    let mut cells = Vec::new();
+    let (mut x, mut y) = (0usize, 0usize);
+    let mut count = 0usize;
+    // 多状态前缀 p..y
+    let mut prefix: Option<u32> = None;
+
+    for c in body.chars() {
+        if prefix.is_some() && !c.is_ascii_uppercase() {
+            return Err(format!("RLE 中状态前缀后缺少字母: '{}'", c));
+        }
+        match c {
+            '0'..='9' => {
+                count = count
+                    .checked_mul(10)
+                    .and_then(|n| n.checked_add(c.to_digit(10).unwrap() as usize))
+                    .ok_or_else(|| "RLE 中的游程长度过大".to_string())?;
+                continue;
+            }
+            'p'..='y' => {
+                prefix = Some(c as u32 - 'p' as u32 + 1);
+                continue;
+            }
+            'b' | '.' => x = x.saturating_add(count.max(1)),
+            'o' | 'A'..='X' => {
+                let state = match c {
+                    'o' => 1,
+                    c => prefix.take().unwrap_or(0) * 24 + (c as u32 - 'A' as u32) + 1,
+                };
+                let state =
+                    u8::try_from(state).map_err(|_| format!("RLE 中的状态 {} 过大", state))?;
+                if y >= GRID_SIZE || x.saturating_add(count.max(1)) > GRID_SIZE {
+                    return Err(format!("RLE 图案超出 {0}×{0} 的网格", GRID_SIZE));
+                }
+                for _ in 0..count.max(1) {
+                    cells.push((x, y, state));
+                    x += 1;
+                }
+            }
+            '$' => {
+                y = y.saturating_add(count.max(1));
+                x = 0;
+            }
+            '!' => return Ok(cells),
+            c if c.is_whitespace() => {}
+            c => return Err(format!("RLE 中出现无法识别的字符 '{}'", c)),
+        }
+        count = 0;
+    }
+
+    // 缺少结束符时也接受已读到的内容
+    Ok(cells)
+}
+
+/// 带头部的完整 RLE 文本（`x = .., y = .., rule = ..`），数据体按 70 列折行
+pub fn write_pattern(
+    width: usize,
+    height: usize,
+    rule: &str,
+    multistate: bool,
+    state: impl Fn(usize, usize) -> u8,
+) -> String {
+    let mut out = format!("x = {}, y = {}, rule = {}\n", width, height, rule);
+    let body = encode(width, height, multistate, state);
+    let mut line_len = 0;
+    // 在游程边界处折行，避免把数字、状态前缀与其后的标记拆开
+    for token in body.split_inclusive(|c: char| !c.is_ascii_digit() && !('p'..='y').contains(&c)) {
+        if line_len + token.len() > 70 {
+            out.push('\n');
+            line_len = 0;
+        }
+        out.push_str(token);
+        line_len += token.len();
+    }
+    out.push('\n');
+    out
+}
+
+/// 从完整 RLE 文本解析出的图案
+pub struct Pattern {
+    pub width: usize,
+    pub height: usize,
+    // 非零状态的格子 (x, y, 状态)
+    pub cells: Vec<(usize, usize, u8)>,
+}
+
+/// 解析完整 RLE 文本，跳过 `#` 注释行
+/// 没有头部时宽高取活细胞的外接范围
+pub fn parse_pattern(text: &str) -> Result<Pattern, String> {
+    let mut size = None;
+    let mut body = String::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('x') && size.is_none() && body.is_empty() {
+            let mut width = None;
+            let mut height = None;
+            for field in line.split(',') {
+                let Some((key, value)) = field.split_once('=') else {
+                    continue;
+                };
+                match key.trim() {
+                    "x" => width = value.trim().parse().ok(),
+                    "y" => height = value.trim().parse().ok(),
+                    _ => {}
+                }
+            }
+            match (width, height) {
+                (Some(w), Some(h)) => size = Some((w, h)),
+                _ => return Err(format!("无法解析 RLE 头部: {}", line)),
+            }
+            continue;
+        }
+        body.push_str(line);
+    }
+
+    if body.is_empty() {
+        return Err("RLE 内容为空".to_string());
+    }
+    let cells = decode(&body)?;
+    let (width, height) = size.unwrap_or_else(|| {
+        let w = cells.iter().map(|&(x, _, _)| x + 1).max().unwrap_or(0);
+        let h = cells.iter().map(|&(_, y, _)| y + 1).max().unwrap_or(0);
+        (w, h)
+    });
+    Ok(Pattern {
+        width,
+        height,
+        cells,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_two_state() {
+        assert_eq!(
+            decode("bo$2bo$3o!").unwrap(),
+            vec![(1, 0, 1), (2, 1, 1), (0, 2, 1), (1, 2, 1), (2, 2, 1)]
+        );
+    }
+
+    #[test]
+    fn encode_leading_and_inner_blank_rows() {
+        let alive = [(1, 2), (0, 5)];
+        let body = encode(3, 6, false, |x, y| alive.contains(&(x, y)) as u8);
+        assert_eq!(body, "2$bo3$o!");
+        assert_eq!(decode(&body).unwrap(), vec![(1, 2, 1), (0, 5, 1)]);
+    }
+
+    #[test]
+    fn multistate_round_trip() {
+        let states = [0u8, 1, 2, 24, 25, 48, 49, 255];
+        let body = encode(states.len(), 1, true, |x, _| states[x]);
+        assert_eq!(body, ".ABXpApXqAyO!");
+        let cells: Vec<_> = (1..states.len()).map(|x| (x, 0, states[x])).collect();
+        assert_eq!(decode(&body).unwrap(), cells);
+        // 游程与换行
+        assert_eq!(
+            decode("2.3B$pA!").unwrap(),
+            vec![(2, 0, 2), (3, 0, 2), (4, 0, 2), (0, 1, 25)]
+        );
+        assert!(decode("pb!").is_err());
+    }
+
+    #[test]
+    fn decode_rejects_oversized_runs() {
+        // 游程长度溢出
+        assert!(decode("99999999999999999999999o!").is_err());
+        // 不溢出但远超网格，不能真的生成这么多格子
+        assert!(decode("999999999999o!").is_err());
+        assert!(decode("999999999999$o!").is_err());
+        assert!(decode(&format!("{}bo!", GRID_SIZE)).is_err());
+        assert!(decode(&format!("{}bo!", GRID_SIZE - 1)).is_ok());
+    }
+}
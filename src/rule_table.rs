@@ -0,0 +1,659 @@
+use crate::evolution::MaskSet;
+use crate::{DEAD, GRID_SIZE, Grid};
+use std::collections::HashMap;
+
+/// Golly 规则文件所在目录，相对当前工作目录
+pub const RULES_DIR: &str = "assets/rules";
+
+/// 规则表使用的邻域；邻居按 Golly 的顺序，从正上方开始顺时针排列
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableNeighborhood {
+    Moore,
+    VonNeumann,
+    // Golly 的六边形邻域画在方格上：去掉东北与西南两个角
+    Hexagonal,
+}
+
+impl TableNeighborhood {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Moore" => Some(TableNeighborhood::Moore),
+            "vonNeumann" => Some(TableNeighborhood::VonNeumann),
+            "hexagonal" => Some(TableNeighborhood::Hexagonal),
+            _ => None,
+        }
+    }
+
+    fn offsets(self) -> &'static [(isize, isize)] {
+        match self {
+            TableNeighborhood::Moore => &[
+                (0, -1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+                (0, 1),
+                (-1, 1),
+                (-1, 0),
+                (-1, -1),
+            ],
+            TableNeighborhood::VonNeumann => &[(0, -1), (1, 0), (0, 1), (-1, 0)],
+            TableNeighborhood::Hexagonal => &[(0, -1), (1, 0), (1, 1), (0, 1), (-1, 0), (-1, -1)],
+        }
+    }
+}
+
+/// 一条转移：inputs[0] 为中心，其后依次为各邻居允许的状态
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Transition {
+    inputs: Vec<MaskSet>,
+    output: u8,
+}
+
+/// 转移的对称性
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Symmetry {
+    // 邻居的若干种重排：第 i 个输入与格子的第 perm[i] 个邻居比较
+    Permutations(Vec<Vec<usize>>),
+    // 任意重排，只看各状态的邻居个数
+    Permute,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Lookup {
+    // @TABLE：按顺序取第一条匹配的转移，都不匹配时状态不变
+    Table {
+        transitions: Vec<Transition>,
+        symmetry: Symmetry,
+    },
+    // @TREE：决策树，nodes[i] 为各状态对应的子节点（第 1 层为下一状态），根为最后一个节点
+    Tree {
+        nodes: Vec<Vec<u32>>,
+        // 自根向下依次读取的格子，下标含义同 RuleTable::apply 的 cells
+        order: &'static [usize],
+    },
+}
+
+/// 从 Golly .rule 文件读取的规则
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleTable {
+    pub name: String,
+    pub description: String,
+    pub states: u8,
+    neighborhood: TableNeighborhood,
+    lookup: Lookup,
+    // @COLORS 中给出的各状态颜色 (状态, [r, g, b])
+    colors: Vec<(u8, [u8; 3])>,
+}
+
+impl RuleTable {
+    /// 解析 .rule 文件：@RULE 名称与说明、@TABLE 或 @TREE，以及可选的 @COLORS
+    /// 其余小节（@ICONS 等）忽略
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut sections: Vec<(&str, &str, Vec<&str>)> = Vec::new();
+        for line in text.lines() {
+            let trimmed = line.trim();
+            if let Some(header) = trimmed.strip_prefix('@') {
+                let (kind, rest) = header
+                    .split_once(char::is_whitespace)
+                    .unwrap_or((header, ""));
+                sections.push((kind, rest.trim(), Vec::new()));
+            } else if let Some((_, _, lines)) = sections.last_mut() {
+                lines.push(line);
+            }
+        }
+        let find = |kind: &str| sections.iter().find(|(k, _, _)| *k == kind);
+
+        let (_, name, description) = find("RULE").ok_or("缺少 @RULE")?;
+        if name.is_empty() {
+            return Err("@RULE 缺少规则名".to_string());
+        }
+        let description = description
+            .iter()
+            .map(|l| l.trim())
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string();
+
+        let (states, neighborhood, lookup) = match (find("TABLE"), find("TREE")) {
+            (Some((_, _, lines)), _) => parse_table(lines)?,
+            (None, Some((_, _, lines))) => parse_tree(lines)?,
+            (None, None) => return Err("缺少 @TABLE 或 @TREE".to_string()),
+        };
+
+        let mut colors = Vec::new();
+        if let Some((_, _, lines)) = find("COLORS") {
+            for line in lines
+                .iter()
+                .map(|l| strip_comment(l))
+                .filter(|l| !l.is_empty())
+            {
+                let values: Vec<u8> = line
+                    .split_whitespace()
+                    .map(|v| v.parse().map_err(|_| format!("@COLORS 无法解析: {}", line)))
+                    .collect::<Result<_, _>>()?;
+                // 只支持 "状态 r g b" 形式
+                if let [state, r, g, b] = values[..] {
+                    colors.push((state, [r, g, b]));
+                }
+            }
+        }
+
+        Ok(RuleTable {
+            name: name.to_string(),
+            description,
+            states,
+            neighborhood,
+            lookup,
+            colors,
+        })
+    }
+
+    /// @COLORS 中给出的颜色
+    pub fn color(&self, state: u8) -> Option<[u8; 3]> {
+        self.colors
+            .iter()
+            .find(|(s, _)| *s == state)
+            .map(|(_, color)| *color)
+    }
+
+    /// cells[0] 为中心，其后为按 Golly 顺序排列的邻居
+    fn apply(&self, cells: &[u8]) -> u8 {
+        match &self.lookup {
+            Lookup::Table {
+                transitions,
+                symmetry,
+            } => transitions
+                .iter()
+                .find(|t| t.matches(cells, symmetry))
+                .map_or(cells[0], |t| t.output),
+            Lookup::Tree { nodes, order } => {
+                let mut node = nodes.len() - 1;
+                for &i in order.iter() {
+                    node = nodes[node].get(cells[i] as usize).copied().unwrap_or(0) as usize;
+                }
+                node as u8
+            }
+        }
+    }
+
+    /// 表驱动的演化：同一邻域构型的结果只计算一次
+    pub fn next_generation(&self, current: &Grid, wrap: bool) -> Grid {
+        let size = GRID_SIZE as isize;
+        let offsets = self.neighborhood.offsets();
+        let mut cache: HashMap<Vec<u8>, u8> = HashMap::new();
+        let mut cells = Vec::with_capacity(offsets.len() + 1);
+        let mut next = [[DEAD; GRID_SIZE]; GRID_SIZE];
+        for (y, row) in next.iter_mut().enumerate() {
+            for (x, state) in row.iter_mut().enumerate() {
+                cells.clear();
+                cells.push(current[y][x]);
+                for &(dx, dy) in offsets {
+                    let (mut nx, mut ny) = (x as isize + dx, y as isize + dy);
+                    if wrap {
+                        nx = nx.rem_euclid(size);
+                        ny = ny.rem_euclid(size);
+                    } else if nx < 0 || nx >= size || ny < 0 || ny >= size {
+                        cells.push(DEAD);
+                        continue;
+                    }
+                    cells.push(current[ny as usize][nx as usize]);
+                }
+                *state = match cache.get(&cells) {
+                    Some(s) => *s,
+                    None => {
+                        let s = self.apply(&cells);
+                        cache.insert(cells.clone(), s);
+                        s
+                    }
+                };
+            }
+        }
+        next
+    }
+}
+
+impl Transition {
+    fn matches(&self, cells: &[u8], symmetry: &Symmetry) -> bool {
+        if !self.inputs[0].contains(cells[0] as usize) {
+            return false;
+        }
+        let (inputs, neighbors) = (&self.inputs[1..], &cells[1..]);
+        match symmetry {
+            Symmetry::Permutations(perms) => perms.iter().any(|perm| {
+                perm.iter()
+                    .zip(inputs)
+                    .all(|(&j, input)| input.contains(neighbors[j] as usize))
+            }),
+            Symmetry::Permute => permute_matches(inputs, neighbors, 0),
+        }
+    }
+}
+
+/// 是否存在一种一一对应，使每个输入都匹配一个未用过的邻居（used 为已用邻居的位掩码）
+fn permute_matches(inputs: &[MaskSet], neighbors: &[u8], used: u32) -> bool {
+    let Some((first, rest)) = inputs.split_first() else {
+        return true;
+    };
+    (0..neighbors.len()).any(|j| {
+        used & 1 << j == 0
+            && first.contains(neighbors[j] as usize)
+            && permute_matches(rest, neighbors, used | 1 << j)
+    })
+}
+
+fn strip_comment(line: &str) -> &str {
+    line.split('#').next().unwrap_or("").trim()
+}
+
+/// "rotate4"、"rotate8reflect"、"reflect_horizontal" 等对称性；n 为邻居个数
+fn parse_symmetry(name: &str, n: usize) -> Option<Symmetry> {
+    let (rotations, reflect) = match name {
+        "none" => (1, false),
+        "permute" => return Some(Symmetry::Permute),
+        "reflect_horizontal" => (1, true),
+        _ => {
+            let rest = name.strip_prefix("rotate")?;
+            let (k, reflect) = match rest.strip_suffix("reflect") {
+                Some(k) => (k, true),
+                None => (rest, false),
+            };
+            (k.parse::<usize>().ok()?, reflect)
+        }
+    };
+    if rotations == 0 || !n.is_multiple_of(rotations) {
+        return None;
+    }
+    let step = n / rotations;
+    let mut perms = Vec::new();
+    for r in 0..rotations {
+        for mirrored in [false, true] {
+            if mirrored && !reflect {
+                continue;
+            }
+            // 顺时针排列的邻居：旋转为平移下标，翻转为以正上方为轴反向
+            perms.push(
+                (0..n)
+                    .map(|i| {
+                        let i = if mirrored { n - i } else { i };
+                        (i + r * step) % n
+                    })
+                    .collect(),
+            );
+        }
+    }
+    Some(Symmetry::Permutations(perms))
+}
+
+/// 转移中的一项：允许的状态集合，以及变量名（直接写出的状态或集合没有名字）
+type Term = (MaskSet, Option<String>);
+
+/// 按逗号分隔，花括号内的逗号不分隔；不含逗号时每个字符为一项（状态数不超过 10 时的紧凑写法）
+fn split_terms(line: &str) -> Vec<String> {
+    let line: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    if !line.contains(',') {
+        return line.chars().map(String::from).collect();
+    }
+    let mut terms = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+    for c in line.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                terms.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    terms.push(current);
+    terms
+}
+
+/// 状态、变量名或花括号集合
+fn parse_term(term: &str, states: usize, vars: &HashMap<String, MaskSet>) -> Result<Term, String> {
+    if let Some(inner) = term.strip_prefix('{').and_then(|t| t.strip_suffix('}')) {
+        let mut set = MaskSet::default();
+        for item in inner.split(',').filter(|i| !i.is_empty()) {
+            let (values, _) = parse_term(item, states, vars)?;
+            (0..states)
+                .filter(|s| values.contains(*s))
+                .for_each(|s| set.insert(s));
+        }
+        return Ok((set, None));
+    }
+    if let Ok(state) = term.parse::<usize>() {
+        if state >= states {
+            return Err(format!("状态 {} 超出状态数 {}", state, states));
+        }
+        let mut set = MaskSet::default();
+        set.insert(state);
+        return Ok((set, None));
+    }
+    match vars.get(term) {
+        Some(set) => Ok((*set, Some(term.to_string()))),
+        None => Err(format!("未定义的变量 {}", term)),
+    }
+}
+
+fn parse_table(lines: &[&str]) -> Result<(u8, TableNeighborhood, Lookup), String> {
+    let mut states = None;
+    let mut neighborhood = None;
+    let mut symmetry_name = "none".to_string();
+    let mut vars: HashMap<String, MaskSet> = HashMap::new();
+    let mut transitions = Vec::new();
+
+    for line in lines
+        .iter()
+        .map(|l| strip_comment(l))
+        .filter(|l| !l.is_empty())
+    {
+        if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim();
+            match key.trim() {
+                "n_states" => {
+                    let n = value.parse::<u8>().ok().filter(|n| *n >= 2);
+                    states = Some(n.ok_or_else(|| format!("状态数无效: {}", value))?);
+                }
+                "neighborhood" => {
+                    neighborhood = Some(
+                        TableNeighborhood::from_name(value)
+                            .ok_or_else(|| format!("不支持的邻域: {}", value))?,
+                    );
+                }
+                "symmetries" => symmetry_name = value.to_string(),
+                _ => return Err(format!("无法解析: {}", line)),
+            }
+            continue;
+        }
+        let n_states = states.ok_or("n_states 必须写在转移之前")? as usize;
+
+        if let Some(rest) = line.strip_prefix("var") {
+            let (name, set) = rest
+                .split_once('=')
+                .ok_or_else(|| format!("无法解析: {}", line))?;
+            let set: String = set.chars().filter(|c| !c.is_whitespace()).collect();
+            let (values, _) = parse_term(&set, n_states, &vars)?;
+            vars.insert(name.trim().to_string(), values);
+            continue;
+        }
+
+        let n = neighborhood
+            .ok_or("neighborhood 必须写在转移之前")?
+            .offsets()
+            .len();
+        let terms = split_terms(line)
+            .iter()
+            .map(|t| parse_term(t, n_states, &vars))
+            .collect::<Result<Vec<Term>, String>>()?;
+        if terms.len() != n + 2 {
+            return Err(format!("转移应有 {} 项: {}", n + 2, line));
+        }
+        expand_transition(&terms, n_states, &mut transitions)
+            .ok_or_else(|| format!("输出必须是状态或输入中出现过的变量: {}", line))?;
+    }
+
+    let states = states.ok_or("缺少 n_states")?;
+    let neighborhood = neighborhood.ok_or("缺少 neighborhood")?;
+    let symmetry = parse_symmetry(&symmetry_name, neighborhood.offsets().len())
+        .ok_or_else(|| format!("不支持的对称性: {}", symmetry_name))?;
+    let lookup = Lookup::Table {
+        transitions,
+        symmetry,
+    };
+    Ok((states, neighborhood, lookup))
+}
+
+/// 同一转移中多次出现的变量必须取相同的值：逐个展开这些变量，得到只含单次变量的转移
+fn expand_transition(terms: &[Term], states: usize, out: &mut Vec<Transition>) -> Option<()> {
+    let (inputs, output) = terms.split_at(terms.len() - 1);
+    let (output_set, _) = &output[0];
+    let repeated = terms.iter().enumerate().find_map(|(i, (_, name))| {
+        let name = name.as_ref()?;
+        let count = terms
+            .iter()
+            .filter(|(_, n)| n.as_ref() == Some(name))
+            .count();
+        (count > 1).then_some((i, name.clone()))
+    });
+
+    let Some((index, name)) = repeated else {
+        // 输出只能是单个状态
+        let mut outputs = (0..states).filter(|s| output_set.contains(*s));
+        let (Some(output), None) = (outputs.next(), outputs.next()) else {
+            return None;
+        };
+        out.push(Transition {
+            inputs: inputs.iter().map(|(set, _)| *set).collect(),
+            output: output as u8,
+        });
+        return Some(());
+    };
+
+    let values = terms[index].0;
+    for value in (0..states).filter(|s| values.contains(*s)) {
+        let mut single = MaskSet::default();
+        single.insert(value);
+        let bound: Vec<Term> = terms
+            .iter()
+            .map(|(set, n)| {
+                if n.as_ref() == Some(&name) {
+                    (single, None)
+                } else {
+                    (*set, n.clone())
+                }
+            })
+            .collect();
+        expand_transition(&bound, states, out)?;
+    }
+    Some(())
+}
+
+fn parse_tree(lines: &[&str]) -> Result<(u8, TableNeighborhood, Lookup), String> {
+    let (mut states, mut neighbors, mut count) = (None, None, None);
+    let mut nodes: Vec<Vec<u32>> = Vec::new();
+    let mut levels: Vec<u32> = Vec::new();
+
+    for line in lines
+        .iter()
+        .map(|l| strip_comment(l))
+        .filter(|l| !l.is_empty())
+    {
+        if let Some((key, value)) = line.split_once('=') {
+            let value: usize = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("无法解析: {}", line))?;
+            match key.trim() {
+                "num_states" => states = Some(value),
+                "num_neighbors" => neighbors = Some(value),
+                "num_nodes" => count = Some(value),
+                _ => return Err(format!("无法解析: {}", line)),
+            }
+            continue;
+        }
+        let n_states = states.ok_or("num_states 必须写在节点之前")?;
+        let values: Vec<u32> = line
+            .split_whitespace()
+            .map(|v| v.parse().map_err(|_| format!("无法解析节点: {}", line)))
+            .collect::<Result<_, _>>()?;
+        let (&level, children) = values
+            .split_first()
+            .ok_or_else(|| format!("无法解析节点: {}", line))?;
+        if children.len() != n_states {
+            return Err(format!("节点应有 {} 个子节点: {}", n_states, line));
+        }
+        // 第 1 层的子节点是状态，更高层是前面某个下一层节点的编号
+        let valid = level > 0
+            && children.iter().all(|&c| {
+                if level == 1 {
+                    (c as usize) < n_states
+                } else {
+                    (c as usize) < nodes.len() && levels[c as usize] == level - 1
+                }
+            });
+        if !valid {
+            return Err(format!("节点无效: {}", line));
+        }
+        nodes.push(children.to_vec());
+        levels.push(level);
+    }
+
+    let states = states
+        .and_then(|s| u8::try_from(s).ok())
+        .filter(|s| *s >= 2)
+        .ok_or("num_states 无效")?;
+    // 自根向下读取的顺序：摩尔邻域为 西北、东北、西南、东南、北、西、东、南、中心，
+    // 冯·诺依曼邻域为 北、西、东、南、中心（下标见 TableNeighborhood::offsets）
+    let (neighborhood, order): (_, &'static [usize]) = match neighbors {
+        Some(8) => (TableNeighborhood::Moore, &[8, 2, 6, 4, 1, 7, 3, 5, 0]),
+        Some(4) => (TableNeighborhood::VonNeumann, &[1, 4, 2, 3, 0]),
+        _ => return Err("num_neighbors 只能为 4 或 8".to_string()),
+    };
+    if count != Some(nodes.len()) || levels.last() != Some(&(order.len() as u32)) {
+        return Err("节点数或根节点层数不匹配".to_string());
+    }
+    Ok((states, neighborhood, Lookup::Tree { nodes, order }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 冯·诺依曼邻域的三状态规则表，变量与转移写在 body 中
+    fn von_neumann(symmetries: &str, body: &str) -> Result<RuleTable, String> {
+        RuleTable::parse(&format!(
+            "@RULE Test\n@TABLE\nn_states:3\nneighborhood:vonNeumann\nsymmetries:{}\n{}",
+            symmetries, body
+        ))
+    }
+
+    #[test]
+    fn wireworld_diode() {
+        let rule = RuleTable::parse(include_str!("../assets/rules/WireWorld.rule")).unwrap();
+        assert_eq!(rule.name, "WireWorld");
+        assert_eq!(rule.states, 4);
+        assert!(rule.description.starts_with("Brian Silverman"));
+        assert_eq!(rule.color(1), Some([0, 128, 255]));
+        assert_eq!(rule.color(4), None);
+
+        // 导线中间断开一格，上下各有两格导体：电子只能从左向右通过
+        let diode = ["   ##     ", "#### #####", "   ##     "];
+        let arrives = |from_left: bool| {
+            let mut grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+            for (y, row) in diode.iter().enumerate() {
+                for (x, c) in row.chars().enumerate() {
+                    if c == '#' {
+                        grid[10 + y][10 + x] = 3;
+                    }
+                }
+            }
+            let (tail, head, target) = if from_left {
+                (10, 11, 19)
+            } else {
+                (19, 18, 10)
+            };
+            grid[11][tail] = 2;
+            grid[11][head] = 1;
+            (1..=40).find(|_| {
+                grid = rule.next_generation(&grid, false);
+                grid[11][target] == 1
+            })
+        };
+        assert_eq!(arrives(true), Some(8));
+        assert_eq!(arrives(false), None);
+    }
+
+    #[test]
+    fn variables_bind_within_a_transition() {
+        // 北、南两侧相同时变为该状态
+        let rule = von_neumann("none", "var a={1,2}\n0,a,0,a,0,a").unwrap();
+        assert_eq!(rule.apply(&[0, 1, 0, 1, 0]), 1);
+        assert_eq!(rule.apply(&[0, 2, 0, 2, 0]), 2);
+        assert_eq!(rule.apply(&[0, 1, 0, 2, 0]), 0);
+        // 只出现在输出中的变量无法确定结果
+        assert!(von_neumann("none", "var a={1,2}\n0,1,0,0,0,a").is_err());
+        assert!(von_neumann("none", "0,b,0,0,0,1").is_err());
+        assert!(von_neumann("none", "0,3,0,0,0,1").is_err());
+        // 花括号集合与不带逗号的紧凑写法
+        let rule = von_neumann("none", "0,{1,2},0,0,0,1").unwrap();
+        assert_eq!(rule.apply(&[0, 2, 0, 0, 0]), 1);
+        let rule = von_neumann("none", "010001").unwrap();
+        assert_eq!(rule.apply(&[0, 1, 0, 0, 0]), 1);
+    }
+
+    #[test]
+    fn symmetries_expand_transitions() {
+        // 只写了北侧，旋转后东、南、西同样成立
+        let none = von_neumann("none", "0,1,0,0,0,1").unwrap();
+        let rotate = von_neumann("rotate4", "0,1,0,0,0,1").unwrap();
+        assert_eq!(none.apply(&[0, 0, 0, 0, 1]), 0);
+        for i in 1..=4 {
+            let mut cells = [0; 5];
+            cells[i] = 1;
+            assert_eq!(rotate.apply(&cells), 1);
+        }
+        // 北、东两侧的构型翻转后为北、西
+        let reflect = von_neumann("reflect_horizontal", "0,1,1,0,0,2").unwrap();
+        assert_eq!(reflect.apply(&[0, 1, 1, 0, 0]), 2);
+        assert_eq!(reflect.apply(&[0, 1, 0, 0, 1]), 2);
+        assert_eq!(reflect.apply(&[0, 0, 1, 1, 0]), 0);
+        let permute = von_neumann("permute", "0,1,2,0,0,1").unwrap();
+        assert_eq!(permute.apply(&[0, 0, 2, 0, 1]), 1);
+        assert_eq!(permute.apply(&[0, 0, 2, 0, 2]), 0);
+
+        assert!(matches!(
+            parse_symmetry("rotate8reflect", 8),
+            Some(Symmetry::Permutations(p)) if p.len() == 16
+        ));
+        assert!(parse_symmetry("rotate3", 8).is_none());
+        assert!(parse_symmetry("rotate4", 6).is_none());
+        assert!(parse_symmetry("mirror", 8).is_none());
+    }
+
+    /// 冯·诺依曼邻域的决策树：北侧为 1 时变为 1，否则为 0
+    const NORTH_TREE: &str = "@RULE North\n@TREE\nnum_states=2\nnum_neighbors=4\nnum_nodes=9\n\
+        1 0 0\n1 1 1\n2 0 0\n2 1 1\n3 2 2\n3 3 3\n4 4 4\n4 5 5\n5 6 7\n";
+
+    #[test]
+    fn tree_reads_cells_in_golly_order() {
+        let rule = RuleTable::parse(NORTH_TREE).unwrap();
+        assert_eq!(rule.states, 2);
+        assert_eq!(rule.apply(&[0, 1, 0, 0, 0]), 1);
+        assert_eq!(rule.apply(&[1, 0, 1, 1, 1]), 0);
+        let mut grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+        grid[5][5] = 1;
+        let next = rule.next_generation(&grid, false);
+        assert_eq!(next[6][5], 1);
+        assert_eq!(next[5][5], 0);
+    }
+
+    #[test]
+    fn tree_validates_levels_and_order() {
+        // 节点数与声明不符
+        let text = NORTH_TREE.replace("num_nodes=9", "num_nodes=8");
+        assert!(RuleTable::parse(&text).is_err());
+        // 子节点不在下一层
+        let text = NORTH_TREE.replace("5 6 7", "5 5 7");
+        assert!(RuleTable::parse(&text).is_err());
+        // 根节点的层数与邻居数不符
+        let text = NORTH_TREE.replace("num_neighbors=4", "num_neighbors=8");
+        assert!(RuleTable::parse(&text).is_err());
+        // 第 1 层的子节点必须是状态
+        let text = NORTH_TREE.replace("1 1 1\n", "1 1 2\n");
+        assert!(RuleTable::parse(&text).is_err());
+    }
+
+    #[test]
+    fn colors_and_missing_sections() {
+        let text = format!("{}@COLORS\n0 0 0 0\n1 255 255 0 # yellow\n", NORTH_TREE);
+        let rule = RuleTable::parse(&text).unwrap();
+        assert_eq!(rule.color(1), Some([255, 255, 0]));
+        assert!(RuleTable::parse(&format!("{}@COLORS\n1 300 0 0\n", NORTH_TREE)).is_err());
+        assert!(RuleTable::parse("@RULE Empty\n").is_err());
+        assert!(RuleTable::parse("@TABLE\nn_states:2\n").is_err());
+    }
+}
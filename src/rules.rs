@@ -0,0 +1,230 @@
+use crate::evolution::Rule;
+use crate::i18n::Language;
+use crate::keybindings::Action;
+use crate::rule_table::{RULES_DIR, RuleTable};
+use crate::theme::{ThemeColor, Themed, ThemedText};
+use crate::{CellStates, RulesLabel, Simulation, clamp_states};
+use bevy::prelude::*;
+use std::fs;
+use std::sync::Arc;
+
+/// 可切换的预置规则：(i18n 字符串表中的 key, 规则字符串)
+const PRESETS: &[(&str, &str)] = &[
+    ("rule.conway", "B3/S23"),
+    ("rule.highlife", "B36/S23"),
+    ("rule.brians_brain", "B2/S/C3"),
+    ("rule.star_wars", "B2/S345/C4"),
+    ("rule.just_friends", "B2-a/S12"),
+    ("rule.bosco", "R5,C0,M1,S34..58,B34..45,NM"),
+    ("rule.majority", "R4,C0,M1,S41..81,B41..81,NM"),
+];
+
+/// 可切换的规则列表：预置规则加上 assets/rules 中的 Golly 规则文件
+/// 名称为 i18n 字符串表中的 key，规则文件则直接使用 @RULE 中的名称
+#[derive(Resource)]
+pub struct RuleLibrary {
+    rules: Vec<(String, Rule)>,
+    current: usize,
+}
+
+impl Default for RuleLibrary {
+    fn default() -> Self {
+        let rules = PRESETS
+            .iter()
+            .map(|(name, rule)| (name.to_string(), Rule::parse(rule).expect("预置规则无效")))
+            .collect();
+        Self { rules, current: 0 }
+    }
+}
+
+/// 启动时读取 assets/rules 下的所有 .rule 文件
+pub fn load_rule_files(mut library: ResMut<RuleLibrary>) {
+    library.load_files();
+}
+
+impl RuleLibrary {
+    /// 读取 assets/rules 下的所有 .rule 文件（按文件名排序）
+    pub fn load_files(&mut self) {
+        let Ok(entries) = fs::read_dir(RULES_DIR) else {
+            return;
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "rule"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            let table = fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| RuleTable::parse(&text));
+            match table {
+                Ok(table) => {
+                    info!(
+                        "从 {} 读取了规则 {}（{} 个状态）",
+                        path.display(),
+                        table.name,
+                        table.states
+                    );
+                    self.rules
+                        .push((table.name.clone(), Rule::Table(Arc::new(table))));
+                }
+                Err(e) => warn!("{} 无效: {}", path.display(), e),
+            }
+        }
+    }
+
+    /// 解析规则字符串；规则表以名称表示，在已加载的规则文件中查找
+    pub fn parse(&self, text: &str) -> Option<Rule> {
+        Rule::parse(text).or_else(|| {
+            self.rules
+                .iter()
+                .find(|(_, rule)| matches!(rule, Rule::Table(table) if table.name == text.trim()))
+                .map(|(_, rule)| rule.clone())
+        })
+    }
+
+    /// 按钮上显示的名称：预置规则显示名字，其余（如会话中读入的规则）显示规则字符串
+    pub fn label(&self, rule: &Rule, language: Language) -> String {
+        let name = match self.rules.iter().find(|(_, r)| r == rule) {
+            Some((name, _)) => language.tr(name).to_string(),
+            None => rule.to_string(),
+        };
+        language.trf("label.rule", name)
+    }
+
+    /// 列表中 rule 之后的一条规则（不在列表中时为第一条）
+    pub fn next(&self, rule: &Rule) -> Rule {
+        let index = self
+            .rules
+            .iter()
+            .position(|(_, r)| r == rule)
+            .map_or(0, |i| (i + 1) % self.rules.len());
+        self.rules[index].1.clone()
+    }
+}
+
+/// 规则切换按钮
+#[derive(Component)]
+pub struct RuleButton;
+
+#[derive(Component)]
+pub struct RuleButtonText;
+
+pub fn rule_ui(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(140.0),
+                    height: Val::Px(40.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            RuleButton,
+            Themed(ThemeColor::ModeButton),
+        ))
+        .with_children(|r| {
+            r.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 18.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                RuleButtonText,
+                ThemedText(ThemeColor::ButtonText),
+            ));
+        });
+}
+
+/// 点击按钮或按快捷键循环切换预置规则
+/// 左上方的规则说明：康威规则显示文字说明，规则文件显示名称与说明，其余显示名称与规则字符串
+#[allow(clippy::too_many_arguments)]
+pub fn rule_switch_system(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<RuleButton>)>,
+    mut actions: EventReader<Action>,
+    mut library: ResMut<RuleLibrary>,
+    mut sim: ResMut<Simulation>,
+    mut cell_states: ResMut<CellStates>,
+    language: Res<Language>,
+    mut text: Query<&mut Text, With<RuleButtonText>>,
+    mut rules_label: Query<&mut Text, (With<RulesLabel>, Without<RuleButtonText>)>,
+    mut last: Local<Option<Rule>>,
+) {
+    let pressed = interaction_query
+        .iter()
+        .filter(|i| **i == Interaction::Pressed)
+        .count();
+    let cycles = pressed + actions.read().filter(|a| **a == Action::CycleRule).count();
+    if cycles > 0 {
+        library.current = (library.current + cycles) % library.rules.len();
+        sim.rule = library.rules[library.current].1.clone();
+        info!("切换规则: {}", sim.rule);
+    }
+
+    // 规则也可能由打开的会话改变，这里统一同步显示
+    let changed = last.as_ref() != Some(&sim.rule);
+    if changed {
+        *last = Some(sim.rule.clone());
+        // 状态数变少时，网格中留下的高状态在新规则下没有意义
+        let states = sim.rule.states();
+        if cell_states.states.iter().flatten().any(|s| *s >= states) {
+            clamp_states(&mut cell_states, states);
+        }
+    }
+    if changed || language.is_changed() {
+        for mut text in &mut text {
+            text.sections[0].value = library.label(&sim.rule, *language);
+        }
+        let description = match &sim.rule {
+            Rule::Table(table) => format!(
+                "{}\n{}",
+                language.trf("label.rule", &table.name),
+                table.description
+            ),
+            // 规则说明的文字只适用于康威规则，其余规则显示名称与规则字符串
+            rule if *rule == Rule::default() => language.tr("label.rules").to_string(),
+            rule if library.rules.iter().any(|(_, r)| r == rule) => {
+                format!("{}\n{}", library.label(rule, *language), rule)
+            }
+            rule => library.label(rule, *language),
+        };
+        for mut text in &mut rules_label {
+            text.sections[0].value = description.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switching_rules_clamps_states() {
+        let mut app = App::new();
+        app.add_event::<Action>()
+            .init_resource::<RuleLibrary>()
+            .init_resource::<Language>()
+            .insert_resource(CellStates::default())
+            .insert_resource(Simulation {
+                rule: Rule::parse("B2/S/C3").unwrap(),
+                ..default()
+            })
+            .add_systems(Update, rule_switch_system);
+        app.world_mut().resource_mut::<CellStates>().states[4][5] = 2;
+        app.update();
+        assert_eq!(app.world().resource::<CellStates>().states[4][5], 2);
+
+        app.world_mut().resource_mut::<Simulation>().rule = Rule::default();
+        app.update();
+        assert_eq!(
+            app.world().resource::<CellStates>().states[4][5],
+            crate::ALIVE
+        );
+    }
+}
@@ -0,0 +1,463 @@
+use crate::draw::{BlocksGrid, DrawTool, cursor_cell};
+use crate::edit::CellEdit;
+use crate::evolution::{Rule, Topology, next_generation};
+use crate::i18n::Localized;
+use crate::keybindings::Action;
+use crate::theme::{ThemeColor, Themed, ThemedBorder, ThemedText};
+use crate::{ALIVE, CellStates, DEAD, GRID_SIZE, GridCamera, GridViewport, Simulation, rle};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+/// 选区操作
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectionAction {
+    Copy,
+    Cut,
+    Paste,
+    ClearInside,
+    ClearOutside,
+    RandomFill,
+    Rotate,
+    FlipHorizontal,
+    FlipVertical,
+    Step,
+}
+
+/// 网格上的选区框
+#[derive(Component)]
+pub struct SelectionOverlay;
+
+/// 复制出的矩形块，按行存储每格的状态
+#[derive(Clone)]
+struct Clip {
+    width: usize,
+    height: usize,
+    cells: Vec<u8>,
+}
+
+impl Clip {
+    fn get(&self, x: usize, y: usize) -> u8 {
+        self.cells[y * self.width + x]
+    }
+
+    fn from_fn(width: usize, height: usize, f: impl Fn(usize, usize) -> u8) -> Self {
+        let mut cells = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                cells.push(f(x, y));
+            }
+        }
+        Self {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// 顺时针旋转 90°
+    fn rotated(&self) -> Self {
+        Self::from_fn(self.height, self.width, |x, y| {
+            self.get(y, self.height - 1 - x)
+        })
+    }
+
+    fn flipped_horizontal(&self) -> Self {
+        Self::from_fn(self.width, self.height, |x, y| {
+            self.get(self.width - 1 - x, y)
+        })
+    }
+
+    fn flipped_vertical(&self) -> Self {
+        Self::from_fn(self.width, self.height, |x, y| {
+            self.get(x, self.height - 1 - y)
+        })
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Selection {
+    // 选区的两个角（含边界），min <= max
+    rect: Option<(IVec2, IVec2)>,
+    // 拖拽选区时的起点
+    anchor: Option<IVec2>,
+    clipboard: Option<Clip>,
+}
+
+impl Selection {
+    /// 当前选区的两个角（含边界）
+    pub fn rect(&self) -> Option<(IVec2, IVec2)> {
+        self.rect
+    }
+}
+
+fn clamp_to_grid(p: IVec2) -> IVec2 {
+    p.clamp(IVec2::ZERO, IVec2::splat(GRID_SIZE as i32 - 1))
+}
+
+fn extract(cell_states: &CellStates, min: IVec2, max: IVec2) -> Clip {
+    let size = max - min + IVec2::ONE;
+    Clip::from_fn(size.x as usize, size.y as usize, |x, y| {
+        cell_states.states[min.y as usize + y][min.x as usize + x]
+    })
+}
+
+/// 把块写到 at 处（超出网格的部分丢弃）
+fn place(clip: &Clip, at: IVec2) -> Vec<(usize, usize, u8)> {
+    let mut cells = Vec::new();
+    for y in 0..clip.height {
+        for x in 0..clip.width {
+            let (gx, gy) = (at.x as usize + x, at.y as usize + y);
+            if gx < GRID_SIZE && gy < GRID_SIZE {
+                cells.push((gx, gy, clip.get(x, y)));
+            }
+        }
+    }
+    cells
+}
+
+/// 选区内外的所有格子
+fn region(min: IVec2, max: IVec2, inside: bool) -> impl Iterator<Item = (usize, usize)> {
+    (0..GRID_SIZE).flat_map(move |y| {
+        (0..GRID_SIZE).filter_map(move |x| {
+            let p = IVec2::new(x as i32, y as i32);
+            let contained = p.cmpge(min).all() && p.cmple(max).all();
+            (contained == inside).then_some((x, y))
+        })
+    })
+}
+
+fn clip_to_rle(clip: &Clip, rule: &Rule) -> String {
+    rle::write_pattern(
+        clip.width,
+        clip.height,
+        &rule.to_string(),
+        rule.is_generations(),
+        |x, y| clip.get(x, y),
+    )
+}
+
+/// 超出当前规则状态数的格子按存活处理
+/// 头部的宽高来自剪贴板中的任意文本，不能直接信任：比网格还大的图案不接受
+fn clip_from_rle(text: &str, states: u8) -> Option<Clip> {
+    let pattern = rle::parse_pattern(text).ok()?;
+    let (width, height) = (pattern.width, pattern.height);
+    let area = width.checked_mul(height)?;
+    if area == 0 || width > GRID_SIZE || height > GRID_SIZE {
+        return None;
+    }
+    let mut clip = Clip::from_fn(width, height, |_, _| DEAD);
+    for (x, y, state) in pattern.cells {
+        if x < width && y < height {
+            clip.cells[y * width + x] = if state < states { state } else { ALIVE };
+        }
+    }
+    Some(clip)
+}
+
+/// 选择工具下拖拽出矩形选区，Deselect 操作（默认 Esc）取消选区
+#[allow(clippy::too_many_arguments)]
+pub fn selection_mouse_system(
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut actions: EventReader<Action>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    viewport: Query<(&Node, &GlobalTransform), With<GridViewport>>,
+    camera: Res<GridCamera>,
+    tool: Res<DrawTool>,
+    mut selection: ResMut<Selection>,
+    blockers: Query<&Interaction, With<BlocksGrid>>,
+) {
+    if actions.read().any(|a| *a == Action::Deselect) {
+        selection.rect = None;
+        selection.anchor = None;
+    }
+    if *tool != DrawTool::Select {
+        return;
+    }
+    let (Ok(window), Ok(viewport)) = (windows.get_single(), viewport.get_single()) else {
+        return;
+    };
+    let Some(cursor) = cursor_cell(window, viewport, &camera) else {
+        return;
+    };
+
+    if mouse.just_pressed(MouseButton::Left) {
+        let (node, transform) = viewport;
+        let visible = Rect::from_center_size(transform.translation().truncate(), node.size());
+        let in_grid =
+            cursor.cmpge(IVec2::ZERO).all() && cursor.cmplt(IVec2::splat(GRID_SIZE as i32)).all();
+        if in_grid
+            && window
+                .cursor_position()
+                .is_some_and(|p| visible.contains(p))
+            && blockers.iter().all(|i| *i == Interaction::None)
+        {
+            selection.anchor = Some(cursor);
+            selection.rect = Some((cursor, cursor));
+        }
+    } else if let Some(anchor) = selection.anchor {
+        let current = clamp_to_grid(cursor);
+        let rect = (anchor.min(current), anchor.max(current));
+        if selection.rect != Some(rect) {
+            selection.rect = Some(rect);
+        }
+        if !mouse.pressed(MouseButton::Left) {
+            selection.anchor = None;
+        }
+    }
+}
+
+/// 执行选区操作（按钮或快捷键，默认 Ctrl+C / Ctrl+X / Ctrl+V / Delete / Shift+Delete）
+#[allow(clippy::too_many_arguments)]
+pub fn selection_action_system(
+    interaction_query: Query<(&Interaction, &SelectionAction), Changed<Interaction>>,
+    mut key_actions: EventReader<Action>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    viewport: Query<(&Node, &GlobalTransform), With<GridViewport>>,
+    camera: Res<GridCamera>,
+    cell_states: Res<CellStates>,
+    sim: Res<Simulation>,
+    mut selection: ResMut<Selection>,
+    mut edits: EventWriter<CellEdit>,
+) {
+    let mut actions: Vec<SelectionAction> = key_actions
+        .read()
+        .filter_map(|action| match action {
+            Action::Copy => Some(SelectionAction::Copy),
+            Action::Cut => Some(SelectionAction::Cut),
+            Action::Paste => Some(SelectionAction::Paste),
+            Action::ClearSelection => Some(SelectionAction::ClearInside),
+            Action::ClearOutside => Some(SelectionAction::ClearOutside),
+            _ => None,
+        })
+        .collect();
+    for (interaction, action) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            actions.push(*action);
+        }
+    }
+
+    for action in actions {
+        // 粘贴不要求已有选区，其余操作都作用于选区
+        if action == SelectionAction::Paste {
+            let text = arboard::Clipboard::new().and_then(|mut c| c.get_text());
+            let clip = match text
+                .ok()
+                .and_then(|text| clip_from_rle(&text, sim.rule.states()))
+            {
+                Some(clip) => clip,
+                None => match &selection.clipboard {
+                    Some(clip) => clip.clone(),
+                    None => {
+                        info!("剪贴板中没有可粘贴的图案");
+                        continue;
+                    }
+                },
+            };
+            let at = selection.rect.map(|(min, _)| min).or_else(|| {
+                let window = windows.get_single().ok()?;
+                let viewport = viewport.get_single().ok()?;
+                cursor_cell(window, viewport, &camera)
+            });
+            let at = clamp_to_grid(at.unwrap_or(IVec2::ZERO));
+            edits.send(CellEdit {
+                cells: place(&clip, at),
+                group: None,
+            });
+            let max = clamp_to_grid(at + IVec2::new(clip.width as i32, clip.height as i32) - 1);
+            selection.rect = Some((at, max));
+            info!("已粘贴 {}×{} 的图案", clip.width, clip.height);
+            continue;
+        }
+
+        let Some((min, max)) = selection.rect else {
+            info!("请先用选择工具框选区域");
+            continue;
+        };
+        let clip = extract(&cell_states, min, max);
+
+        let cells = match action {
+            SelectionAction::Copy | SelectionAction::Cut => {
+                let text = clip_to_rle(&clip, &sim.rule);
+                if let Err(e) = arboard::Clipboard::new().and_then(|mut c| c.set_text(text)) {
+                    warn!("无法写入系统剪贴板，仅保存在程序内: {}", e);
+                }
+                selection.clipboard = Some(clip);
+                if action == SelectionAction::Copy {
+                    continue;
+                }
+                region(min, max, true).map(|(x, y)| (x, y, DEAD)).collect()
+            }
+            SelectionAction::ClearInside => {
+                region(min, max, true).map(|(x, y)| (x, y, DEAD)).collect()
+            }
+            SelectionAction::ClearOutside => {
+                region(min, max, false).map(|(x, y)| (x, y, DEAD)).collect()
+            }
+            SelectionAction::RandomFill => region(min, max, true)
+                .map(|(x, y)| (x, y, if rand::random() { ALIVE } else { DEAD }))
+                .collect(),
+            SelectionAction::Rotate => {
+                let rotated = clip.rotated();
+                let mut cells: Vec<_> = region(min, max, true).map(|(x, y)| (x, y, DEAD)).collect();
+                cells.extend(place(&rotated, min));
+                let size = IVec2::new(rotated.width as i32, rotated.height as i32);
+                selection.rect = Some((min, clamp_to_grid(min + size - 1)));
+                cells
+            }
+            SelectionAction::FlipHorizontal => place(&clip.flipped_horizontal(), min),
+            SelectionAction::FlipVertical => place(&clip.flipped_vertical(), min),
+            SelectionAction::Step => {
+                // 只保留选区内的细胞，选区外视为死细胞，演化一代后写回选区
+                let mut grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+                for (x, y) in region(min, max, true) {
+                    grid[y][x] = cell_states.states[y][x];
+                }
+                let next = next_generation(&grid, &sim.rule, &sim.neighborhood, Topology::Bounded);
+                region(min, max, true)
+                    .map(|(x, y)| (x, y, next[y][x]))
+                    .collect()
+            }
+            SelectionAction::Paste => unreachable!(),
+        };
+        edits.send(CellEdit { cells, group: None });
+    }
+}
+
+/// 根据选区与缩放更新选区框的位置与显示
+pub fn selection_overlay_system(
+    selection: Res<Selection>,
+    camera: Res<GridCamera>,
+    mut overlay: Query<&mut Style, With<SelectionOverlay>>,
+) {
+    if !selection.is_changed() && !camera.is_changed() {
+        return;
+    }
+    let cell_total = camera.cell_size();
+    for mut style in &mut overlay {
+        match selection.rect {
+            Some((min, max)) => {
+                let mut size = (max - min + IVec2::ONE).as_vec2() * cell_total;
+                let mut left = min.x as f32 * cell_total;
+                // 六边形显示时错开的行让选区框多出半格
+                if camera.hex && min.y != max.y {
+                    size.x += cell_total / 2.0;
+                } else if camera.hex && min.y % 2 == 1 {
+                    left += cell_total / 2.0;
+                }
+                style.display = Display::Flex;
+                style.left = Val::Px(left);
+                style.top = Val::Px(min.y as f32 * cell_total);
+                style.width = Val::Px(size.x);
+                style.height = Val::Px(size.y);
+            }
+            None => style.display = Display::None,
+        }
+    }
+}
+
+/// 选区框节点，作为网格内容的子节点随相机平移
+pub fn selection_overlay(parent: &mut ChildBuilder) {
+    parent.spawn((
+        NodeBundle {
+            style: Style {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                border: UiRect::all(Val::Px(2.0)),
+                ..default()
+            },
+            // 画在网格线之上
+            z_index: ZIndex::Local(2),
+            ..default()
+        },
+        SelectionOverlay,
+        Themed(ThemeColor::SelectionFill),
+        ThemedBorder(ThemeColor::SelectionBorder),
+    ));
+}
+
+/// 右侧选区操作按钮（两列排布）
+pub fn selection_ui(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    parent.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: font.clone(),
+                font_size: 18.0,
+                color: Color::BLACK,
+            },
+        ),
+        Localized("label.selection"),
+        ThemedText(ThemeColor::Text),
+    ));
+
+    let actions = [
+        ("selection.copy", SelectionAction::Copy),
+        ("selection.cut", SelectionAction::Cut),
+        ("selection.paste", SelectionAction::Paste),
+        ("selection.random_fill", SelectionAction::RandomFill),
+        ("selection.clear_inside", SelectionAction::ClearInside),
+        ("selection.clear_outside", SelectionAction::ClearOutside),
+        ("selection.rotate", SelectionAction::Rotate),
+        ("selection.step", SelectionAction::Step),
+        ("selection.flip_horizontal", SelectionAction::FlipHorizontal),
+        ("selection.flip_vertical", SelectionAction::FlipVertical),
+    ];
+
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                flex_wrap: FlexWrap::Wrap,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|grid| {
+            for (label, action) in actions {
+                grid.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(80.0),
+                            height: Val::Px(26.0),
+                            margin: UiRect::all(Val::Px(2.0)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    action,
+                    Themed(ThemeColor::SelectionButton),
+                ))
+                .with_children(|btn| {
+                    btn.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 14.0,
+                                color: Color::WHITE,
+                            },
+                        ),
+                        Localized(label),
+                        ThemedText(ThemeColor::ButtonText),
+                    ));
+                });
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paste_rejects_oversized_header() {
+        assert!(clip_from_rle("x = 100000, y = 100000\no!", 2).is_none());
+        assert!(clip_from_rle("x = 18446744073709551615, y = 2\no!", 2).is_none());
+        let clip = clip_from_rle("x = 3, y = 1\n2o!", 2).unwrap();
+        assert_eq!((clip.width, clip.height), (3, 1));
+        assert_eq!(clip.cells, vec![ALIVE, ALIVE, DEAD]);
+    }
+}
@@ -0,0 +1,334 @@
+use crate::edit::EditHistory;
+use crate::evolution::{Neighborhood, Rule, Topology};
+use crate::i18n::Localized;
+use crate::keybindings::Action;
+use crate::rules::RuleLibrary;
+use crate::theme::{ThemeColor, Themed, ThemedText};
+use crate::{
+    ALIVE, CellStates, DEAD, EvolutionTimer, GRID_SIZE, Grid, GridCamera, History, Simulation, rle,
+    set_cell_state,
+};
+use bevy::prelude::*;
+use std::fs;
+use std::time::Duration;
+
+/// 会话文件保存在当前工作目录下
+pub const SESSION_FILE: &str = "lifegame_session.txt";
+
+/// 会话按钮
+#[derive(Component)]
+pub enum SessionButton {
+    Save,
+    Open,
+}
+
+/// 一次完整会话：网格、代数、规则、邻域、拓扑、速度、相机位置与缩放、历史
+pub struct Session {
+    pub generation: u64,
+    pub rule: Rule,
+    pub neighborhood: Neighborhood,
+    pub topology: Topology,
+    pub step_interval: f32,
+    pub camera: Vec2,
+    pub zoom: f32,
+    pub grid: Grid,
+    pub history: Vec<Grid>,
+}
+
+impl Session {
+    /// 文本格式：每行 `key = value`，网格与历史用单行 RLE 表示
+    pub fn to_text(&self) -> String {
+        let mut out = String::from("# lifegame session\n");
+        out += &format!("size = {}\n", GRID_SIZE);
+        out += &format!("generation = {}\n", self.generation);
+        out += &format!("rule = {}\n", self.rule);
+        out += &format!("neighborhood = {}\n", self.neighborhood.name());
+        out += &format!("topology = {}\n", self.topology.name());
+        out += &format!("step_interval = {}\n", self.step_interval);
+        out += &format!(
+            "camera = {} {} {}\n",
+            self.camera.x, self.camera.y, self.zoom
+        );
+        out += &format!("grid = {}\n", encode_grid(&self.grid));
+        // 历史按从旧到新的顺序写出
+        for grid in &self.history {
+            out += &format!("history = {}\n", encode_grid(grid));
+        }
+        out
+    }
+
+    /// rules 用于查找以名称保存的规则文件
+    pub fn from_text(text: &str, rules: &RuleLibrary) -> Result<Self, String> {
+        let mut session = Session {
+            generation: 0,
+            rule: Rule::default(),
+            neighborhood: Neighborhood::default(),
+            topology: Topology::default(),
+            step_interval: crate::STEP_INTERVAL,
+            camera: Vec2::ZERO,
+            zoom: 1.0,
+            grid: [[DEAD; GRID_SIZE]; GRID_SIZE],
+            history: Vec::new(),
+        };
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let err = |what: &str| format!("第 {} 行{}: {}", index + 1, what, line);
+            let (key, value) = line
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .ok_or_else(|| err("缺少 '='"))?;
+
+            match key {
+                "size" => {
+                    if value.parse::<usize>() != Ok(GRID_SIZE) {
+                        return Err(err("网格尺寸不匹配"));
+                    }
+                }
+                "generation" => session.generation = value.parse().map_err(|_| err("代数无效"))?,
+                "rule" => session.rule = rules.parse(value).ok_or_else(|| err("规则无效"))?,
+                "neighborhood" => {
+                    session.neighborhood =
+                        Neighborhood::from_name(value).ok_or_else(|| err("邻域无效"))?
+                }
+                "topology" => {
+                    session.topology = Topology::from_name(value).ok_or_else(|| err("拓扑无效"))?
+                }
+                // 与调速快捷键的范围相同
+                "step_interval" => {
+                    session.step_interval = value
+                        .parse()
+                        .ok()
+                        .filter(|v: &f32| v.is_finite() && *v > 0.0)
+                        .ok_or_else(|| err("速度无效"))?
+                        .clamp(crate::MIN_STEP_INTERVAL, crate::MAX_STEP_INTERVAL)
+                }
+                // 缩放可省略（旧版会话文件只有位置）
+                "camera" => {
+                    let mut parts = value.split_whitespace().map(str::parse::<f32>);
+                    match (parts.next(), parts.next(), parts.next()) {
+                        (Some(Ok(x)), Some(Ok(y)), zoom) => {
+                            session.camera = Vec2::new(x, y);
+                            session.zoom = match zoom {
+                                None => 1.0,
+                                Some(Ok(z)) if z > 0.0 => z,
+                                _ => return Err(err("相机缩放无效")),
+                            };
+                        }
+                        _ => return Err(err("相机位置无效")),
+                    }
+                }
+                "grid" => session.grid = decode_grid(value).map_err(|e| err(&e))?,
+                "history" => session
+                    .history
+                    .push(decode_grid(value).map_err(|e| err(&e))?),
+                // 未知字段忽略，便于以后扩展
+                _ => warn!("忽略未知的会话字段: {}", key),
+            }
+        }
+
+        // 超出规则状态数的格子按存活处理（与粘贴相同）；规则可能写在网格之后，所以读完再处理
+        let states = session.rule.states();
+        for grid in std::iter::once(&mut session.grid).chain(&mut session.history) {
+            for state in grid.iter_mut().flatten() {
+                if *state >= states {
+                    *state = ALIVE;
+                }
+            }
+        }
+
+        Ok(session)
+    }
+}
+
+// 只有两种状态时沿用 b/o 记法，与旧版会话文件保持一致
+fn encode_grid(grid: &Grid) -> String {
+    let multistate = grid.iter().flatten().any(|s| *s > ALIVE);
+    rle::encode(GRID_SIZE, GRID_SIZE, multistate, |x, y| grid[y][x])
+}
+
+fn decode_grid(body: &str) -> Result<Grid, String> {
+    let mut grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+    for (x, y, state) in rle::decode(body)? {
+        if x >= GRID_SIZE || y >= GRID_SIZE {
+            return Err(format!("细胞 ({}, {}) 超出网格", x, y));
+        }
+        grid[y][x] = state;
+    }
+    Ok(grid)
+}
+
+/// 左侧控制栏中的保存 / 打开按钮
+pub fn session_ui(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    for (label, button) in [
+        ("button.save_session", SessionButton::Save),
+        ("button.open_session", SessionButton::Open),
+    ] {
+        parent
+            .spawn((
+                ButtonBundle {
+                    style: Style {
+                        width: Val::Px(140.0),
+                        height: Val::Px(40.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                },
+                button,
+                Themed(ThemeColor::SessionButton),
+            ))
+            .with_children(|r| {
+                r.spawn((
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 18.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    Localized(label),
+                    ThemedText(ThemeColor::ButtonText),
+                ));
+            });
+    }
+}
+
+/// 点击按钮或按快捷键（默认 Ctrl+S / Ctrl+O）保存、打开会话
+#[allow(clippy::too_many_arguments)]
+pub fn session_system(
+    interaction_query: Query<(&Interaction, &SessionButton), Changed<Interaction>>,
+    mut actions: EventReader<Action>,
+    mut cell_states: ResMut<CellStates>,
+    mut sim: ResMut<Simulation>,
+    mut timer: ResMut<EvolutionTimer>,
+    mut camera: ResMut<GridCamera>,
+    mut history: ResMut<History>,
+    mut edit_history: ResMut<EditHistory>,
+    rules: Res<RuleLibrary>,
+) {
+    let mut save = false;
+    let mut open = false;
+    for action in actions.read() {
+        match action {
+            Action::Save => save = true,
+            Action::Open => open = true,
+            _ => {}
+        }
+    }
+    for (interaction, button) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            match button {
+                SessionButton::Save => save = true,
+                SessionButton::Open => open = true,
+            }
+        }
+    }
+
+    if save {
+        let session = Session {
+            generation: sim.generation,
+            rule: sim.rule.clone(),
+            neighborhood: sim.neighborhood.clone(),
+            topology: sim.topology,
+            step_interval: timer.0.duration().as_secs_f32(),
+            camera: camera.offset,
+            zoom: camera.zoom,
+            grid: cell_states.states,
+            history: history.generations.iter().copied().collect(),
+        };
+        match fs::write(SESSION_FILE, session.to_text()) {
+            Ok(()) => info!("会话已保存到 {}", SESSION_FILE),
+            Err(e) => error!("保存会话失败: {}", e),
+        }
+    }
+
+    if open {
+        let session = fs::read_to_string(SESSION_FILE)
+            .map_err(|e| e.to_string())
+            .and_then(|text| Session::from_text(&text, &rules));
+        let session = match session {
+            Ok(session) => session,
+            Err(e) => {
+                error!("打开会话失败: {}", e);
+                return;
+            }
+        };
+
+        for y in 0..GRID_SIZE {
+            for x in 0..GRID_SIZE {
+                set_cell_state(&mut cell_states, x, y, session.grid[y][x]);
+            }
+        }
+        sim.generation = session.generation;
+        sim.rule = session.rule;
+        sim.neighborhood = session.neighborhood;
+        sim.topology = session.topology;
+        timer
+            .0
+            .set_duration(Duration::from_secs_f32(session.step_interval));
+        camera.offset = session.camera;
+        camera.zoom = session.zoom.clamp(crate::MIN_ZOOM, crate::MAX_ZOOM);
+        history.generations = session.history.into();
+        // 打开的会话与之前的编辑无关，清空撤销栈
+        edit_history.clear();
+        info!("已从 {} 打开会话（第 {} 代）", SESSION_FILE, sim.generation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn states_beyond_rule_load_as_alive() {
+        let text = "grid = yO2o!\nhistory = yO!\nrule = B3/S23\n";
+        let session = Session::from_text(text, &RuleLibrary::default()).unwrap();
+        assert_eq!(session.grid[0][..4], [ALIVE, ALIVE, ALIVE, DEAD]);
+        assert_eq!(session.history[0][0][0], ALIVE);
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let mut grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+        grid[1][2] = ALIVE;
+        grid[3][4] = 2;
+        let session = Session {
+            generation: 42,
+            rule: Rule::parse("B2/S/C3").unwrap(),
+            neighborhood: Neighborhood::default(),
+            topology: Topology::Torus,
+            step_interval: 0.4,
+            camera: Vec2::new(12.5, -3.0),
+            zoom: 2.0,
+            grid,
+            history: vec![[[DEAD; GRID_SIZE]; GRID_SIZE], grid],
+        };
+        let loaded = Session::from_text(&session.to_text(), &RuleLibrary::default()).unwrap();
+        assert_eq!(loaded.generation, 42);
+        assert_eq!(loaded.rule, session.rule);
+        assert_eq!(loaded.neighborhood, session.neighborhood);
+        assert_eq!(loaded.topology, Topology::Torus);
+        assert_eq!(loaded.step_interval, 0.4);
+        assert_eq!((loaded.camera, loaded.zoom), (session.camera, 2.0));
+        assert_eq!(loaded.grid, grid);
+        assert_eq!(loaded.history, session.history);
+    }
+
+    #[test]
+    fn step_interval_must_be_finite() {
+        let rules = RuleLibrary::default();
+        for value in ["inf", "NaN", "0", "-1"] {
+            let text = format!("step_interval = {}\n", value);
+            assert!(Session::from_text(&text, &rules).is_err(), "{}", value);
+        }
+        // 超出调速范围的值按范围截断
+        let session = Session::from_text("step_interval = 1e30\n", &rules).unwrap();
+        assert_eq!(session.step_interval, crate::MAX_STEP_INTERVAL);
+    }
+}
@@ -0,0 +1,516 @@
+use crate::i18n::Language;
+use crate::keybindings::Action;
+use bevy::prelude::*;
+use std::fs;
+
+/// 用户主题文件，位于当前工作目录
+pub const THEMES_FILE: &str = "themes.cfg";
+
+/// 界面元素使用的颜色角色
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThemeColor {
+    Background,
+    Panel,
+    GridLine,
+    GridMajor,
+    CellAlive,
+    CellDead,
+    CellDying,
+    Text,
+    ButtonText,
+    StartButton,
+    ClearButton,
+    SessionButton,
+    ModeButton,
+    PatternButton,
+    ToolButton,
+    ToolSelected,
+    SelectionButton,
+    SelectionBorder,
+    SelectionFill,
+    PreviewPaint,
+    PreviewErase,
+    HelpBackdrop,
+    HelpPanel,
+    DiffHere,
+    DiffThere,
+}
+
+impl ThemeColor {
+    const ALL: &[ThemeColor] = &[
+        ThemeColor::Background,
+        ThemeColor::Panel,
+        ThemeColor::GridLine,
+        ThemeColor::GridMajor,
+        ThemeColor::CellAlive,
+        ThemeColor::CellDead,
+        ThemeColor::CellDying,
+        ThemeColor::Text,
+        ThemeColor::ButtonText,
+        ThemeColor::StartButton,
+        ThemeColor::ClearButton,
+        ThemeColor::SessionButton,
+        ThemeColor::ModeButton,
+        ThemeColor::PatternButton,
+        ThemeColor::ToolButton,
+        ThemeColor::ToolSelected,
+        ThemeColor::SelectionButton,
+        ThemeColor::SelectionBorder,
+        ThemeColor::SelectionFill,
+        ThemeColor::PreviewPaint,
+        ThemeColor::PreviewErase,
+        ThemeColor::HelpBackdrop,
+        ThemeColor::HelpPanel,
+        ThemeColor::DiffHere,
+        ThemeColor::DiffThere,
+    ];
+    const COUNT: usize = Self::ALL.len();
+
+    /// 主题文件中使用的名称
+    fn name(self) -> &'static str {
+        match self {
+            ThemeColor::Background => "background",
+            ThemeColor::Panel => "panel",
+            ThemeColor::GridLine => "grid_line",
+            ThemeColor::GridMajor => "grid_major",
+            ThemeColor::CellAlive => "cell_alive",
+            ThemeColor::CellDead => "cell_dead",
+            ThemeColor::CellDying => "cell_dying",
+            ThemeColor::Text => "text",
+            ThemeColor::ButtonText => "button_text",
+            ThemeColor::StartButton => "start_button",
+            ThemeColor::ClearButton => "clear_button",
+            ThemeColor::SessionButton => "session_button",
+            ThemeColor::ModeButton => "mode_button",
+            ThemeColor::PatternButton => "pattern_button",
+            ThemeColor::ToolButton => "tool_button",
+            ThemeColor::ToolSelected => "tool_selected",
+            ThemeColor::SelectionButton => "selection_button",
+            ThemeColor::SelectionBorder => "selection_border",
+            ThemeColor::SelectionFill => "selection_fill",
+            ThemeColor::PreviewPaint => "preview_paint",
+            ThemeColor::PreviewErase => "preview_erase",
+            ThemeColor::HelpBackdrop => "help_backdrop",
+            ThemeColor::HelpPanel => "help_panel",
+            ThemeColor::DiffHere => "diff_here",
+            ThemeColor::DiffThere => "diff_there",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|c| c.name() == name)
+    }
+}
+
+/// 当前主题：每个颜色角色对应一种颜色
+#[derive(Resource, Clone)]
+pub struct Theme {
+    // 内置预设为 i18n 字符串表中的 key，用户主题为文件中写的名称
+    pub name: String,
+    colors: [Color; ThemeColor::COUNT],
+}
+
+impl Theme {
+    pub fn color(&self, role: ThemeColor) -> Color {
+        self.colors[role as usize]
+    }
+
+    fn set(&mut self, role: ThemeColor, color: Color) {
+        self.colors[role as usize] = color;
+    }
+
+    /// 网格中细胞的颜色
+    pub fn cell_color(&self, alive: bool) -> Color {
+        self.color(if alive {
+            ThemeColor::CellAlive
+        } else {
+            ThemeColor::CellDead
+        })
+    }
+
+    fn from_list(name: &str, list: [(ThemeColor, Color); ThemeColor::COUNT]) -> Self {
+        let mut theme = Theme {
+            name: name.to_string(),
+            colors: [Color::NONE; ThemeColor::COUNT],
+        };
+        for (role, color) in list {
+            theme.set(role, color);
+        }
+        theme
+    }
+
+    pub fn light() -> Self {
+        use ThemeColor::*;
+        Self::from_list(
+            "theme.light",
+            [
+                (Background, Color::WHITE),
+                (Panel, Color::srgb(0.95, 0.95, 0.95)),
+                (GridLine, Color::srgb(0.8, 0.8, 0.8)),
+                (GridMajor, Color::srgb(0.55, 0.55, 0.55)),
+                (CellAlive, Color::BLACK),
+                (CellDead, Color::WHITE),
+                (CellDying, Color::srgb(0.3, 0.45, 0.9)),
+                (Text, Color::BLACK),
+                (ButtonText, Color::WHITE),
+                (StartButton, Color::srgb(0.2, 0.6, 0.9)),
+                (ClearButton, Color::srgb(0.85, 0.2, 0.2)),
+                (SessionButton, Color::srgb(0.3, 0.65, 0.4)),
+                (ModeButton, Color::srgb(0.55, 0.4, 0.7)),
+                (PatternButton, Color::srgb(0.4, 0.4, 0.8)),
+                (ToolButton, Color::srgb(0.4, 0.4, 0.8)),
+                (ToolSelected, Color::srgb(0.2, 0.2, 0.55)),
+                (SelectionButton, Color::srgb(0.35, 0.55, 0.75)),
+                (SelectionBorder, Color::srgb(0.1, 0.4, 0.9)),
+                (SelectionFill, Color::srgba(0.2, 0.5, 1.0, 0.15)),
+                (PreviewPaint, Color::srgb(0.45, 0.45, 0.45)),
+                (PreviewErase, Color::srgb(0.95, 0.7, 0.7)),
+                (HelpBackdrop, Color::srgba(0.0, 0.0, 0.0, 0.6)),
+                (HelpPanel, Color::srgb(0.98, 0.98, 0.98)),
+                (DiffHere, Color::srgb(0.95, 0.45, 0.1)),
+                (DiffThere, Color::srgb(0.3, 0.55, 0.95)),
+            ],
+        )
+    }
+
+    pub fn dark() -> Self {
+        use ThemeColor::*;
+        Self::from_list(
+            "theme.dark",
+            [
+                (Background, Color::srgb(0.08, 0.08, 0.09)),
+                (Panel, Color::srgb(0.14, 0.14, 0.16)),
+                (GridLine, Color::srgb(0.25, 0.25, 0.28)),
+                (GridMajor, Color::srgb(0.42, 0.42, 0.46)),
+                (CellAlive, Color::srgb(0.9, 0.9, 0.85)),
+                (CellDead, Color::srgb(0.1, 0.1, 0.11)),
+                (CellDying, Color::srgb(0.35, 0.55, 0.95)),
+                (Text, Color::srgb(0.88, 0.88, 0.88)),
+                (ButtonText, Color::srgb(0.95, 0.95, 0.95)),
+                (StartButton, Color::srgb(0.15, 0.42, 0.7)),
+                (ClearButton, Color::srgb(0.65, 0.18, 0.18)),
+                (SessionButton, Color::srgb(0.2, 0.47, 0.3)),
+                (ModeButton, Color::srgb(0.42, 0.3, 0.56)),
+                (PatternButton, Color::srgb(0.28, 0.28, 0.55)),
+                (ToolButton, Color::srgb(0.28, 0.28, 0.55)),
+                (ToolSelected, Color::srgb(0.5, 0.5, 0.88)),
+                (SelectionButton, Color::srgb(0.22, 0.38, 0.52)),
+                (SelectionBorder, Color::srgb(0.4, 0.7, 1.0)),
+                (SelectionFill, Color::srgba(0.4, 0.7, 1.0, 0.15)),
+                (PreviewPaint, Color::srgb(0.55, 0.55, 0.55)),
+                (PreviewErase, Color::srgb(0.5, 0.25, 0.25)),
+                (HelpBackdrop, Color::srgba(0.0, 0.0, 0.0, 0.7)),
+                (HelpPanel, Color::srgb(0.16, 0.16, 0.18)),
+                (DiffHere, Color::srgb(1.0, 0.6, 0.2)),
+                (DiffThere, Color::srgb(0.3, 0.6, 1.0)),
+            ],
+        )
+    }
+
+    pub fn high_contrast() -> Self {
+        use ThemeColor::*;
+        Self::from_list(
+            "theme.high_contrast",
+            [
+                (Background, Color::BLACK),
+                (Panel, Color::BLACK),
+                (GridLine, Color::srgb(0.5, 0.5, 0.5)),
+                (GridMajor, Color::WHITE),
+                (CellAlive, Color::srgb(1.0, 1.0, 0.0)),
+                (CellDead, Color::BLACK),
+                (CellDying, Color::srgb(0.0, 0.6, 1.0)),
+                (Text, Color::WHITE),
+                (ButtonText, Color::BLACK),
+                (StartButton, Color::srgb(0.0, 1.0, 1.0)),
+                (ClearButton, Color::srgb(1.0, 0.35, 0.35)),
+                (SessionButton, Color::srgb(0.0, 1.0, 0.0)),
+                (ModeButton, Color::srgb(1.0, 0.5, 1.0)),
+                (PatternButton, Color::WHITE),
+                (ToolButton, Color::WHITE),
+                (ToolSelected, Color::srgb(1.0, 1.0, 0.0)),
+                (SelectionButton, Color::WHITE),
+                (SelectionBorder, Color::srgb(0.0, 1.0, 1.0)),
+                (SelectionFill, Color::srgba(0.0, 1.0, 1.0, 0.2)),
+                (PreviewPaint, Color::srgb(0.6, 0.6, 0.6)),
+                (PreviewErase, Color::srgb(1.0, 0.4, 0.4)),
+                (HelpBackdrop, Color::srgba(0.0, 0.0, 0.0, 0.85)),
+                (HelpPanel, Color::BLACK),
+                (DiffHere, Color::srgb(1.0, 0.5, 0.0)),
+                (DiffThere, Color::srgb(0.0, 0.8, 1.0)),
+            ],
+        )
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::light()
+    }
+}
+
+/// 可切换的主题列表：内置的三个预设加上用户主题
+#[derive(Resource)]
+pub struct ThemeLibrary {
+    themes: Vec<Theme>,
+    current: usize,
+}
+
+impl Default for ThemeLibrary {
+    fn default() -> Self {
+        Self {
+            themes: vec![Theme::light(), Theme::dark(), Theme::high_contrast()],
+            current: 0,
+        }
+    }
+}
+
+/// 解析 "#rrggbb" 或 "#rrggbbaa"
+fn parse_hex(text: &str) -> Option<Color> {
+    let hex = text.strip_prefix('#')?;
+    // from_str_radix 会接受 '+' 号，这里要求全是十六进制数字
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let byte = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    match hex.len() {
+        6 => Some(Color::srgb_u8(byte(0)?, byte(2)?, byte(4)?)),
+        8 => Some(Color::srgba_u8(byte(0)?, byte(2)?, byte(4)?, byte(6)?)),
+        _ => None,
+    }
+}
+
+/// 解析主题文件：`[主题名]` 开始一个主题，其后每行 `颜色角色 = #rrggbb`
+/// 可用 `base = dark` 指定基础预设（light / dark / high_contrast），未写的角色沿用基础预设
+pub fn parse_themes(text: &str) -> Vec<Theme> {
+    let mut themes: Vec<Theme> = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            let mut theme = Theme::light();
+            theme.name = name.trim().to_string();
+            themes.push(theme);
+            continue;
+        }
+        let Some(theme) = themes.last_mut() else {
+            warn!(
+                "{} 第 {} 行不属于任何主题: {}",
+                THEMES_FILE,
+                index + 1,
+                line
+            );
+            continue;
+        };
+        let Some((key, value)) = line.split_once('=').map(|(k, v)| (k.trim(), v.trim())) else {
+            warn!("{} 第 {} 行缺少 '=': {}", THEMES_FILE, index + 1, line);
+            continue;
+        };
+
+        if key == "base" {
+            let base = match value {
+                "light" => Theme::light(),
+                "dark" => Theme::dark(),
+                "high_contrast" => Theme::high_contrast(),
+                _ => {
+                    warn!("{} 第 {} 行: 未知预设 {}", THEMES_FILE, index + 1, value);
+                    continue;
+                }
+            };
+            theme.colors = base.colors;
+            continue;
+        }
+        match (ThemeColor::from_name(key), parse_hex(value)) {
+            (Some(role), Some(color)) => theme.set(role, color),
+            (None, _) => warn!("{} 第 {} 行: 未知颜色角色 {}", THEMES_FILE, index + 1, key),
+            (_, None) => warn!(
+                "{} 第 {} 行: 无法解析颜色 {}",
+                THEMES_FILE,
+                index + 1,
+                value
+            ),
+        }
+    }
+
+    themes
+}
+
+/// 启动时读取用户主题
+pub fn load_themes(mut library: ResMut<ThemeLibrary>) {
+    let Ok(text) = fs::read_to_string(THEMES_FILE) else {
+        return;
+    };
+    let themes = parse_themes(&text);
+    info!("从 {} 读取了 {} 个主题", THEMES_FILE, themes.len());
+    library.themes.extend(themes);
+}
+
+/// 背景色跟随主题的节点
+#[derive(Component, Clone, Copy)]
+pub struct Themed(pub ThemeColor);
+
+/// 边框色跟随主题的节点
+#[derive(Component, Clone, Copy)]
+pub struct ThemedBorder(pub ThemeColor);
+
+/// 文字颜色跟随主题
+#[derive(Component, Clone, Copy)]
+pub struct ThemedText(pub ThemeColor);
+
+/// 主题切换按钮
+#[derive(Component)]
+pub struct ThemeButton;
+
+#[derive(Component)]
+pub struct ThemeButtonText;
+
+pub fn theme_ui(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(140.0),
+                    height: Val::Px(40.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            ThemeButton,
+            Themed(ThemeColor::ModeButton),
+        ))
+        .with_children(|r| {
+            r.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 18.0,
+                        ..default()
+                    },
+                ),
+                ThemeButtonText,
+                ThemedText(ThemeColor::ButtonText),
+            ));
+        });
+}
+
+/// 点击按钮或按快捷键循环切换主题；语言变化时刷新按钮文字
+pub fn theme_switch_system(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<ThemeButton>)>,
+    mut actions: EventReader<Action>,
+    mut library: ResMut<ThemeLibrary>,
+    mut theme: ResMut<Theme>,
+    language: Res<Language>,
+    mut text: Query<&mut Text, With<ThemeButtonText>>,
+) {
+    let pressed = interaction_query
+        .iter()
+        .filter(|i| **i == Interaction::Pressed)
+        .count();
+    let cycles = pressed + actions.read().filter(|a| **a == Action::CycleTheme).count();
+    if cycles > 0 {
+        library.current = (library.current + cycles) % library.themes.len();
+        *theme = library.themes[library.current].clone();
+        info!("切换主题: {}", language.tr(&theme.name));
+    }
+
+    if cycles > 0 || language.is_changed() {
+        for mut text in &mut text {
+            text.sections[0].value = language.trf("label.theme", language.tr(&theme.name));
+        }
+    }
+}
+
+/// 主题变化时刷新全部带主题标记的节点，新生成的节点也在这里上色
+pub fn apply_theme_system(
+    theme: Res<Theme>,
+    mut clear_color: ResMut<ClearColor>,
+    mut backgrounds: Query<(Ref<Themed>, &mut BackgroundColor)>,
+    mut borders: Query<(Ref<ThemedBorder>, &mut BorderColor)>,
+    mut texts: Query<(Ref<ThemedText>, &mut Text)>,
+) {
+    let all = theme.is_changed();
+    if all {
+        clear_color.0 = theme.color(ThemeColor::Background);
+    }
+
+    for (themed, mut color) in &mut backgrounds {
+        if all || themed.is_added() {
+            color.0 = theme.color(themed.0);
+        }
+    }
+    for (themed, mut color) in &mut borders {
+        if all || themed.is_added() {
+            color.0 = theme.color(themed.0);
+        }
+    }
+    for (themed, mut text) in &mut texts {
+        if all || themed.is_added() {
+            let color = theme.color(themed.0);
+            for section in &mut text.sections {
+                section.style.color = color;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roles_index_colors() {
+        for (i, role) in ThemeColor::ALL.iter().enumerate() {
+            assert_eq!(*role as usize, i);
+            assert_eq!(ThemeColor::from_name(role.name()), Some(*role));
+        }
+    }
+
+    #[test]
+    fn hex_colors() {
+        assert_eq!(parse_hex("#ff8000"), Some(Color::srgb_u8(255, 128, 0)));
+        assert_eq!(
+            parse_hex("#0000ff80"),
+            Some(Color::srgba_u8(0, 0, 255, 128))
+        );
+        for text in [
+            "ff8000", "#ff800", "#ff80000", "#gg0000", "#+f+f+f", "#ff80é",
+        ] {
+            assert_eq!(parse_hex(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn theme_file() {
+        let themes = parse_themes(
+            "cell_alive = #ffffff\n\
+             [夜间]\n\
+             base = dark\n\
+             cell_alive = #00ff00\n\
+             unknown_role = #000000\n\
+             text = nope\n\
+             [ Plain ]\n\
+             base = sepia\n",
+        );
+        assert_eq!(themes.len(), 2);
+        let (night, plain) = (&themes[0], &themes[1]);
+        assert_eq!(night.name, "夜间");
+        assert_eq!(
+            night.color(ThemeColor::CellAlive),
+            Color::srgb_u8(0, 255, 0)
+        );
+        // 未写或写错的角色沿用基础预设
+        let dark = Theme::dark();
+        assert_eq!(night.color(ThemeColor::Text), dark.color(ThemeColor::Text));
+        assert_eq!(
+            night.color(ThemeColor::Panel),
+            dark.color(ThemeColor::Panel)
+        );
+        assert_eq!(plain.name, "Plain");
+        assert_eq!(plain.colors, Theme::light().colors);
+    }
+}
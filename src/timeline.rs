@@ -0,0 +1,697 @@
+use crate::draw::BlocksGrid;
+use crate::edit::EditHistory;
+use crate::evolution::{Neighborhood, Rule, Topology};
+use crate::i18n::Language;
+use crate::keybindings::Action;
+use crate::lenia::Lenia;
+use crate::objects::World;
+use crate::theme::{ThemeColor, Themed, ThemedText};
+use crate::{
+    CellStates, DEAD, GRID_SIZE, Grid, HISTORY_LIMIT, History, Simulation, set_cell_state,
+};
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+use bevy::ui::RelativeCursorPosition;
+
+/// 每隔多少代保存一个关键帧，其间的代按需从关键帧重新演化
+const KEYFRAME_EVERY: u64 = 16;
+/// 所有分支合计保存的关键帧上限（每帧 GRID_SIZE² 字节）
+const MAX_KEYFRAMES: usize = 4096;
+
+/// 一段连续演化的历史：从 start 代到 end 代，规则不变
+/// start 之前的代沿用 parent 分支（编辑或换规则的位置即分叉点）
+struct Branch {
+    id: u32,
+    parent: Option<u32>,
+    start: u64,
+    end: u64,
+    // keyframes[i] 为第 start + i * KEYFRAME_EVERY 代
+    keyframes: Vec<Grid>,
+    rule: Rule,
+    neighborhood: Neighborhood,
+    topology: Topology,
+}
+
+impl Branch {
+    fn world(&self) -> World<'_> {
+        World {
+            rule: &self.rule,
+            neighborhood: &self.neighborhood,
+            topology: self.topology,
+        }
+    }
+
+    fn same_world(&self, sim: &Simulation) -> bool {
+        self.rule == sim.rule
+            && self.neighborhood == sim.neighborhood
+            && self.topology == sim.topology
+    }
+
+    /// 从最近的关键帧演化到第 generation 代（须在 start..=end 内）
+    fn grid_at(&self, generation: u64) -> Grid {
+        let offset = generation - self.start;
+        let world = self.world();
+        let mut grid = self.keyframes[(offset / KEYFRAME_EVERY) as usize];
+        for _ in 0..offset % KEYFRAME_EVERY {
+            grid = world.step(&grid);
+        }
+        grid
+    }
+}
+
+/// 已演化过的所有代，按分支组织；时间轴显示当前分支及其祖先连成的一条历史
+/// 拖动到过去某代后继续演化时沿用已有的历史（演化是确定的），
+/// 在那里编辑或换规则则分出新分支，原来的分支保留，可在时间轴上切换
+#[derive(Resource)]
+pub struct Timeline {
+    branches: Vec<Branch>,
+    next_id: u32,
+    // 时间轴显示的分支
+    current: u32,
+    // 网格当前所在的代、该代所属的分支与网格
+    cursor: u64,
+    owner: u32,
+    at: Grid,
+    visible: bool,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self {
+            branches: Vec::new(),
+            next_id: 0,
+            current: 0,
+            cursor: 0,
+            owner: 0,
+            at: [[DEAD; GRID_SIZE]; GRID_SIZE],
+            visible: false,
+        }
+    }
+}
+
+impl Timeline {
+    fn branch(&self, id: u32) -> &Branch {
+        self.branches
+            .iter()
+            .find(|b| b.id == id)
+            .expect("分支已被删除")
+    }
+
+    fn branch_mut(&mut self, id: u32) -> &mut Branch {
+        self.branches
+            .iter_mut()
+            .find(|b| b.id == id)
+            .expect("分支已被删除")
+    }
+
+    /// id 分支及其祖先中包含第 generation 代的那个
+    fn owner_of(&self, id: u32, generation: u64) -> Option<&Branch> {
+        let branch = self.branch(id);
+        if generation >= branch.start {
+            (generation <= branch.end).then_some(branch)
+        } else {
+            branch.parent.and_then(|p| self.owner_of(p, generation))
+        }
+    }
+
+    /// id 分支及其祖先，从 id 开始
+    fn lineage(&self, id: u32) -> Vec<u32> {
+        let mut ids = vec![id];
+        while let Some(parent) = self.branch(*ids.last().unwrap()).parent {
+            ids.push(parent);
+        }
+        ids
+    }
+
+    /// 时间轴的范围：最早的祖先的起点到当前分支的末尾
+    fn range(&self) -> (u64, u64) {
+        let root = *self.lineage(self.current).last().unwrap();
+        (self.branch(root).start, self.branch(self.current).end)
+    }
+
+    fn start_branch(
+        &mut self,
+        parent: Option<u32>,
+        generation: u64,
+        grid: &Grid,
+        sim: &Simulation,
+    ) {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.branches.push(Branch {
+            id,
+            parent,
+            start: generation,
+            end: generation,
+            keyframes: vec![*grid],
+            rule: sim.rule.clone(),
+            neighborhood: sim.neighborhood.clone(),
+            topology: sim.topology,
+        });
+        self.current = id;
+        self.owner = id;
+    }
+
+    /// 记录网格的变化：沿所在分支前进一代、原地编辑（分出新分支）、
+    /// 回到历史上已有的一代，或跳到无关的状态（新的历史）
+    fn record(&mut self, generation: u64, grid: &Grid, sim: &Simulation) {
+        if self.branches.is_empty() {
+            self.start_branch(None, generation, grid, sim);
+        } else {
+            let owner = self.branch(self.owner);
+            if generation == self.cursor && *grid == self.at && owner.same_world(sim) {
+                return;
+            }
+            let stepped = generation == self.cursor + 1
+                && owner.same_world(sim)
+                && owner.world().step(&self.at) == *grid;
+            if stepped {
+                let id = owner.id;
+                if owner.end == self.cursor {
+                    let branch = self.branch_mut(id);
+                    branch.end += 1;
+                    if (branch.end - branch.start).is_multiple_of(KEYFRAME_EVERY) {
+                        branch.keyframes.push(*grid);
+                    }
+                }
+                // 在祖先分支上前进到了分叉点之后，改为显示该祖先分支
+                if self.owner_of(self.current, generation).map(|b| b.id) != Some(id) {
+                    self.current = id;
+                }
+            } else if generation == self.cursor {
+                let has_children = self.branches.iter().any(|b| b.parent == Some(owner.id));
+                if owner.start == generation && owner.end == generation && !has_children {
+                    // 刚分出、还没演化过的分支上继续编辑，直接替换
+                    let id = owner.id;
+                    let branch = self.branch_mut(id);
+                    branch.keyframes[0] = *grid;
+                    branch.rule = sim.rule.clone();
+                    branch.neighborhood = sim.neighborhood.clone();
+                    branch.topology = sim.topology;
+                } else {
+                    let parent = if generation > owner.start {
+                        Some(owner.id)
+                    } else {
+                        owner.parent
+                    };
+                    self.start_branch(parent, generation, grid, sim);
+                }
+            } else {
+                // 撤销回到较早的代时，这一代已在历史上且网格相同，只移动位置
+                let recorded = self
+                    .owner_of(self.owner, generation)
+                    .filter(|b| b.same_world(sim) && b.grid_at(generation) == *grid)
+                    .map(|b| b.id);
+                if let Some(id) = recorded {
+                    self.owner = id;
+                } else {
+                    // 同一帧里既演化又编辑等：从历史上的前一代分出，与历史无关时才开始新的历史
+                    let parent = generation
+                        .checked_sub(1)
+                        .and_then(|g| self.owner_of(self.owner, g))
+                        .map(|b| b.id);
+                    self.start_branch(parent, generation, grid, sim);
+                }
+            }
+        }
+        self.cursor = generation;
+        self.at = *grid;
+        self.trim();
+    }
+
+    /// 关键帧超出上限时先删不在当前历史上、也没有子分支的旧分支，再删当前历史最早的关键帧
+    /// 最早的分支在子分支分叉点之后的关键帧已不在当前历史上，先删掉这些
+    fn trim(&mut self) {
+        while self
+            .branches
+            .iter()
+            .map(|b| b.keyframes.len())
+            .sum::<usize>()
+            > MAX_KEYFRAMES
+        {
+            let lineage = self.lineage(self.current);
+            let leaf = self
+                .branches
+                .iter()
+                .filter(|b| !lineage.contains(&b.id))
+                .find(|b| !self.branches.iter().any(|c| c.parent == Some(b.id)))
+                .map(|b| b.id);
+            if let Some(id) = leaf {
+                self.branches.retain(|b| b.id != id);
+                continue;
+            }
+            let root = *lineage.last().unwrap();
+            // 当前历史上 root 只占到下一个分支的分叉点之前
+            let fork = (lineage.len() > 1).then(|| self.branch(lineage[lineage.len() - 2]).start);
+            let owner = self.owner;
+            let cursor = self.cursor;
+            let branch = self.branch_mut(root);
+            if let Some(fork) = fork.filter(|fork| *fork > branch.start) {
+                let needed = ((fork - 1 - branch.start) / KEYFRAME_EVERY + 1) as usize;
+                if branch.keyframes.len() > needed {
+                    branch.keyframes.truncate(needed);
+                    branch.end = branch.end.min(fork - 1);
+                    continue;
+                }
+            }
+            if branch.keyframes.len() > 1
+                && (root != owner || cursor >= branch.start + KEYFRAME_EVERY)
+            {
+                branch.keyframes.remove(0);
+                branch.start += KEYFRAME_EVERY;
+            } else if root != owner && lineage.len() > 1 {
+                self.branches.retain(|b| b.id != root);
+                self.branch_mut(lineage[lineage.len() - 2]).parent = None;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// 跳到当前历史上的第 generation 代（超出范围时取最近的一端），返回该代的网格
+    fn seek(&mut self, generation: u64) -> Grid {
+        let (first, last) = self.range();
+        let generation = generation.clamp(first, last);
+        let owner = self.owner_of(self.current, generation).unwrap();
+        let (id, grid) = (owner.id, owner.grid_at(generation));
+        self.cursor = generation;
+        self.owner = id;
+        self.at = grid;
+        grid
+    }
+
+    /// 当前历史上 from..to 各代的网格，逐代演化而不是每代都从关键帧开始
+    fn grids(&self, from: u64, to: u64) -> Vec<Grid> {
+        let mut grids = Vec::new();
+        let mut previous: Option<(u32, Grid)> = None;
+        for generation in from..to {
+            let Some(branch) = self.owner_of(self.current, generation) else {
+                continue;
+            };
+            let grid = match previous {
+                Some((id, grid))
+                    if id == branch.id
+                        && !(generation - branch.start).is_multiple_of(KEYFRAME_EVERY) =>
+                {
+                    branch.world().step(&grid)
+                }
+                _ => branch.grid_at(generation),
+            };
+            grids.push(grid);
+            previous = Some((branch.id, grid));
+        }
+        grids
+    }
+
+    /// 切换到下一个分支（按创建顺序循环），时间轴位置尽量保持
+    fn next_branch(&mut self) {
+        let next = self
+            .branches
+            .iter()
+            .map(|b| b.id)
+            .find(|id| *id > self.current)
+            .or_else(|| self.branches.first().map(|b| b.id));
+        if let Some(id) = next {
+            self.current = id;
+        }
+    }
+}
+
+/// 网格底部的时间轴，按住时网格不响应绘制与框选
+#[derive(Component)]
+pub struct TimelineBar;
+
+#[derive(Component)]
+pub struct TimelineTrack;
+
+#[derive(Component)]
+pub struct TimelineFill;
+
+#[derive(Component)]
+pub struct TimelineText;
+
+#[derive(Component)]
+pub struct TimelineBranchButton;
+
+#[derive(Component)]
+pub struct TimelineBranchText;
+
+/// 网格外框底部的时间轴：分支按钮、可拖动的轨道与代数，默认隐藏
+pub fn timeline_bar(parent: &mut ChildBuilder, font: &Handle<Font>) {
+    parent
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    display: Display::None,
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(0.0),
+                    width: Val::Percent(100.0),
+                    height: Val::Px(32.0),
+                    padding: UiRect::horizontal(Val::Px(8.0)),
+                    column_gap: Val::Px(8.0),
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                focus_policy: FocusPolicy::Block,
+                ..default()
+            },
+            Interaction::default(),
+            TimelineBar,
+            BlocksGrid,
+            Themed(ThemeColor::Panel),
+        ))
+        .with_children(|bar| {
+            bar.spawn((
+                ButtonBundle {
+                    style: Style {
+                        width: Val::Px(90.0),
+                        height: Val::Px(24.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    focus_policy: FocusPolicy::Pass,
+                    ..default()
+                },
+                TimelineBranchButton,
+                Themed(ThemeColor::ModeButton),
+            ))
+            .with_children(|btn| {
+                btn.spawn((
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 13.0,
+                            color: Color::WHITE,
+                        },
+                    ),
+                    TimelineBranchText,
+                    ThemedText(ThemeColor::ButtonText),
+                ));
+            });
+            bar.spawn((
+                ButtonBundle {
+                    style: Style {
+                        flex_grow: 1.0,
+                        height: Val::Px(10.0),
+                        ..default()
+                    },
+                    focus_policy: FocusPolicy::Pass,
+                    ..default()
+                },
+                TimelineTrack,
+                RelativeCursorPosition::default(),
+                Themed(ThemeColor::ToolButton),
+            ))
+            .with_children(|track| {
+                track.spawn((
+                    NodeBundle {
+                        style: Style {
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    TimelineFill,
+                    Themed(ThemeColor::ToolSelected),
+                ));
+            });
+            bar.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 14.0,
+                        color: Color::BLACK,
+                    },
+                ),
+                TimelineText,
+                ThemedText(ThemeColor::Text),
+            ));
+        });
+}
+
+/// 记录每一代；拖动时间轴、按快捷键或切换分支时把网格换成历史上的那一代
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn timeline_system(
+    mut actions: EventReader<Action>,
+    track: Query<(&Interaction, &RelativeCursorPosition), With<TimelineTrack>>,
+    branch_button: Query<&Interaction, (Changed<Interaction>, With<TimelineBranchButton>)>,
+    mut timeline: ResMut<Timeline>,
+    mut cell_states: ResMut<CellStates>,
+    mut sim: ResMut<Simulation>,
+    mut history: ResMut<History>,
+    mut edit_history: ResMut<EditHistory>,
+    lenia: Res<Lenia>,
+    language: Res<Language>,
+    mut bar: Query<&mut Style, (With<TimelineBar>, Without<TimelineFill>)>,
+    mut fill: Query<&mut Style, (With<TimelineFill>, Without<TimelineBar>)>,
+    mut texts: Query<&mut Text, (With<TimelineText>, Without<TimelineBranchText>)>,
+    mut branch_texts: Query<&mut Text, (With<TimelineBranchText>, Without<TimelineText>)>,
+) {
+    let mut target = None;
+    let mut switch_branch = false;
+    for action in actions.read() {
+        match action {
+            Action::ToggleTimeline => {
+                timeline.visible = !timeline.visible;
+                for mut style in &mut bar {
+                    style.display = if timeline.visible {
+                        Display::Flex
+                    } else {
+                        Display::None
+                    };
+                }
+            }
+            Action::TimelineBack => target = Some(timeline.cursor.saturating_sub(1)),
+            Action::TimelineForward => target = Some(timeline.cursor + 1),
+            _ => {}
+        }
+    }
+    for (interaction, cursor) in &track {
+        if *interaction == Interaction::Pressed
+            && let Some(position) = cursor.normalized
+        {
+            let (first, last) = timeline.range();
+            let t = position.x.clamp(0.0, 1.0) as f64;
+            target = Some(first + ((last - first) as f64 * t).round() as u64);
+        }
+    }
+    if branch_button.iter().any(|i| *i == Interaction::Pressed) {
+        switch_branch = true;
+    }
+
+    // 连续模式不是逐代演化，不记录也不回放
+    if lenia.enabled {
+        return;
+    }
+    if timeline.branches.is_empty() {
+        timeline.record(sim.generation, &cell_states.states, &sim);
+    }
+
+    if switch_branch {
+        timeline.next_branch();
+        target = Some(target.unwrap_or(timeline.cursor));
+    }
+    match target {
+        Some(generation) if generation != timeline.cursor || switch_branch => {
+            let grid = timeline.seek(generation);
+            for (y, row) in grid.iter().enumerate() {
+                for (x, &state) in row.iter().enumerate() {
+                    if cell_states.states[y][x] != state {
+                        set_cell_state(&mut cell_states, x, y, state);
+                    }
+                }
+            }
+            let branch = timeline.branch(timeline.owner);
+            if !branch.same_world(&sim) {
+                sim.rule = branch.rule.clone();
+                sim.neighborhood = branch.neighborhood.clone();
+                sim.topology = branch.topology;
+            }
+            sim.generation = timeline.cursor;
+            // 会话保存的最近若干代也换成这一代之前的历史
+            let first = timeline.range().0;
+            let from = timeline
+                .cursor
+                .saturating_sub(HISTORY_LIMIT as u64)
+                .max(first);
+            history.generations = timeline.grids(from, timeline.cursor).into();
+            // 换了一张网格（切换分支时代数可能不变），之前的编辑不能再撤销
+            edit_history.clear();
+            info!("时间轴: 第 {} 代", timeline.cursor);
+        }
+        _ => {
+            if cell_states.is_changed() || sim.is_changed() {
+                timeline.record(sim.generation, &cell_states.states, &sim);
+            }
+        }
+    }
+
+    if !timeline.is_changed() && !language.is_changed() {
+        return;
+    }
+    let (first, last) = timeline.range();
+    let t = if last > first {
+        (timeline.cursor - first) as f32 / (last - first) as f32
+    } else {
+        1.0
+    };
+    for mut style in &mut fill {
+        style.width = Val::Percent(t * 100.0);
+    }
+    for mut text in &mut texts {
+        text.sections[0].value = language.trf(
+            "timeline.generation",
+            format!("{} / {}", timeline.cursor, last),
+        );
+    }
+    let index = timeline
+        .branches
+        .iter()
+        .position(|b| b.id == timeline.current)
+        .unwrap_or(0);
+    for mut text in &mut branch_texts {
+        text.sections[0].value = language.trf(
+            "timeline.branch",
+            format!("{}/{}", index + 1, timeline.branches.len()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ALIVE;
+
+    /// 空网格上、康威规则下的分支，共 keyframes 个关键帧
+    fn branch(id: u32, parent: Option<u32>, start: u64, keyframes: usize) -> Branch {
+        Branch {
+            id,
+            parent,
+            start,
+            end: start + (keyframes as u64 - 1) * KEYFRAME_EVERY,
+            keyframes: vec![[[DEAD; GRID_SIZE]; GRID_SIZE]; keyframes],
+            rule: Rule::default(),
+            neighborhood: Neighborhood::default(),
+            topology: Topology::Bounded,
+        }
+    }
+
+    /// 第 fork 代从根分支分出子分支，当前位于子分支末尾，然后裁剪
+    fn trimmed(root_keyframes: usize, fork: u64, child_keyframes: usize) -> Timeline {
+        let child = branch(1, Some(0), fork, child_keyframes);
+        let mut timeline = Timeline {
+            cursor: child.end,
+            current: 1,
+            owner: 1,
+            next_id: 2,
+            ..default()
+        };
+        timeline.branches = vec![branch(0, None, 0, root_keyframes), child];
+        timeline.trim();
+        timeline
+    }
+
+    /// 网格中央的闪烁器，横向或竖向
+    fn blinker(across: bool) -> Grid {
+        let mut grid = [[DEAD; GRID_SIZE]; GRID_SIZE];
+        let c = GRID_SIZE / 2;
+        for d in [c - 1, c, c + 1] {
+            let (x, y) = if across { (d, c) } else { (c, d) };
+            grid[y][x] = ALIVE;
+        }
+        grid
+    }
+
+    fn reachable(timeline: &Timeline) -> bool {
+        let (first, last) = timeline.range();
+        (first..=last).all(|g| timeline.owner_of(timeline.current, g).is_some())
+    }
+
+    #[test]
+    fn trim_drops_keyframes_past_fork() {
+        let timeline = trimmed(101, 800, 4000);
+        let root = timeline.branch(0);
+        assert_eq!((root.start, root.end, root.keyframes.len()), (0, 799, 50));
+        assert_eq!(timeline.range().0, 0);
+        assert!(reachable(&timeline));
+    }
+
+    #[test]
+    fn trim_keeps_current_branch_reachable() {
+        // 根分支在分叉点之前的关键帧删完后整个删掉，子分支的每一代仍可到达
+        let timeline = trimmed(101, 40, MAX_KEYFRAMES);
+        assert_eq!(timeline.branches.len(), 1);
+        assert_eq!(timeline.range().0, 40);
+        assert!(reachable(&timeline));
+    }
+
+    #[test]
+    fn record_step_edit_and_fork() {
+        let sim = Simulation::default();
+        let mut timeline = Timeline::default();
+        timeline.record(0, &blinker(true), &sim);
+        timeline.record(1, &blinker(false), &sim);
+        timeline.record(2, &blinker(true), &sim);
+        assert_eq!(timeline.branches.len(), 1);
+        assert_eq!(timeline.range(), (0, 2));
+
+        // 原地编辑：从第 2 代分出子分支
+        let mut edited = blinker(true);
+        edited[0][0] = ALIVE;
+        timeline.record(2, &edited, &sim);
+        assert_eq!(timeline.branches.len(), 2);
+        let fork = timeline.branch(timeline.current);
+        assert_eq!((fork.parent, fork.start), (Some(0), 2));
+
+        // 同一帧里演化后又编辑：仍接在当前历史上，而不是新的根
+        let mut stepped = fork.world().step(&edited);
+        stepped[GRID_SIZE - 1][GRID_SIZE - 1] = ALIVE;
+        timeline.record(3, &stepped, &sim);
+        let id = timeline.current;
+        assert_eq!(timeline.branches.len(), 3);
+        assert_eq!(
+            (timeline.branch(id).parent, timeline.branch(id).start),
+            (Some(1), 3)
+        );
+        assert_eq!(timeline.range(), (0, 3));
+        assert!(reachable(&timeline));
+
+        // 跳回分叉点之前，再回到末尾
+        assert_eq!(timeline.seek(1), blinker(false));
+        assert_eq!(timeline.owner, 0);
+        assert_eq!(timeline.seek(2), edited);
+        assert_eq!(timeline.owner, 1);
+        assert_eq!(timeline.seek(3), stepped);
+        assert_eq!(timeline.owner, id);
+
+        // 撤销回到历史上已有的一代不会分出新分支
+        timeline.record(1, &blinker(false), &sim);
+        assert_eq!((timeline.branches.len(), timeline.owner), (3, 0));
+
+        // 与历史无关的状态开始新的历史
+        timeline.record(0, &[[DEAD; GRID_SIZE]; GRID_SIZE], &sim);
+        assert_eq!(timeline.branches.len(), 4);
+        assert_eq!(timeline.branch(timeline.current).parent, None);
+    }
+
+    #[test]
+    fn next_branch_cycles() {
+        let sim = Simulation::default();
+        let mut timeline = Timeline::default();
+        timeline.record(0, &blinker(true), &sim);
+        timeline.record(1, &blinker(false), &sim);
+        timeline.record(1, &blinker(true), &sim);
+        assert_eq!((timeline.current, timeline.range()), (1, (0, 1)));
+
+        // 切到原来的分支后，同一代的网格换成那条历史上的
+        timeline.next_branch();
+        assert_eq!(timeline.current, 0);
+        assert_eq!(timeline.seek(timeline.cursor), blinker(false));
+        timeline.next_branch();
+        assert_eq!(timeline.current, 1);
+        assert_eq!(timeline.seek(timeline.cursor), blinker(true));
+    }
+}